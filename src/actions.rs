@@ -1,20 +1,46 @@
+use std::marker::PhantomData;
+
+use crate::components::map_position::MapPosition;
+use crate::config::{AxisDirection, BindingSettings, InputBinding, Settings};
 use crate::GameState;
-use bevy::{prelude::*, render::camera::RenderTarget};
+use bevy::{
+    ecs::system::SystemParam, input::mouse::MouseWheel, prelude::*, render::camera::RenderTarget,
+    utils::HashSet,
+};
+use bevy_egui::EguiContext;
 
-/// This plugin listens for keyboard input and converts the input into Actions
+/// This plugin listens for keyboard, mouse and gamepad input and converts the input into Actions
 /// Actions can then be used as a resource in other systems to act on the player input.
 pub struct ActionsPlugin;
 
+/// Label for reading the bound inputs, before they are turned into actions
+const CONTROLS_LABEL: &str = "ReadControls";
+
+/// Label for reading a count, before its digits can be taken as other controls
+const COUNT_LABEL: &str = "ReadCount";
+
+/// Label for the systems that set [`Actions`] from the player's input
+pub const ACTIONS_LABEL: &str = "SetActions";
+
 impl Plugin for ActionsPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Actions>().add_system_set(
-            SystemSet::on_update(GameState::Playing)
-                .with_system(set_movement_actions)
-                .with_system(cursor_system)
-                .with_system(set_item_pick_up)
-                .with_system(set_interact)
-                .with_system(use_item),
-        );
+        app.init_resource::<Actions>()
+            .init_resource::<ControlState>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(update_control_state.label(CONTROLS_LABEL))
+                    .with_system(set_count.label(COUNT_LABEL).after(CONTROLS_LABEL))
+                    .with_system(set_movement_actions.label(ACTIONS_LABEL).after(COUNT_LABEL))
+                    .with_system(cursor_system.label(ACTIONS_LABEL))
+                    .with_system(set_item_pick_up.label(ACTIONS_LABEL).after(COUNT_LABEL))
+                    .with_system(set_interact.label(ACTIONS_LABEL).after(COUNT_LABEL))
+                    .with_system(set_explore.label(ACTIONS_LABEL).after(COUNT_LABEL))
+                    .with_system(set_wait.label(ACTIONS_LABEL).after(COUNT_LABEL))
+                    .with_system(set_rest.label(ACTIONS_LABEL).after(COUNT_LABEL))
+                    .with_system(set_zoom.label(ACTIONS_LABEL).after(COUNT_LABEL))
+                    .with_system(set_look.label(ACTIONS_LABEL).after(COUNT_LABEL))
+                    .with_system(use_item.label(ACTIONS_LABEL).after(COUNT_LABEL)),
+            );
     }
 }
 
@@ -29,8 +55,22 @@ pub struct Actions {
     pub pick_up_item: Option<bool>,
     /// Interact
     pub interact: Option<bool>,
+    /// Explore the level automatically
+    pub explore: Option<bool>,
+    /// Skip a turn
+    pub wait: Option<bool>,
+    /// Wait until healed or interrupted
+    pub rest: Option<bool>,
+    /// Times to repeat the next action, kept until an action takes it
+    pub count: Option<u32>,
     /// Use an item in inventory
     pub use_item: Option<usize>,
+    /// Tile clicked on in the game
+    pub clicked_tile: Option<MapPosition>,
+    /// Zoom steps, positive to zoom in
+    pub zoom: Option<i32>,
+    /// Start or stop looking around
+    pub look: Option<bool>,
 }
 
 /// Position of the mouse cursor
@@ -49,6 +89,9 @@ fn cursor_system(
     wnds: Res<Windows>,
     // query to get camera transform
     q_camera: Query<(&Camera, &GlobalTransform)>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut egui_context: ResMut<EguiContext>,
+    settings: Res<Settings>,
 ) {
     // get the camera info and transform
     // assuming there is exactly one main camera entity, so query::single() is OK
@@ -78,63 +121,62 @@ fn cursor_system(
         // reduce it to a 2D value
         let world_pos: Vec2 = world_pos.truncate();
 
+        // Clicks on the HUD are for the HUD
+        let clicked = mouse_input.just_pressed(MouseButton::Left)
+            && !egui_context.ctx_mut().is_pointer_over_area();
+        actions.clicked_tile =
+            clicked.then(|| MapPosition::from_translation(world_pos, settings.tile_size));
+
         actions.mouse_rollover = Some(MousePosition {
             game_position: world_pos,
             screen_position: screen_pos,
         });
     } else {
         actions.mouse_rollover = None;
+        actions.clicked_tile = None;
     }
 }
 
-/// From keyboard input turn into player movement
-fn set_movement_actions(
-    mut actions: ResMut<Actions>,
-    mut mut_keyboard_input: ResMut<Input<KeyCode>>,
-) {
-    let keyboard_input = mut_keyboard_input.as_ref();
-    if GameControl::Up.just_released(keyboard_input)
-        || GameControl::Up.just_pressed(keyboard_input)
-        || GameControl::Left.just_released(keyboard_input)
-        || GameControl::Left.just_pressed(keyboard_input)
-        || GameControl::Down.just_released(keyboard_input)
-        || GameControl::Down.just_pressed(keyboard_input)
-        || GameControl::Right.just_released(keyboard_input)
-        || GameControl::Right.just_pressed(keyboard_input)
+/// From the bound inputs turn into player movement
+fn set_movement_actions(mut actions: ResMut<Actions>, mut controls: ResMut<ControlState>) {
+    if controls.just_released(GameControl::Up)
+        || controls.just_pressed(GameControl::Up)
+        || controls.just_released(GameControl::Left)
+        || controls.just_pressed(GameControl::Left)
+        || controls.just_released(GameControl::Down)
+        || controls.just_pressed(GameControl::Down)
+        || controls.just_released(GameControl::Right)
+        || controls.just_pressed(GameControl::Right)
     {
         let mut player_movement = Vec2::ZERO;
 
-        if GameControl::Up.just_released(keyboard_input)
-            || GameControl::Down.just_released(keyboard_input)
-        {
-            if GameControl::Up.pressed(keyboard_input) {
+        if controls.just_released(GameControl::Up) || controls.just_released(GameControl::Down) {
+            if controls.pressed(GameControl::Up) {
                 player_movement.y = 1.;
-            } else if GameControl::Down.pressed(keyboard_input) {
+            } else if controls.pressed(GameControl::Down) {
                 player_movement.y = -1.;
             } else {
                 player_movement.y = 0.;
             }
-        } else if GameControl::Up.just_pressed(keyboard_input) {
+        } else if controls.just_pressed(GameControl::Up) {
             player_movement.y = 1.;
-        } else if GameControl::Down.just_pressed(keyboard_input) {
+        } else if controls.just_pressed(GameControl::Down) {
             player_movement.y = -1.;
         } else {
             player_movement.y = actions.player_movement.unwrap_or(Vec2::ZERO).y;
         }
 
-        if GameControl::Right.just_released(keyboard_input)
-            || GameControl::Left.just_released(keyboard_input)
-        {
-            if GameControl::Right.pressed(keyboard_input) {
+        if controls.just_released(GameControl::Right) || controls.just_released(GameControl::Left) {
+            if controls.pressed(GameControl::Right) {
                 player_movement.x = 1.;
-            } else if GameControl::Left.pressed(keyboard_input) {
+            } else if controls.pressed(GameControl::Left) {
                 player_movement.x = -1.;
             } else {
                 player_movement.x = 0.;
             }
-        } else if GameControl::Right.just_pressed(keyboard_input) {
+        } else if controls.just_pressed(GameControl::Right) {
             player_movement.x = 1.;
-        } else if GameControl::Left.just_pressed(keyboard_input) {
+        } else if controls.just_pressed(GameControl::Left) {
             player_movement.x = -1.;
         } else {
             player_movement.x = actions.player_movement.unwrap_or(Vec2::ZERO).x;
@@ -142,65 +184,166 @@ fn set_movement_actions(
 
         if player_movement != Vec2::ZERO {
             player_movement = player_movement.normalize();
-            info!("Keyboard input made player movement: {}", player_movement);
+            info!("Input made player movement: {}", player_movement);
             actions.player_movement = Some(player_movement);
-            mut_keyboard_input.clear();
+            controls.clear();
         }
     } else {
         actions.player_movement = None;
     }
 }
 
-/// From keyboard input turn into item pick up
-fn set_item_pick_up(mut actions: ResMut<Actions>, mut mut_keyboard_input: ResMut<Input<KeyCode>>) {
-    let keyboard_input = mut_keyboard_input.as_ref();
-    if GameControl::PickUp.just_released(keyboard_input)
-        || GameControl::PickUp.just_pressed(keyboard_input)
-    {
+/// From the bound inputs turn into item pick up
+fn set_item_pick_up(mut actions: ResMut<Actions>, mut controls: ResMut<ControlState>) {
+    if controls.just_released(GameControl::PickUp) || controls.just_pressed(GameControl::PickUp) {
         actions.pick_up_item = Some(true);
-        info!("Keyboard input made player pick up");
-        mut_keyboard_input.clear();
+        info!("Input made player pick up");
+        controls.clear();
     } else {
         actions.pick_up_item = None;
     }
 }
 
-/// From keyboard input turn into item pick up
-fn set_interact(mut actions: ResMut<Actions>, mut mut_keyboard_input: ResMut<Input<KeyCode>>) {
-    let keyboard_input = mut_keyboard_input.as_ref();
-    if GameControl::Interact.just_released(keyboard_input)
-        || GameControl::Interact.just_pressed(keyboard_input)
+/// From the bound inputs turn into interact
+fn set_interact(mut actions: ResMut<Actions>, mut controls: ResMut<ControlState>) {
+    if controls.just_released(GameControl::Interact) || controls.just_pressed(GameControl::Interact)
     {
         actions.interact = Some(true);
-        info!("Keyboard input made player interact");
-        mut_keyboard_input.clear();
+        info!("Input made player interact");
+        controls.clear();
     } else {
         actions.interact = None;
     }
 }
 
-/// From keyboard input turn into player inventory choice
-fn use_item(mut actions: ResMut<Actions>, mut mut_keyboard_input: ResMut<Input<KeyCode>>) {
-    let keyboard_input = mut_keyboard_input.as_ref();
+/// From the bound inputs turn into auto explore
+fn set_explore(mut actions: ResMut<Actions>, mut controls: ResMut<ControlState>) {
+    if controls.just_released(GameControl::Explore) || controls.just_pressed(GameControl::Explore) {
+        actions.explore = Some(true);
+        info!("Input made player explore");
+        controls.clear();
+    } else {
+        actions.explore = None;
+    }
+}
+
+/// From the bound inputs turn into waiting a turn
+fn set_wait(mut actions: ResMut<Actions>, mut controls: ResMut<ControlState>) {
+    if controls.just_released(GameControl::Wait) || controls.just_pressed(GameControl::Wait) {
+        actions.wait = Some(true);
+        info!("Input made player wait");
+        controls.clear();
+    } else {
+        actions.wait = None;
+    }
+}
+
+/// From the bound inputs turn into resting
+fn set_rest(mut actions: ResMut<Actions>, mut controls: ResMut<ControlState>) {
+    if controls.just_released(GameControl::Rest) || controls.just_pressed(GameControl::Rest) {
+        actions.rest = Some(true);
+        info!("Input made player rest");
+        controls.clear();
+    } else {
+        actions.rest = None;
+    }
+}
+
+/// Most times an action can be repeated
+pub const MAX_COUNT: u32 = 999;
+
+/// From the bound inputs start a count, then read the digits typed after it
+/// instead of letting them use items
+fn set_count(
+    mut actions: ResMut<Actions>,
+    mut controls: ResMut<ControlState>,
+    settings: Res<Settings>,
+) {
+    if controls.just_pressed(GameControl::Count) {
+        // Pressing it again drops the count
+        actions.count = match actions.count {
+            Some(_) => None,
+            None => Some(0),
+        };
+        info!("Input made count {:?}", actions.count);
+        controls.clear();
+        return;
+    }
+    let Some(count) = actions.count else {
+        return;
+    };
+    let digits = (0..settings.bindings.digits.len()).map(GameControl::Digit);
+    if let Some(digit) = digits
+        .clone()
+        .position(|digit| controls.just_pressed(digit))
+    {
+        actions.count = Some((count * 10 + digit as u32).min(MAX_COUNT));
+        info!("Input made count {:?}", actions.count);
+        controls.clear();
+    } else if digits
+        .into_iter()
+        .any(|digit| controls.just_released(digit))
+    {
+        controls.clear();
+    }
+}
+
+/// From the bound inputs turn into player inventory choice
+fn use_item(
+    mut actions: ResMut<Actions>,
+    mut controls: ResMut<ControlState>,
+    settings: Res<Settings>,
+) {
     let mut used = false;
-    (0..9).for_each(|n| {
-        if GameControl::UseItem(n).just_released(keyboard_input)
-            || GameControl::UseItem(n).just_pressed(keyboard_input)
+    (0..settings.bindings.use_item.len()).for_each(|n| {
+        if controls.just_released(GameControl::UseItem(n))
+            || controls.just_pressed(GameControl::UseItem(n))
         {
             actions.use_item = Some(n);
-            info!("Keyboard input made player use item {}", n);
+            info!("Input made player use item {}", n);
             used = true;
         }
     });
     if used {
-        mut_keyboard_input.clear();
+        controls.clear();
     } else {
         actions.use_item = None;
     }
 }
 
+/// From the bound inputs and the mouse wheel turn into zooming
+fn set_zoom(
+    mut actions: ResMut<Actions>,
+    controls: Res<ControlState>,
+    mut wheel: EventReader<MouseWheel>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    // The wheel scrolls the HUD when over it
+    let over_hud = egui_context.ctx_mut().is_pointer_over_area();
+    let scrolled = wheel
+        .iter()
+        .filter(|_| !over_hud)
+        .map(|event| event.y.signum() as i32)
+        .sum::<i32>();
+    let pressed = controls.just_pressed(GameControl::ZoomIn) as i32
+        - controls.just_pressed(GameControl::ZoomOut) as i32;
+    let zoom = scrolled + pressed;
+    actions.zoom = (zoom != 0).then_some(zoom);
+}
+
+/// From the bound inputs turn into looking around
+fn set_look(mut actions: ResMut<Actions>, mut controls: ResMut<ControlState>) {
+    if controls.just_pressed(GameControl::Look) {
+        actions.look = Some(true);
+        controls.clear();
+    } else {
+        actions.look = None;
+    }
+}
+
 /// Possible Player actions
-enum GameControl {
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameControl {
     /// Move up
     Up,
     /// Move down
@@ -213,108 +356,267 @@ enum GameControl {
     PickUp,
     /// Interact button to cover multiple options
     Interact,
+    /// Walk towards unexplored tiles until something happens
+    Explore,
+    /// Skip a turn
+    Wait,
+    /// Wait until healed or something happens
+    Rest,
+    /// Repeat the next action by the number typed after this
+    Count,
+    /// Digit typed into a count
+    Digit(usize),
     /// Use Item
     UseItem(usize),
+    ZoomIn,
+    ZoomOut,
+    /// Move a cursor around instead of the player
+    Look,
 }
 
 impl GameControl {
-    /// Convert keyboard input to game control on released
-    fn just_released(&self, keyboard_input: &Input<KeyCode>) -> bool {
+    /// Every control that has bindings
+    pub fn all(bindings: &BindingSettings) -> Vec<GameControl> {
+        [
+            GameControl::Up,
+            GameControl::Down,
+            GameControl::Left,
+            GameControl::Right,
+            GameControl::PickUp,
+            GameControl::Interact,
+            GameControl::Explore,
+            GameControl::Wait,
+            GameControl::Rest,
+            GameControl::Count,
+            GameControl::ZoomIn,
+            GameControl::ZoomOut,
+            GameControl::Look,
+        ]
+        .into_iter()
+        .chain((0..bindings.digits.len()).map(GameControl::Digit))
+        .chain((0..bindings.use_item.len()).map(GameControl::UseItem))
+        .collect()
+    }
+
+    /// Name shown on the rebinding screen
+    pub fn label(&self) -> String {
         match self {
-            GameControl::Up => {
-                keyboard_input.just_released(KeyCode::W)
-                    || keyboard_input.just_released(KeyCode::Up)
-            }
-            GameControl::Down => {
-                keyboard_input.just_released(KeyCode::S)
-                    || keyboard_input.just_released(KeyCode::Down)
-            }
-            GameControl::Left => {
-                keyboard_input.just_released(KeyCode::A)
-                    || keyboard_input.just_released(KeyCode::Left)
-            }
-            GameControl::Right => {
-                keyboard_input.just_released(KeyCode::D)
-                    || keyboard_input.just_released(KeyCode::Right)
-            }
-            GameControl::PickUp => keyboard_input.just_released(KeyCode::G),
-            GameControl::Interact => keyboard_input.just_released(KeyCode::E),
-            GameControl::UseItem(0) => keyboard_input.just_released(KeyCode::Key0),
-            GameControl::UseItem(1) => keyboard_input.just_released(KeyCode::Key1),
-            GameControl::UseItem(2) => keyboard_input.just_released(KeyCode::Key2),
-            GameControl::UseItem(3) => keyboard_input.just_released(KeyCode::Key3),
-            GameControl::UseItem(4) => keyboard_input.just_released(KeyCode::Key4),
-            GameControl::UseItem(5) => keyboard_input.just_released(KeyCode::Key5),
-            GameControl::UseItem(6) => keyboard_input.just_released(KeyCode::Key6),
-            GameControl::UseItem(7) => keyboard_input.just_released(KeyCode::Key7),
-            GameControl::UseItem(8) => keyboard_input.just_released(KeyCode::Key8),
-            GameControl::UseItem(9) => keyboard_input.just_released(KeyCode::Key9),
-            GameControl::UseItem(_) => false,
+            GameControl::PickUp => "Pick up".to_string(),
+            GameControl::Digit(n) => format!("Count digit {}", n),
+            GameControl::UseItem(n) => format!("Use item {}", n),
+            GameControl::ZoomIn => "Zoom in".to_string(),
+            GameControl::ZoomOut => "Zoom out".to_string(),
+            _ => format!("{:?}", self),
         }
     }
 
-    /// Convert keyboard input to game control on pressed
-    fn pressed(&self, keyboard_input: &Input<KeyCode>) -> bool {
+    /// Inputs bound to the control
+    pub fn bindings<'a>(&self, bindings: &'a BindingSettings) -> &'a [InputBinding] {
         match self {
-            GameControl::Up => {
-                keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up)
-            }
-            GameControl::Down => {
-                keyboard_input.pressed(KeyCode::S) || keyboard_input.pressed(KeyCode::Down)
-            }
-            GameControl::Left => {
-                keyboard_input.pressed(KeyCode::A) || keyboard_input.pressed(KeyCode::Left)
-            }
-            GameControl::Right => {
-                keyboard_input.pressed(KeyCode::D) || keyboard_input.pressed(KeyCode::Right)
-            }
-            GameControl::PickUp => keyboard_input.pressed(KeyCode::G),
-            GameControl::Interact => keyboard_input.pressed(KeyCode::E),
-            GameControl::UseItem(0) => keyboard_input.pressed(KeyCode::Key0),
-            GameControl::UseItem(1) => keyboard_input.pressed(KeyCode::Key1),
-            GameControl::UseItem(2) => keyboard_input.pressed(KeyCode::Key2),
-            GameControl::UseItem(3) => keyboard_input.pressed(KeyCode::Key3),
-            GameControl::UseItem(4) => keyboard_input.pressed(KeyCode::Key4),
-            GameControl::UseItem(5) => keyboard_input.pressed(KeyCode::Key5),
-            GameControl::UseItem(6) => keyboard_input.pressed(KeyCode::Key6),
-            GameControl::UseItem(7) => keyboard_input.pressed(KeyCode::Key7),
-            GameControl::UseItem(8) => keyboard_input.pressed(KeyCode::Key8),
-            GameControl::UseItem(9) => keyboard_input.pressed(KeyCode::Key9),
-            GameControl::UseItem(_) => false,
+            GameControl::Up => &bindings.up,
+            GameControl::Down => &bindings.down,
+            GameControl::Left => &bindings.left,
+            GameControl::Right => &bindings.right,
+            GameControl::PickUp => &bindings.pick_up,
+            GameControl::Interact => &bindings.interact,
+            GameControl::Explore => &bindings.explore,
+            GameControl::Wait => &bindings.wait,
+            GameControl::Rest => &bindings.rest,
+            GameControl::Count => &bindings.count,
+            GameControl::Digit(n) => bindings.digits.get(*n).map_or(&[], Vec::as_slice),
+            GameControl::UseItem(n) => bindings.use_item.get(*n).map_or(&[], Vec::as_slice),
+            GameControl::ZoomIn => &bindings.zoom_in,
+            GameControl::ZoomOut => &bindings.zoom_out,
+            GameControl::Look => &bindings.look,
         }
     }
 
-    /// Convert keyboard input to game control on just pressed
-    fn just_pressed(&self, keyboard_input: &Input<KeyCode>) -> bool {
+    /// Inputs bound to the control, to rebind
+    pub fn bindings_mut<'a>(
+        &self,
+        bindings: &'a mut BindingSettings,
+    ) -> Option<&'a mut Vec<InputBinding>> {
         match self {
-            GameControl::Up => {
-                keyboard_input.just_pressed(KeyCode::W) || keyboard_input.just_pressed(KeyCode::Up)
-            }
-            GameControl::Down => {
-                keyboard_input.just_pressed(KeyCode::S)
-                    || keyboard_input.just_pressed(KeyCode::Down)
-            }
-            GameControl::Left => {
-                keyboard_input.just_pressed(KeyCode::A)
-                    || keyboard_input.just_pressed(KeyCode::Left)
-            }
-            GameControl::Right => {
-                keyboard_input.just_pressed(KeyCode::D)
-                    || keyboard_input.just_pressed(KeyCode::Right)
-            }
-            GameControl::PickUp => keyboard_input.just_pressed(KeyCode::G),
-            GameControl::Interact => keyboard_input.just_pressed(KeyCode::E),
-            GameControl::UseItem(0) => keyboard_input.just_pressed(KeyCode::Key0),
-            GameControl::UseItem(1) => keyboard_input.just_pressed(KeyCode::Key1),
-            GameControl::UseItem(2) => keyboard_input.just_pressed(KeyCode::Key2),
-            GameControl::UseItem(3) => keyboard_input.just_pressed(KeyCode::Key3),
-            GameControl::UseItem(4) => keyboard_input.just_pressed(KeyCode::Key4),
-            GameControl::UseItem(5) => keyboard_input.just_pressed(KeyCode::Key5),
-            GameControl::UseItem(6) => keyboard_input.just_pressed(KeyCode::Key6),
-            GameControl::UseItem(7) => keyboard_input.just_pressed(KeyCode::Key7),
-            GameControl::UseItem(8) => keyboard_input.just_pressed(KeyCode::Key8),
-            GameControl::UseItem(9) => keyboard_input.just_pressed(KeyCode::Key9),
-            GameControl::UseItem(_) => false,
+            GameControl::Up => Some(&mut bindings.up),
+            GameControl::Down => Some(&mut bindings.down),
+            GameControl::Left => Some(&mut bindings.left),
+            GameControl::Right => Some(&mut bindings.right),
+            GameControl::PickUp => Some(&mut bindings.pick_up),
+            GameControl::Interact => Some(&mut bindings.interact),
+            GameControl::Explore => Some(&mut bindings.explore),
+            GameControl::Wait => Some(&mut bindings.wait),
+            GameControl::Rest => Some(&mut bindings.rest),
+            GameControl::Count => Some(&mut bindings.count),
+            GameControl::Digit(n) => bindings.digits.get_mut(*n),
+            GameControl::UseItem(n) => bindings.use_item.get_mut(*n),
+            GameControl::ZoomIn => Some(&mut bindings.zoom_in),
+            GameControl::ZoomOut => Some(&mut bindings.zoom_out),
+            GameControl::Look => Some(&mut bindings.look),
+        }
+    }
+}
+
+/// How far a stick has to be pushed along an axis to count as pressed
+const AXIS_THRESHOLD: f32 = 0.5;
+
+/// Every device a binding can come from
+#[derive(SystemParam)]
+pub struct InputSources<'w, 's> {
+    keys: Res<'w, Input<KeyCode>>,
+    mouse: Res<'w, Input<MouseButton>>,
+    buttons: Res<'w, Input<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    gamepads: Res<'w, Gamepads>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl InputSources<'_, '_> {
+    fn pressed(&self, binding: &InputBinding) -> bool {
+        match *binding {
+            InputBinding::Key(key) => self.keys.pressed(key),
+            InputBinding::Mouse(button) => self.mouse.pressed(button),
+            InputBinding::GamepadButton(button) => self
+                .gamepads
+                .iter()
+                .any(|g| self.buttons.pressed(GamepadButton::new(g, button))),
+            InputBinding::GamepadAxis(axis, direction) => self.gamepads.iter().any(|g| {
+                let value = self.axes.get(GamepadAxis::new(g, axis)).unwrap_or(0.0);
+                match direction {
+                    AxisDirection::Positive => value > AXIS_THRESHOLD,
+                    AxisDirection::Negative => value < -AXIS_THRESHOLD,
+                }
+            }),
+        }
+    }
+
+    /// Sticks have no just pressed of their own, `None` for those
+    fn just_pressed(&self, binding: &InputBinding) -> Option<bool> {
+        match *binding {
+            InputBinding::Key(key) => Some(self.keys.just_pressed(key)),
+            InputBinding::Mouse(button) => Some(self.mouse.just_pressed(button)),
+            InputBinding::GamepadButton(button) => Some(
+                self.gamepads
+                    .iter()
+                    .any(|g| self.buttons.just_pressed(GamepadButton::new(g, button))),
+            ),
+            InputBinding::GamepadAxis(..) => None,
         }
     }
+
+    fn just_released(&self, binding: &InputBinding) -> Option<bool> {
+        match *binding {
+            InputBinding::Key(key) => Some(self.keys.just_released(key)),
+            InputBinding::Mouse(button) => Some(self.mouse.just_released(button)),
+            InputBinding::GamepadButton(button) => Some(
+                self.gamepads
+                    .iter()
+                    .any(|g| self.buttons.just_released(GamepadButton::new(g, button))),
+            ),
+            InputBinding::GamepadAxis(..) => None,
+        }
+    }
+
+    /// Any input that was just pressed, for binding it to a control
+    pub fn any_just_pressed(&self) -> Option<InputBinding> {
+        let key = self
+            .keys
+            .get_just_pressed()
+            .next()
+            .map(|k| InputBinding::Key(*k));
+        let mouse = || {
+            self.mouse
+                .get_just_pressed()
+                .next()
+                .map(|b| InputBinding::Mouse(*b))
+        };
+        let button = || {
+            self.buttons
+                .get_just_pressed()
+                .next()
+                .map(|b| InputBinding::GamepadButton(b.button_type))
+        };
+        let axis = || {
+            [
+                GamepadAxisType::LeftStickX,
+                GamepadAxisType::LeftStickY,
+                GamepadAxisType::RightStickX,
+                GamepadAxisType::RightStickY,
+            ]
+            .into_iter()
+            .flat_map(|a| {
+                [AxisDirection::Positive, AxisDirection::Negative]
+                    .map(|d| InputBinding::GamepadAxis(a, d))
+            })
+            .find(|b| self.pressed(b))
+        };
+        key.or_else(mouse).or_else(button).or_else(axis)
+    }
+}
+
+/// State of each control this frame, from every input bound to it
+#[derive(Resource, Default, Debug)]
+pub struct ControlState {
+    pressed: HashSet<GameControl>,
+    just_pressed: HashSet<GameControl>,
+    just_released: HashSet<GameControl>,
+    /// Stick directions held last frame, to tell when they are pushed or let go
+    held_axes: HashSet<InputBinding>,
+}
+
+impl ControlState {
+    fn update(&mut self, bindings: &BindingSettings, inputs: &InputSources) {
+        let mut held_axes = HashSet::new();
+        self.pressed.clear();
+        self.just_pressed.clear();
+        self.just_released.clear();
+        GameControl::all(bindings).into_iter().for_each(|control| {
+            control.bindings(bindings).iter().for_each(|binding| {
+                let pressed = inputs.pressed(binding);
+                let was_held = self.held_axes.contains(binding);
+                if pressed && matches!(binding, InputBinding::GamepadAxis(..)) {
+                    held_axes.insert(*binding);
+                }
+                if pressed {
+                    self.pressed.insert(control);
+                }
+                if inputs.just_pressed(binding).unwrap_or(pressed && !was_held) {
+                    self.just_pressed.insert(control);
+                }
+                if inputs
+                    .just_released(binding)
+                    .unwrap_or(!pressed && was_held)
+                {
+                    self.just_released.insert(control);
+                }
+            });
+        });
+        self.held_axes = held_axes;
+    }
+
+    fn pressed(&self, control: GameControl) -> bool {
+        self.pressed.contains(&control)
+    }
+
+    fn just_pressed(&self, control: GameControl) -> bool {
+        self.just_pressed.contains(&control)
+    }
+
+    fn just_released(&self, control: GameControl) -> bool {
+        self.just_released.contains(&control)
+    }
+
+    /// Consume this frame's presses so only one action is taken
+    fn clear(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+    }
+}
+
+fn update_control_state(
+    mut controls: ResMut<ControlState>,
+    settings: Res<Settings>,
+    inputs: InputSources,
+) {
+    controls.update(&settings.bindings, &inputs);
 }
@@ -0,0 +1,374 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use iyes_loopless::prelude::IntoConditionalSystem;
+
+use crate::{
+    actions::Actions,
+    cleanup::cleanup_components,
+    components::map_position::MapPosition,
+    config::Settings,
+    entities::{Player, Tile},
+    loading::FontAssets,
+    sprites::PLACE_SPRITES_LABEL,
+    stages::TurnState,
+    systems::{
+        combat::{DamageDealt, WantsToAttack},
+        travel::TRAVEL_LABEL,
+    },
+    GameState,
+};
+
+/// Seconds a sprite takes to slide to the next tile
+const MOVE_SECONDS: f32 = 0.12;
+
+/// Seconds something hit shakes and flashes for
+const HURT_SECONDS: f32 = 0.25;
+
+/// Share of a tile something hit shakes by
+const SHAKE_TILES: f32 = 0.1;
+
+/// Shakes back and forth per second
+const SHAKE_RATE: f32 = 40.0;
+
+/// Seconds a damage number floats up for
+const FLOAT_SECONDS: f32 = 0.8;
+
+/// Label for starting the moves, before they are played
+const START_MOVES_LABEL: &str = "StartMoves";
+
+/// Plugin for easing sprites between tiles and showing the hits. The rules
+/// still move everything at once, only what is drawn lags behind
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        // The moves are made in the turn's stages, so they are picked up
+        // before the transforms are drawn
+        app.add_system_to_stage(
+            CoreStage::PostUpdate,
+            start_moves
+                .label(START_MOVES_LABEL)
+                .after(PLACE_SPRITES_LABEL)
+                .before(TransformSystem::TransformPropagate),
+        )
+        .add_system_to_stage(CoreStage::PostUpdate, hurt_victims.label(START_MOVES_LABEL))
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            play_animations
+                .after(START_MOVES_LABEL)
+                .before(TransformSystem::TransformPropagate),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(
+                    skip_animations
+                        .run_if_resource_equals(TurnState::AwaitingInput)
+                        .after(TRAVEL_LABEL),
+                )
+                .with_system(spawn_damage_numbers)
+                .with_system(float_damage_numbers),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Playing).with_system(cleanup_components::<FloatingText>),
+        );
+    }
+}
+
+/// Where an entity is drawn and what it is doing
+#[derive(Component, Debug)]
+struct Animated {
+    /// Where it is drawn, not counting any shake
+    drawn: Vec3,
+    tween: Option<Tween>,
+    /// Seconds since it was hit
+    hurt: Option<f32>,
+    /// Sprite colour from before it was hit, put back once the flash ends
+    unhurt_color: Option<Color>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Tween {
+    from: Vec3,
+    to: Vec3,
+    elapsed: f32,
+}
+
+impl Tween {
+    /// Eased in and out, at `to` once done
+    fn at(&self) -> Vec3 {
+        let t = (self.elapsed / MOVE_SECONDS).clamp(0.0, 1.0);
+        self.from.lerp(self.to, t * t * (3.0 - 2.0 * t))
+    }
+
+    fn done(&self) -> bool {
+        self.elapsed >= MOVE_SECONDS
+    }
+}
+
+/// Side to side, dying down over the time something is hurt
+fn shake(elapsed: f32, tile_size: f32) -> Vec3 {
+    let left = (1.0 - elapsed / HURT_SECONDS).max(0.0);
+    Vec3::X * (elapsed * SHAKE_RATE).sin() * SHAKE_TILES * tile_size * left
+}
+
+/// A damage number rising and fading away
+#[derive(Component, Default)]
+struct FloatingText {
+    elapsed: f32,
+}
+
+/// Slide what moved a tile from where it was drawn, anything further, such
+/// as going down a level, jumps. Sprites just added start where they are
+#[allow(clippy::type_complexity)]
+fn start_moves(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut moved: Query<
+        (Entity, &mut Transform, Option<&mut Animated>),
+        (
+            Or<(Changed<MapPosition>, Added<Transform>)>,
+            With<MapPosition>,
+            Without<Tile>,
+        ),
+    >,
+) {
+    let step = settings.tile_size as f32 * 1.5;
+    moved
+        .iter_mut()
+        .for_each(|(entity, mut transform, animated)| {
+            let to = transform.translation;
+            let Some(mut animated) = animated else {
+                commands.entity(entity).insert(Animated {
+                    drawn: to,
+                    tween: None,
+                    hurt: None,
+                    unhurt_color: None,
+                });
+                return;
+            };
+            let from = animated.drawn;
+            if from.truncate().distance(to.truncate()) > step {
+                animated.drawn = to;
+                animated.tween = None;
+                return;
+            }
+            animated.tween = Some(Tween {
+                from,
+                to,
+                elapsed: 0.0,
+            });
+            // Drawn where it was until the slide is played
+            transform.translation = from;
+        });
+}
+
+fn hurt_victims(mut attacks: EventReader<WantsToAttack>, mut animated: Query<&mut Animated>) {
+    attacks.iter().for_each(|attack| {
+        if let Ok(mut victim) = animated.get_mut(attack.victim) {
+            victim.hurt = Some(0.0);
+        }
+    });
+}
+
+fn play_animations(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut animated: Query<
+        (
+            &mut Animated,
+            &mut Transform,
+            Option<&mut TextureAtlasSprite>,
+        ),
+        With<MapPosition>,
+    >,
+) {
+    let seconds = time.delta_seconds();
+    animated
+        .iter_mut()
+        .for_each(|(mut animated, mut transform, sprite)| {
+            if let Some(tween) = animated.tween.as_mut() {
+                tween.elapsed += seconds;
+                let (at, done) = (tween.at(), tween.done());
+                animated.drawn = at;
+                if done {
+                    animated.tween = None;
+                }
+            }
+            // Only the hurt touch the colour, and they get back the one they
+            // had, so tints such as the quest givers' are kept. Hits during
+            // the flash keep the colour from before the first
+            if let (Some(_), None, Some(sprite)) = (animated.hurt, animated.unhurt_color, &sprite) {
+                animated.unhurt_color = Some(sprite.color);
+            }
+            let mut color = None;
+            let mut offset = Vec3::ZERO;
+            if let Some(hurt) = animated.hurt.as_mut() {
+                *hurt += seconds;
+                let left = 1.0 - *hurt / HURT_SECONDS;
+                if left > 0.0 {
+                    offset = shake(*hurt, settings.tile_size as f32);
+                    color = Some(Color::rgb(1.0, 1.0 - left, 1.0 - left));
+                } else {
+                    animated.hurt = None;
+                    color = animated.unhurt_color.take();
+                }
+            }
+            let translation = animated.drawn + offset;
+            if transform.translation != translation {
+                transform.translation = translation;
+            }
+            if let (Some(mut sprite), Some(color)) = (sprite, color) {
+                if sprite.color != color {
+                    sprite.color = color;
+                }
+            }
+        });
+}
+
+/// Acting again finishes whatever is still playing, so the turns never
+/// wait on the animations
+fn skip_animations(actions: Res<Actions>, mut animated: Query<(&mut Animated, &mut Transform)>) {
+    let acting = actions.player_movement.is_some()
+        || actions.wait.is_some()
+        || actions.rest.is_some()
+        || actions.pick_up_item.is_some()
+        || actions.interact.is_some()
+        || actions.use_item.is_some()
+        || actions.clicked_tile.is_some();
+    if !acting {
+        return;
+    }
+    animated
+        .iter_mut()
+        .filter(|(animated, _)| animated.tween.is_some() || animated.hurt.is_some())
+        .for_each(|(mut animated, mut transform)| {
+            if let Some(tween) = animated.tween.take() {
+                animated.drawn = tween.to;
+            }
+            // Played out, so the next frame puts the colour back
+            animated.hurt = animated.hurt.map(|_| HURT_SECONDS);
+            transform.translation = animated.drawn;
+        });
+}
+
+fn spawn_damage_numbers(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageDealt>,
+    font: Res<FontAssets>,
+    settings: Res<Settings>,
+    player: Query<Entity, With<Player>>,
+) {
+    let player = player.get_single().ok();
+    damage_events.iter().for_each(|hit| {
+        let color = match Some(hit.victim) == player {
+            true => Color::rgb(1.0, 0.3, 0.3),
+            false => Color::rgb(1.0, 0.9, 0.4),
+        };
+        let translation = hit
+            .position
+            .translation(settings.entity_z_level + 2.0, settings.tile_size);
+        commands
+            .spawn(Text2dBundle {
+                text: Text::from_section(
+                    hit.amount.to_string(),
+                    TextStyle {
+                        font: font.fira_sans.clone(),
+                        font_size: settings.tile_size as f32 / 2.0,
+                        color,
+                    },
+                )
+                .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_translation(translation),
+                ..default()
+            })
+            .insert(FloatingText::default());
+    });
+}
+
+fn float_damage_numbers(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut texts: Query<(Entity, &mut FloatingText, &mut Transform, &mut Text)>,
+) {
+    let rise = settings.tile_size as f32 / FLOAT_SECONDS;
+    texts
+        .iter_mut()
+        .for_each(|(entity, mut floating, mut transform, mut text)| {
+            floating.elapsed += time.delta_seconds();
+            if floating.elapsed >= FLOAT_SECONDS {
+                commands.entity(entity).despawn_recursive();
+                return;
+            }
+            transform.translation.y += rise * time.delta_seconds();
+            let alpha = 1.0 - floating.elapsed / FLOAT_SECONDS;
+            text.sections.iter_mut().for_each(|section| {
+                section.style.color.set_a(alpha);
+            });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tween_and_shake() {
+        let mut tween = Tween {
+            from: Vec3::ZERO,
+            to: Vec3::new(32.0, 0.0, 1.0),
+            elapsed: 0.0,
+        };
+        assert_eq!(tween.at(), Vec3::ZERO);
+        tween.elapsed = MOVE_SECONDS / 2.0;
+        assert_eq!(tween.at(), Vec3::new(16.0, 0.0, 0.5));
+        assert!(!tween.done());
+        // Late frames land on the tile
+        tween.elapsed = MOVE_SECONDS * 3.0;
+        assert_eq!(tween.at(), tween.to);
+        assert!(tween.done());
+
+        assert_eq!(shake(0.0, 32.0), Vec3::ZERO);
+        assert!(shake(0.01, 32.0).x.abs() <= SHAKE_TILES * 32.0);
+        assert_eq!(shake(HURT_SECONDS, 32.0), Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_hurt_keeps_tint() {
+        let mut world = World::new();
+        world.insert_resource(Time::default());
+        world.insert_resource(Settings::load_profile("development").unwrap());
+        let tint = Color::rgb(0.5, 1.0, 0.5);
+        let entity = world
+            .spawn((
+                MapPosition::new(1, 1),
+                Transform::default(),
+                TextureAtlasSprite {
+                    color: tint,
+                    ..default()
+                },
+                Animated {
+                    drawn: Vec3::ZERO,
+                    tween: None,
+                    hurt: Some(0.0),
+                    unhurt_color: None,
+                },
+            ))
+            .id();
+        let mut stage = SystemStage::single_threaded().with_system(play_animations);
+        let color = |world: &World| world.get::<TextureAtlasSprite>(entity).unwrap().color;
+
+        stage.run(&mut world);
+        assert_eq!(color(&world), Color::rgb(1.0, 0.0, 0.0));
+        // Hit again mid-flash
+        world.get_mut::<Animated>(entity).unwrap().hurt = Some(0.0);
+        stage.run(&mut world);
+        world.get_mut::<Animated>(entity).unwrap().hurt = Some(HURT_SECONDS);
+        stage.run(&mut world);
+        assert_eq!(color(&world), tint);
+        assert!(world
+            .get::<Animated>(entity)
+            .unwrap()
+            .unhurt_color
+            .is_none());
+    }
+}
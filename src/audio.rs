@@ -1,6 +1,14 @@
-use crate::actions::Actions;
-use crate::cleanup::cleanup_resource;
+use std::time::Duration;
+
+use crate::config::{Settings, SoundCue, VolumeSettings};
+use crate::entities::{ActivateItem, Player};
 use crate::loading::AudioAssets;
+use crate::stages::TurnState;
+use crate::systems::combat::DamageDealt;
+use crate::systems::inventory::PickUpEvent;
+use crate::systems::movement::EnteredTile;
+use crate::systems::quest_engine::QuestCompleted;
+use crate::theme::ActiveTheme;
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
@@ -11,50 +19,172 @@ pub struct InternalAudioPlugin;
 impl Plugin for InternalAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(AudioPlugin)
-            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(start_audio))
+            .add_audio_channel::<Music>()
+            .add_audio_channel::<Effects>()
+            .init_resource::<AudioAssets>()
+            .init_resource::<MusicTrack>()
+            .add_system(load_sounds)
             .add_system_set(
-                SystemSet::on_update(GameState::Playing).with_system(control_flying_sound),
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(play_event_cues)
+                    .with_system(play_turn_cues)
+                    .with_system(play_theme_music),
             )
-            .add_system_set(
-                SystemSet::on_exit(GameState::Playing).with_system(cleanup_resource::<FlyingAudio>),
-            );
+            .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(stop_music));
     }
 }
 
-/// Handle for the flying audio
+/// Audio channel for the theme music
 #[derive(Resource)]
-struct FlyingAudio(Handle<AudioInstance>);
-
-/// Setup the audio
-fn start_audio(mut commands: Commands, audio_assets: Res<AudioAssets>, audio: Res<Audio>) {
-    audio.pause();
-    let handle = audio
-        .play(audio_assets.flying.clone())
-        .looped()
-        .with_volume(0.3)
-        .handle();
-    commands.insert_resource(FlyingAudio(handle));
-}
-
-/// Turn on and off the audio
-fn control_flying_sound(
-    actions: Res<Actions>,
-    audio: Res<FlyingAudio>,
-    mut audio_instances: ResMut<Assets<AudioInstance>>,
+struct Music;
+
+/// Audio channel for the sound cues
+#[derive(Resource)]
+struct Effects;
+
+/// Music track playing, to only fade when it changes
+#[derive(Resource, Default)]
+struct MusicTrack(Option<String>);
+
+/// Load the sounds named in the config, again when it is reloaded, and
+/// apply the volume to the music playing
+fn load_sounds(
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut audio_assets: ResMut<AudioAssets>,
+    music: Res<AudioChannel<Music>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    *audio_assets = AudioAssets::load(&settings.audio, &asset_server);
+    music.set_volume(music_volume(&settings.audio.volume));
+}
+
+fn music_volume(volume: &VolumeSettings) -> f64 {
+    volume.master * volume.music
+}
+
+fn play_cue(
+    cue: SoundCue,
+    settings: &Settings,
+    audio_assets: &AudioAssets,
+    effects: &AudioChannel<Effects>,
+) {
+    let (Some(sound), Some(cue_settings)) =
+        (audio_assets.cues.get(&cue), settings.audio.cues.get(&cue))
+    else {
+        return;
+    };
+    let volume = &settings.audio.volume;
+    effects
+        .play(sound.clone())
+        .with_volume(cue_settings.volume * volume.master * volume.effects)
+        .with_playback_rate(cue_settings.rate);
+}
+
+/// Each cue once per frame, however many events set it off
+#[allow(clippy::too_many_arguments)]
+fn play_event_cues(
+    settings: Res<Settings>,
+    audio_assets: Res<AudioAssets>,
+    effects: Res<AudioChannel<Effects>>,
+    player: Query<Entity, With<Player>>,
+    mut entered: EventReader<EnteredTile>,
+    mut hits: EventReader<DamageDealt>,
+    mut pick_ups: EventReader<PickUpEvent>,
+    mut activated: EventReader<ActivateItem>,
+    mut quests: EventReader<QuestCompleted>,
+) {
+    let player = player.get_single().ok();
+    [
+        (
+            SoundCue::Step,
+            entered.iter().any(|e| Some(e.entity) == player),
+        ),
+        (SoundCue::Hit, hits.iter().count() > 0),
+        (SoundCue::PickUp, pick_ups.iter().count() > 0),
+        (SoundCue::UseItem, activated.iter().count() > 0),
+        (SoundCue::QuestComplete, quests.iter().count() > 0),
+    ]
+    .into_iter()
+    .filter(|(_, happened)| *happened)
+    .for_each(|(cue, _)| play_cue(cue, &settings, &audio_assets, &effects));
+}
+
+fn turn_cue(turn: TurnState) -> Option<SoundCue> {
+    match turn {
+        TurnState::NextLevel => Some(SoundCue::NextLevel),
+        TurnState::GameOver => Some(SoundCue::GameOver),
+        TurnState::Victory => Some(SoundCue::Victory),
+        _ => None,
+    }
+}
+
+fn play_turn_cues(
+    turn: Res<TurnState>,
+    settings: Res<Settings>,
+    audio_assets: Res<AudioAssets>,
+    effects: Res<AudioChannel<Effects>>,
+) {
+    if !turn.is_changed() {
+        return;
+    }
+    if let Some(cue) = turn_cue(*turn) {
+        play_cue(cue, &settings, &audio_assets, &effects);
+    }
+}
+
+/// Fade into the theme's music when the theme changes
+fn play_theme_music(
+    theme: Res<ActiveTheme>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    music: Res<AudioChannel<Music>>,
+    mut playing: ResMut<MusicTrack>,
 ) {
-    if let Some(instance) = audio_instances.get_mut(&audio.0) {
-        match instance.state() {
-            PlaybackState::Paused { .. } => {
-                if actions.player_movement.is_some() {
-                    instance.resume(AudioTween::default());
-                }
-            }
-            PlaybackState::Playing { .. } => {
-                if actions.player_movement.is_none() {
-                    instance.pause(AudioTween::default());
-                }
-            }
-            _ => {}
-        }
+    if !theme.is_changed() {
+        return;
+    }
+    let track = theme
+        .settings(&settings)
+        .and_then(|t| t.music.as_ref())
+        .or(settings.audio.music.as_ref());
+    if track == playing.0.as_ref() {
+        return;
+    }
+    let fade = AudioTween::linear(Duration::from_secs_f32(settings.audio.crossfade));
+    music.stop().fade_out(fade.clone());
+    if let Some(track) = track {
+        info!("Playing {}", track);
+        music
+            .play(asset_server.load(track.as_str()))
+            .looped()
+            .with_volume(music_volume(&settings.audio.volume))
+            .fade_in(fade);
+    }
+    playing.0 = track.cloned();
+}
+
+fn stop_music(music: Res<AudioChannel<Music>>, mut playing: ResMut<MusicTrack>) {
+    music.stop();
+    playing.0 = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn_cues_and_volume() {
+        assert_eq!(turn_cue(TurnState::NextLevel), Some(SoundCue::NextLevel));
+        assert_eq!(turn_cue(TurnState::Victory), Some(SoundCue::Victory));
+        assert_eq!(turn_cue(TurnState::NPCsTurn), None);
+        let volume = VolumeSettings {
+            master: 0.5,
+            music: 0.4,
+            effects: 1.0,
+        };
+        assert_eq!(music_volume(&volume), 0.2);
     }
 }
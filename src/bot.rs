@@ -0,0 +1,532 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use iyes_loopless::prelude::*;
+
+use crate::{
+    actions::Actions,
+    components::{health::Health, map_position::MapPosition, name::EntityName},
+    config::Settings,
+    entities::{
+        AvailableQuest, Item, MapLevel, Monster, Player, ProvidesHealing, QuestState, TileType,
+        WinItem,
+    },
+    headless::headless_app,
+    map::{grid_map::base_map::BaseMap, map_builder::MapBuilder, tile_map::in_bounds},
+    stages::TurnState,
+    systems::{
+        fov::FieldOfView, inventory::PlayerInventory, quest_engine::AssignedQuest,
+        travel::TRAVEL_LABEL,
+    },
+    GameState,
+};
+
+/// Plugin that lets the [`Bot`] resource play instead of the player's input
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing).with_system(
+                drive_bot
+                    .run_if_resource_equals(TurnState::AwaitingInput)
+                    .before(TRAVEL_LABEL),
+            ),
+        );
+    }
+}
+
+/// What a bot can do in a turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotAction {
+    /// Move, or attack whatever is in the way
+    Move(IVec2),
+    Wait,
+    PickUp,
+    Interact,
+    /// Use the item in an inventory slot
+    UseItem(usize),
+}
+
+impl BotAction {
+    fn apply(self, actions: &mut Actions) {
+        *actions = Actions::default();
+        match self {
+            BotAction::Move(step) => actions.player_movement = Some(step.as_vec2()),
+            BotAction::Wait => actions.wait = Some(true),
+            BotAction::PickUp => actions.pick_up_item = Some(true),
+            BotAction::Interact => actions.interact = Some(true),
+            BotAction::UseItem(slot) => actions.use_item = Some(slot),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeenKind {
+    Monster,
+    QuestGiver,
+    Item,
+    WinItem,
+}
+
+/// Something the player can see
+#[derive(Debug, Clone)]
+pub struct SeenEntity {
+    pub name: String,
+    pub kind: SeenKind,
+    pub position: MapPosition,
+    pub health: Option<Health>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InventorySlot {
+    pub name: String,
+    pub healing: bool,
+}
+
+/// What the player knows at the start of a turn
+#[derive(Debug, Clone)]
+pub struct Observation {
+    pub level: u32,
+    /// Width and height of the map
+    pub size: UVec2,
+    pub position: MapPosition,
+    pub health: Health,
+    /// Tiles in view as they look, hidden traps look like floor
+    pub visible_tiles: Vec<(MapPosition, TileType)>,
+    pub entities: Vec<SeenEntity>,
+    /// Items by the slot used to use them
+    pub inventory: Vec<InventorySlot>,
+    pub quests: Vec<(String, QuestState)>,
+}
+
+/// Plays the game, one action per turn
+pub trait Agent: Send + Sync {
+    fn act(&mut self, observation: &Observation) -> BotAction;
+}
+
+/// The agent playing, and the turns it has taken
+#[derive(Resource)]
+pub struct Bot {
+    agent: Box<dyn Agent>,
+    pub turns: u32,
+}
+
+impl Bot {
+    pub fn new(agent: impl Agent + 'static) -> Self {
+        Self {
+            agent: Box::new(agent),
+            turns: 0,
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn drive_bot(
+    mut bot: ResMut<Bot>,
+    mut actions: ResMut<Actions>,
+    player: Query<(Entity, &MapPosition, &FieldOfView, &Health, &MapLevel), With<Player>>,
+    seen: Query<
+        (
+            &EntityName,
+            &MapPosition,
+            Option<&Health>,
+            Option<&Monster>,
+            Option<&AvailableQuest>,
+            Option<&WinItem>,
+        ),
+        Or<(With<Monster>, With<AvailableQuest>, With<Item>)>,
+    >,
+    inventory: Query<&PlayerInventory>,
+    carried: Query<(&EntityName, Option<&ProvidesHealing>)>,
+    quests: Query<(&EntityName, &QuestState, &AssignedQuest)>,
+    map_builder: Res<MapBuilder>,
+) {
+    let Ok((player, position, fov, health, level)) = player.get_single() else {
+        return;
+    };
+    let in_order = |p: &MapPosition| (p.position.y, p.position.x);
+    let mut visible_tiles = fov
+        .visible_positions
+        .iter()
+        .filter(|p| map_builder.map.in_bounds(**p))
+        .map(|p| (*p, map_builder.map.value(*p).appearance()))
+        .collect::<Vec<_>>();
+    visible_tiles.sort_by_key(|(p, _)| in_order(p));
+    let mut entities = seen
+        .iter()
+        .filter(|(_, p, ..)| fov.visible_positions.contains(p))
+        .map(|(name, p, health, monster, quest, win)| SeenEntity {
+            name: name.0.clone(),
+            kind: match (monster, quest, win) {
+                (Some(_), ..) => SeenKind::Monster,
+                (_, Some(_), _) => SeenKind::QuestGiver,
+                (.., Some(_)) => SeenKind::WinItem,
+                _ => SeenKind::Item,
+            },
+            position: *p,
+            health: health.copied(),
+        })
+        .collect::<Vec<_>>();
+    entities.sort_by_key(|e| in_order(&e.position));
+    let observation = Observation {
+        level: level.value,
+        size: UVec2::new(map_builder.map.width as u32, map_builder.map.height as u32),
+        position: *position,
+        health: *health,
+        visible_tiles,
+        entities,
+        inventory: inventory
+            .get_single()
+            .map(|inventory| {
+                inventory
+                    .key_map
+                    .iter()
+                    .filter_map(|item| carried.get(*item).ok())
+                    .map(|(name, healing)| InventorySlot {
+                        name: name.0.clone(),
+                        healing: healing.is_some(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        quests: quests
+            .iter()
+            .filter(|(.., assigned)| assigned.assignee == player)
+            .map(|(name, state, _)| (name.0.clone(), *state))
+            .collect(),
+    };
+    let action = bot.agent.act(&observation);
+    if let BotAction::UseItem(slot) = action {
+        if let Some(item) = observation.inventory.get(slot) {
+            debug!("Bot uses {}", item.name);
+        }
+    }
+    action.apply(&mut actions);
+    bot.turns += 1;
+}
+
+const DIRECTIONS: [IVec2; 4] = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+
+/// Steps towards goals looked back over for going round in circles
+const LOOP_WINDOW: usize = 12;
+
+/// Times a tile is stood on in the window before the bot is going round in
+/// circles, such as when monsters keep blocking and clearing its way
+const LOOP_VISITS: usize = 3;
+
+/// Heals when low, fights when next to a monster, otherwise heads for the
+/// win item or exit, picking up items and exploring on the way
+#[derive(Default)]
+pub struct GreedyBot {
+    /// Tiles seen on this level
+    known: HashMap<MapPosition, TileType>,
+    /// Items seen on this level and not yet picked up
+    items: HashMap<MapPosition, SeenKind>,
+    /// Where items were picked up from, so one that stays is not tried again
+    picked_up: HashSet<MapPosition>,
+    /// Tiles stood on this level. One at the edge of what has been seen
+    /// that showed nothing new is not explored again
+    visited: HashSet<MapPosition>,
+    /// Where the last steps towards goals were taken from
+    recent: VecDeque<MapPosition>,
+    /// Goals the bot went round in circles trying to reach on this level
+    given_up: HashSet<MapPosition>,
+    level: u32,
+    /// Quest givers talked to, with how many quests were ready to hand in
+    talked: HashMap<String, usize>,
+}
+
+impl GreedyBot {
+    fn remember(&mut self, observation: &Observation) {
+        if observation.level != self.level {
+            self.known.clear();
+            self.items.clear();
+            self.picked_up.clear();
+            self.visited.clear();
+            self.recent.clear();
+            self.given_up.clear();
+            self.level = observation.level;
+        }
+        self.visited.insert(observation.position);
+        self.known.extend(observation.visible_tiles.iter().copied());
+        // Items in view are where they are seen, any others remembered there are gone
+        observation.visible_tiles.iter().for_each(|(p, _)| {
+            self.items.remove(p);
+        });
+        self.items.extend(
+            observation
+                .entities
+                .iter()
+                .filter(|e| matches!(e.kind, SeenKind::Item | SeenKind::WinItem))
+                .filter(|e| !self.picked_up.contains(&e.position))
+                .map(|e| (e.position, e.kind)),
+        );
+    }
+
+    fn walkable(&self, p: MapPosition) -> bool {
+        self.known.get(&p).is_some_and(|t| t.properties().walkable)
+    }
+
+    /// The nearest tile `is_goal` accepts, walking over known tiles, and
+    /// the first step there. Goals given up on are passed over
+    fn first_step(
+        &self,
+        from: MapPosition,
+        blocked: &HashSet<MapPosition>,
+        is_goal: impl Fn(MapPosition) -> bool,
+    ) -> Option<(MapPosition, IVec2)> {
+        let mut first_steps = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(p) = queue.pop_front() {
+            if p != from && !self.given_up.contains(&p) && is_goal(p) {
+                return first_steps.get(&p).map(|step| (p, *step));
+            }
+            DIRECTIONS.iter().for_each(|d| {
+                let next = MapPosition::from_ivec2(p.position + *d);
+                if next != from
+                    && self.walkable(next)
+                    && !blocked.contains(&next)
+                    && !first_steps.contains_key(&next)
+                {
+                    let first = first_steps.get(&p).copied().unwrap_or(*d);
+                    first_steps.insert(next, first);
+                    queue.push_back(next);
+                }
+            });
+        }
+        None
+    }
+}
+
+impl Agent for GreedyBot {
+    fn act(&mut self, observation: &Observation) -> BotAction {
+        self.remember(observation);
+        let position = observation.position;
+        let health = observation.health;
+
+        if health.current * 2 <= health.max {
+            if let Some(slot) = observation.inventory.iter().position(|i| i.healing) {
+                return BotAction::UseItem(slot);
+            }
+        }
+        let monsters = observation
+            .entities
+            .iter()
+            .filter(|e| e.kind == SeenKind::Monster)
+            .map(|e| e.position)
+            .collect::<HashSet<_>>();
+        let adjacent = |e: &&SeenEntity| e.position.distance(position) <= 1.0;
+        let weakest = observation
+            .entities
+            .iter()
+            .filter(|e| e.kind == SeenKind::Monster)
+            .filter(adjacent)
+            .min_by_key(|e| e.health.map_or(i32::MAX, |h| h.current));
+        if let Some(monster) = weakest {
+            return BotAction::Move(monster.position.position - position.position);
+        }
+        // Take new quests, and hand in fetched ones
+        let ready = observation
+            .quests
+            .iter()
+            .filter(|(_, state)| *state == QuestState::Updated)
+            .count();
+        let giver = observation
+            .entities
+            .iter()
+            .filter(|e| e.kind == SeenKind::QuestGiver)
+            .filter(adjacent)
+            .find(|e| self.talked.get(&e.name).map_or(true, |r| ready > *r));
+        if let Some(giver) = giver {
+            self.talked.insert(giver.name.clone(), ready);
+            return BotAction::Interact;
+        }
+        if self.items.contains_key(&position) {
+            self.items.remove(&position);
+            self.picked_up.insert(position);
+            return BotAction::PickUp;
+        }
+
+        let size = observation.size;
+        let goals: [&dyn Fn(MapPosition) -> bool; 4] = [
+            &|p| self.items.get(&p) == Some(&SeenKind::WinItem),
+            &|p| self.known.get(&p) == Some(&TileType::Exit),
+            &|p| self.items.get(&p) == Some(&SeenKind::Item),
+            // The edge of what has been seen
+            &|p| {
+                !self.visited.contains(&p)
+                    && DIRECTIONS.iter().any(|d| {
+                        let next = p.position + *d;
+                        in_bounds(next, size.x as usize, size.y as usize)
+                            && !self.known.contains_key(&MapPosition::from_ivec2(next))
+                    })
+            },
+        ];
+        let next_goal = |bot: &Self| {
+            goals
+                .iter()
+                .find_map(|is_goal| bot.first_step(position, &monsters, is_goal))
+        };
+        let mut next = next_goal(self);
+        // Back here too often, so try for something else
+        if self.recent.iter().filter(|p| **p == position).count() >= LOOP_VISITS {
+            if let Some((goal, _)) = next {
+                debug!("Bot gives up on reaching {:?}", goal);
+                self.given_up.insert(goal);
+                self.recent.clear();
+                next = next_goal(self);
+            }
+        }
+        if next.is_some() {
+            self.recent.push_back(position);
+            if self.recent.len() > LOOP_WINDOW {
+                self.recent.pop_front();
+            }
+        }
+        next.map_or(BotAction::Wait, |(_, step)| BotAction::Move(step))
+    }
+}
+
+/// How a bot's run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    Died,
+    OutOfTurns,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RunReport {
+    pub seed: u64,
+    pub outcome: Outcome,
+    pub turns: u32,
+    pub level: u32,
+}
+
+/// Frames a turn may take, levels are generated in frames of their own
+const FRAMES_PER_TURN: u32 = 4;
+
+/// Play a whole run headless with `agent`, as fast as the turns can be taken
+pub fn run_bot(
+    settings: Settings,
+    seed: u64,
+    agent: impl Agent + 'static,
+    max_turns: u32,
+) -> RunReport {
+    play(headless_app(settings, seed, None), seed, agent, max_turns)
+}
+
+/// Let `agent` play the game `app` runs until it is over or out of turns
+fn play(mut app: App, seed: u64, agent: impl Agent + 'static, max_turns: u32) -> RunReport {
+    app.insert_resource(Bot::new(agent)).add_plugin(BotPlugin);
+    let mut outcome = Outcome::OutOfTurns;
+    for _ in 0..max_turns * FRAMES_PER_TURN {
+        app.update();
+        match app.world.resource::<TurnState>() {
+            TurnState::Victory => outcome = Outcome::Won,
+            TurnState::GameOver => outcome = Outcome::Died,
+            _ if app.world.resource::<Bot>().turns >= max_turns => {}
+            _ => continue,
+        }
+        break;
+    }
+    let level = app
+        .world
+        .query_filtered::<&MapLevel, With<Player>>()
+        .get_single(&app.world)
+        .map_or(0, |l| l.value);
+    RunReport {
+        seed,
+        outcome,
+        turns: app.world.resource::<Bot>().turns,
+        level,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observation(position: MapPosition, map: &str) -> Observation {
+        let mb = map.parse::<MapBuilder>().unwrap();
+        Observation {
+            level: 0,
+            size: UVec2::new(mb.map.width as u32, mb.map.height as u32),
+            position,
+            health: Health {
+                current: 5,
+                max: 10,
+            },
+            visible_tiles: mb
+                .map
+                .tiles
+                .indexed_iter()
+                .map(|(idx, t)| (MapPosition::from_utuple(&idx), *t))
+                .collect(),
+            entities: vec![],
+            inventory: vec![],
+            quests: vec![],
+        }
+    }
+
+    #[test]
+    fn test_greedy_bot() {
+        let mut bot = GreedyBot::default();
+        let mut seen = observation(
+            MapPosition::new(1, 1),
+            "######
+             #...>#
+             ######",
+        );
+        assert_eq!(bot.act(&seen), BotAction::Move(IVec2::X));
+
+        // Fights before walking on
+        seen.entities.push(SeenEntity {
+            name: "Nutritionist".to_string(),
+            kind: SeenKind::Monster,
+            position: MapPosition::new(1, 2),
+            health: None,
+        });
+        seen.visible_tiles
+            .push((MapPosition::new(1, 2), TileType::Floor));
+        assert_eq!(bot.act(&seen), BotAction::Move(IVec2::Y));
+
+        // And heals first when low
+        seen.health.current = 4;
+        seen.inventory.push(InventorySlot {
+            name: "Fries".to_string(),
+            healing: true,
+        });
+        assert_eq!(bot.act(&seen), BotAction::UseItem(0));
+    }
+
+    #[test]
+    fn test_run_bot() {
+        let settings = Settings::load_profile("mini").unwrap();
+        let report = run_bot(settings, 1, GreedyBot::default(), 200);
+        assert!(report.turns > 0);
+        assert!(report.turns <= 200);
+    }
+
+    #[test]
+    fn test_bot_finishes_fixed_map() {
+        let settings = Settings::load_profile("development").unwrap();
+        // The exit is down a dead end, past corners the bot has to explore
+        let map = "##########
+                   #@.#.....#
+                   #..#.###.#
+                   #..#.#>#.#
+                   #....#.#.#
+                   ######...#
+                   ##########"
+            .parse::<MapBuilder>()
+            .unwrap();
+        let app = headless_app(settings, 1, Some(map));
+        let report = play(app, 1, GreedyBot::default(), 100);
+        assert!(report.level > 0, "{:?}", report);
+    }
+}
@@ -1,38 +1,274 @@
 use bevy::prelude::*;
 
 use crate::{
-    cleanup::cleanup_components, config::Settings, map::map_builder::MapBuilder, GameState,
+    actions::{Actions, ACTIONS_LABEL},
+    cleanup::cleanup_components,
+    components::map_position::MapPosition,
+    config::Settings,
+    entities::Player,
+    map::map_builder::MapBuilder,
+    systems::travel::TRAVEL_LABEL,
+    GameState,
 };
 
+/// Projection scales the camera zooms through, from furthest out. Each
+/// draws a tile over a whole number of screen pixels
+const ZOOM_SCALES: [f32; 5] = [2.0, 1.0, 0.5, 1.0 / 3.0, 0.25];
+
+/// Index into [`ZOOM_SCALES`] a run starts at
+const DEFAULT_ZOOM: usize = 1;
+
+/// How quickly the camera catches up with what it follows, per second
+const FOLLOW_RATE: f32 = 8.0;
+
+/// Label for moving the look cursor, before the player's movement is used
+const LOOK_LABEL: &str = "LookAround";
+
 /// Plugin to setup the camera
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(setup_camera))
+        app.init_resource::<CameraController>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(setup_camera)
+                    .with_system(spawn_look_cursor),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(zoom_camera.after(ACTIONS_LABEL))
+                    .with_system(
+                        look_around
+                            .label(LOOK_LABEL)
+                            .after(ACTIONS_LABEL)
+                            .before(TRAVEL_LABEL),
+                    )
+                    .with_system(follow.after(LOOK_LABEL)),
+            )
             .add_system_set(
-                SystemSet::on_exit(GameState::Playing).with_system(cleanup_components::<Camera>),
+                SystemSet::on_exit(GameState::Playing)
+                    .with_system(cleanup_components::<Camera>)
+                    .with_system(cleanup_components::<LookCursor>),
             );
     }
 }
 
+/// What the game camera shows
+#[derive(Resource, Debug)]
+pub struct CameraController {
+    zoom: usize,
+    /// Point to look at instead of the player, eg. from the minimap
+    pub pan: Option<Vec2>,
+    /// Tile under the cursor while looking around
+    pub look: Option<MapPosition>,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            zoom: DEFAULT_ZOOM,
+            pan: None,
+            look: None,
+        }
+    }
+}
+
+impl CameraController {
+    pub fn scale(&self) -> f32 {
+        ZOOM_SCALES[self.zoom]
+    }
+
+    /// Zoom in or out by whole steps, stopping at the ends
+    fn zoom_by(&mut self, steps: i32) {
+        self.zoom = (self.zoom as i32 + steps).clamp(0, ZOOM_SCALES.len() as i32 - 1) as usize;
+    }
+}
+
+/// Marks the tile looked at
+#[derive(Component)]
+struct LookCursor;
+
 /// Insert the game camera
-fn setup_camera(mut commands: Commands, map_builder: Res<MapBuilder>, settings: Res<Settings>) {
-    commands.spawn(Camera2dBundle {
-        transform: Transform::from_translation(
-            map_builder
-                .player_start
-                .translation(settings.entity_z_level, settings.tile_size),
-        ),
-        ..default()
-    });
-}
-
-/// Focus the camera on the player sprite
-pub fn focus_camera(
-    camera_query: &mut Query<&mut Transform, With<Camera2d>>,
-    transform: Mut<Transform>,
+fn setup_camera(
+    mut commands: Commands,
+    mut controller: ResMut<CameraController>,
+    map_builder: Res<MapBuilder>,
+    settings: Res<Settings>,
+) {
+    *controller = default();
+    let mut camera = Camera2dBundle::default();
+    // Keep the bundle's depth so every layer is in front of the camera
+    let start = map_builder
+        .player_start
+        .translation(0.0, settings.tile_size);
+    camera.transform.translation = start.truncate().extend(camera.transform.translation.z);
+    commands.spawn(camera);
+}
+
+fn spawn_look_cursor(mut commands: Commands, settings: Res<Settings>) {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 0.0, 0.35),
+                custom_size: Some(Vec2::splat(settings.tile_size as f32)),
+                ..default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(LookCursor);
+}
+
+fn zoom_camera(
+    actions: Res<Actions>,
+    mut controller: ResMut<CameraController>,
+    mut projection: Query<&mut OrthographicProjection, With<Camera2d>>,
 ) {
-    let mut camera_transform = camera_query.single_mut();
-    *camera_transform = Transform::from_translation(transform.translation);
+    if let Some(steps) = actions.zoom {
+        controller.zoom_by(steps);
+    }
+    if let Ok(mut projection) = projection.get_single_mut() {
+        if projection.scale != controller.scale() {
+            projection.scale = controller.scale();
+        }
+    }
+}
+
+/// Start looking around from the player, or stop. While looking the
+/// movement controls move the cursor, not the player
+fn look_around(
+    mut actions: ResMut<Actions>,
+    mut controller: ResMut<CameraController>,
+    map_builder: Res<MapBuilder>,
+    settings: Res<Settings>,
+    player: Query<&MapPosition, With<Player>>,
+    mut cursor: Query<(&mut Transform, &mut Visibility), With<LookCursor>>,
+) {
+    if actions.look.is_some() {
+        controller.look = match controller.look {
+            Some(_) => None,
+            None => player.get_single().ok().copied(),
+        };
+    }
+    let Some(look) = controller.look else {
+        if let Ok((_, mut visibility)) = cursor.get_single_mut() {
+            visibility.is_visible = false;
+        }
+        return;
+    };
+    let mut look = look;
+    if let Some(movement) = actions.player_movement.take() {
+        let step = MapPosition::from_ivec2(look.position + movement.round().as_ivec2());
+        if map_builder.map.in_bounds(step) {
+            look = step;
+        }
+    }
+    actions.explore = None;
+    controller.look = Some(look);
+    if let Ok((mut transform, mut visibility)) = cursor.get_single_mut() {
+        transform.translation = look.translation(settings.entity_z_level + 1.0, settings.tile_size);
+        visibility.is_visible = true;
+    }
+}
+
+/// Ease the camera towards the look cursor, a pan or the player, keeping
+/// the map on screen
+fn follow(
+    time: Res<Time>,
+    controller: Res<CameraController>,
+    map_builder: Res<MapBuilder>,
+    settings: Res<Settings>,
+    windows: Res<Windows>,
+    player: Query<&Transform, (With<Player>, Without<Camera2d>)>,
+    mut camera: Query<&mut Transform, With<Camera2d>>,
+) {
+    let Ok(mut camera) = camera.get_single_mut() else {
+        return;
+    };
+    let target = match (controller.look, controller.pan, player.get_single()) {
+        (Some(look), ..) => look.translation(0.0, settings.tile_size).truncate(),
+        (None, Some(pan), _) => pan,
+        (None, None, Ok(player)) => player.translation.truncate(),
+        _ => return,
+    };
+    let scale = controller.scale();
+    let view = windows
+        .get_primary()
+        .map_or(Vec2::ZERO, |w| Vec2::new(w.width(), w.height()) * scale);
+    let map = &map_builder.map;
+    let tile_size = settings.tile_size as f32;
+    let map_size = Vec2::new(map.width as f32, map.height as f32) * tile_size;
+    // Tile translations are their centres
+    let map_min = Vec2::splat(-tile_size / 2.0);
+    let target = clamp_to_map(target, view, map_min, map_min + map_size);
+    let eased = ease(camera.translation.truncate(), target, time.delta_seconds());
+    // Whole screen pixels, so the tiles don't shimmer
+    let snapped = (eased / scale).round() * scale;
+    camera.translation = snapped.extend(camera.translation.z);
+}
+
+/// Part of the way from `current` to `target`, the same share each second
+/// whatever the frame rate
+fn ease(current: Vec2, target: Vec2, seconds: f32) -> Vec2 {
+    current.lerp(target, 1.0 - (-FOLLOW_RATE * seconds).exp())
+}
+
+/// Move the centre of a view so it stays within the map, centring the map
+/// along any side it is smaller than the view
+fn clamp_to_map(centre: Vec2, view: Vec2, map_min: Vec2, map_max: Vec2) -> Vec2 {
+    let half = view / 2.0;
+    let clamp = |c: f32, half: f32, min: f32, max: f32| {
+        if max - min <= half * 2.0 {
+            (min + max) / 2.0
+        } else {
+            c.clamp(min + half, max - half)
+        }
+    };
+    Vec2::new(
+        clamp(centre.x, half.x, map_min.x, map_max.x),
+        clamp(centre.y, half.y, map_min.y, map_max.y),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamp_to_map() {
+        let (min, max) = (Vec2::ZERO, Vec2::new(1000.0, 200.0));
+        let view = Vec2::new(400.0, 300.0);
+        // Pushed in from the left edge, the map is shorter than the view
+        assert_eq!(
+            clamp_to_map(Vec2::new(50.0, 10.0), view, min, max),
+            Vec2::new(200.0, 100.0)
+        );
+        assert_eq!(
+            clamp_to_map(Vec2::new(500.0, 10.0), view, min, max),
+            Vec2::new(500.0, 100.0)
+        );
+        assert_eq!(
+            clamp_to_map(Vec2::new(990.0, 10.0), view, min, max),
+            Vec2::new(800.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn test_zoom_and_ease() {
+        let mut controller = CameraController::default();
+        controller.zoom_by(10);
+        assert_eq!(controller.scale(), 0.25);
+        controller.zoom_by(-10);
+        assert_eq!(controller.scale(), 2.0);
+        // Every step draws a 32 pixel tile over whole pixels
+        assert!(ZOOM_SCALES
+            .iter()
+            .all(|s| ((32.0 / s) - (32.0 / s).round()).abs() < 0.001));
+
+        let halfway = ease(Vec2::ZERO, Vec2::X * 100.0, 2f32.ln() / FOLLOW_RATE);
+        assert!((halfway.x - 50.0).abs() < 0.01);
+        // Slow frames don't overshoot
+        assert!(ease(Vec2::ZERO, Vec2::X * 100.0, 10.0).x <= 100.0);
+    }
 }
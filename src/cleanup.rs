@@ -1,4 +1,4 @@
-use bevy::{ecs::system::Resource, prelude::*};
+use bevy::prelude::*;
 
 /// Remove any entities with the components
 pub fn cleanup_components<T: Component>(mut commands: Commands, q: Query<Entity, With<T>>) {
@@ -7,8 +7,3 @@ pub fn cleanup_components<T: Component>(mut commands: Commands, q: Query<Entity,
         commands.entity(e).despawn_recursive();
     }
 }
-
-/// Cleanup any resources of a type
-pub fn cleanup_resource<T: Resource>(mut commands: Commands) {
-    commands.remove_resource::<T>();
-}
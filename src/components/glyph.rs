@@ -0,0 +1,6 @@
+use bevy::prelude::*;
+
+/// What an entity is drawn as, an index into the dungeon font. The window
+/// draws it as a sprite from the texture atlas, the terminal as a character
+#[derive(Component, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyph(pub usize);
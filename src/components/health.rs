@@ -1,7 +1,67 @@
 use bevy::prelude::*;
 
+use crate::config::RegenerationSettings;
+
 #[derive(Debug, Component, Default, Clone, Copy)]
 pub struct Health {
     pub current: i32,
     pub max: i32,
 }
+
+impl Health {
+    /// Restore health, never above the max
+    pub fn heal(&mut self, amount: i32) {
+        self.current = self.max.min(self.current + amount);
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.current >= self.max
+    }
+}
+
+/// Natural healing over time, `None` settings never heal
+#[derive(Debug, Component, Default, Clone, Copy)]
+pub struct Regeneration {
+    settings: Option<RegenerationSettings>,
+    /// Turns since the last heal
+    turns: u32,
+}
+
+impl Regeneration {
+    pub fn new(settings: Option<RegenerationSettings>) -> Self {
+        Self { settings, turns: 0 }
+    }
+
+    /// Count a turn, giving the amount to heal when it is time to
+    pub fn tick(&mut self) -> Option<i32> {
+        let settings = self.settings?;
+        self.turns += 1;
+        if self.turns < settings.turns {
+            return None;
+        }
+        self.turns = 0;
+        Some(settings.amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regeneration() {
+        let mut health = Health { current: 5, max: 6 };
+        let mut regeneration = Regeneration::new(Some(RegenerationSettings {
+            turns: 2,
+            amount: 3,
+        }));
+        assert_eq!(regeneration.tick(), None);
+        let amount = regeneration.tick().unwrap();
+        health.heal(amount);
+        assert_eq!(health.current, 6);
+        assert!(health.is_full());
+        assert_eq!(regeneration.tick(), None);
+
+        assert_eq!(Regeneration::new(None).tick(), None);
+    }
+}
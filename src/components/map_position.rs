@@ -33,6 +33,11 @@ impl MapPosition {
         )
     }
 
+    /// Tile a point in the game world falls on
+    pub fn from_translation(translation: Vec2, tile_size: i32) -> Self {
+        Self::from_ivec2((translation / tile_size as f32).round().as_ivec2())
+    }
+
     pub fn from_ivec2(position: IVec2) -> Self {
         Self { position }
     }
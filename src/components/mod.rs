@@ -1,4 +1,5 @@
 pub mod damage;
+pub mod glyph;
 pub mod health;
 pub mod map_position;
 pub mod name;
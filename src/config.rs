@@ -1,28 +1,125 @@
-use std::env;
+use std::{env, fmt::Display, fs, path::Path, time::SystemTime};
 
-use bevy::{
-    prelude::{Plugin, Resource},
-    utils::HashMap,
-};
+use bevy::{prelude::*, utils::HashMap};
 use config::{Config, ConfigError, Environment, File};
 use serde::{Deserialize, Serialize};
 
-use crate::entities::TileType;
+use crate::{entities::TileType, map::map_builder::MapBuilder};
 
 pub struct ConfigPlugin;
 
 impl Plugin for ConfigPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        let config = Settings::new().unwrap();
-        app.insert_resource(config);
+        let config = Settings::load().unwrap_or_else(|e| panic!("{}", e));
+        app.insert_resource(config)
+            .insert_resource(ConfigWatcher {
+                run_mode: run_mode(),
+                timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+                last_modified: latest_modified(CONFIG_DIR),
+            })
+            .add_system(reload_settings);
     }
 }
 
+/// Directory the yaml profiles are loaded from
+pub const CONFIG_DIR: &str = "config";
+
+/// Directory the game's assets are read from
+pub const ASSETS_DIR: &str = "assets";
+
+/// Bindings saved from the rebinding screen, layered over the profiles
+const BINDINGS_FILE: &str = "bindings";
+
+/// Options saved from the options screen, layered over the local profile
+const PREFERENCES_FILE: &str = "preferences";
+
+/// Volumes saved before they moved into the preferences, still layered
+/// under them until the options are saved again
+const VOLUME_FILE: &str = "volume";
+
+/// Profile used when none is named, it needs no file of its own
+const DEFAULT_RUN_MODE: &str = "development";
+
+/// Polls the config directory so settings can be tuned while playing
+#[derive(Resource)]
+struct ConfigWatcher {
+    run_mode: String,
+    timer: Timer,
+    last_modified: Option<SystemTime>,
+}
+
+/// Whether a file in the config directory is one the settings are loaded
+/// from, files the game writes itself, such as the high scores, are not
+fn is_settings_file(path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("yml") => true,
+        Some("json") => matches!(
+            path.file_stem().and_then(|s| s.to_str()),
+            Some(stem) if [VOLUME_FILE, PREFERENCES_FILE, BINDINGS_FILE].contains(&stem)
+        ),
+        _ => false,
+    }
+}
+
+fn latest_modified(dir: &str) -> Option<SystemTime> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            if !is_settings_file(&entry.path()) {
+                return None;
+            }
+            entry.metadata().ok()?.modified().ok()
+        })
+        .max()
+}
+
+/// Swap in the new settings when a profile changes, as long as they are valid.
+/// Systems reading [`Settings`] pick them up on the next spawn or level.
+fn reload_settings(
+    mut watcher: ResMut<ConfigWatcher>,
+    mut settings: ResMut<Settings>,
+    time: Res<Time>,
+) {
+    if !watcher.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let modified = latest_modified(CONFIG_DIR);
+    if modified <= watcher.last_modified {
+        return;
+    }
+    watcher.last_modified = modified;
+
+    // Saving another profile from the options screen switches to it
+    watcher.run_mode = run_mode();
+    match Settings::load_saved(&watcher.run_mode) {
+        Ok(new_settings) => {
+            info!("Reloaded config profile {}", watcher.run_mode);
+            *settings = new_settings;
+        }
+        Err(e) => warn!("Ignoring config change. {}", e),
+    }
+}
+
+/// Number of sprites in the dungeon font texture atlas (16 x 16)
+pub const ATLAS_SPRITES: usize = 256;
+
 #[derive(Debug, Deserialize)]
 pub struct ActorSettings {
     pub entity: EntitySettings,
     pub max_health: i32,
     pub fov_radius: i32,
+    /// Natural healing, actors without it never heal on their own
+    #[serde(default)]
+    pub regeneration: Option<RegenerationSettings>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct RegenerationSettings {
+    /// Turns between each heal
+    pub turns: u32,
+    /// Health restored each time
+    pub amount: i32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -38,6 +135,8 @@ pub enum Behaviour {
     Random,
     #[default]
     Chasing,
+    /// Chases the player, smashing through walls with its base damage
+    Smashing,
 }
 
 #[derive(Debug, Deserialize)]
@@ -78,24 +177,253 @@ pub struct MapSettings {
     pub height: usize,
     pub z_level: f32,
     pub architect: ArchitectSettings,
+    pub traps: TrapSettings,
+    /// Damage a wall takes before it is dug out
+    pub wall_health: i32,
+    pub lighting: LightingSettings,
 }
 
-#[derive(Debug, Deserialize, Default)]
+/// Torches and the colours lit tiles are tinted with
+#[derive(Debug, Deserialize)]
+pub struct LightingSettings {
+    /// Tiles a torch placed by a prefab lights
+    pub torch_radius: i32,
+    pub torch_sprite: usize,
+    /// Used when the theme has no palette
+    pub palette: Palette,
+}
+
+/// Red, green and blue from 0 to 1 of each kind of light
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct Palette {
+    /// Light the player carries, as far as they can see
+    pub player: [f32; 3],
+    pub torch: [f32; 3],
+    /// Added to every tile in view however far it is from a light
+    pub ambient: [f32; 3],
+    /// Tiles seen before that are out of view
+    pub remembered: [f32; 3],
+}
+
+impl Palette {
+    fn validate(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        [
+            ("player", self.player),
+            ("torch", self.torch),
+            ("ambient", self.ambient),
+            ("remembered", self.remembered),
+        ]
+        .iter()
+        .filter(|(_, rgb)| rgb.iter().any(|c| !(0.0..=1.0).contains(c)))
+        .for_each(|(name, _)| {
+            errors.push(ValidationError::new(
+                format!("{}.{}", path, name),
+                "must be between 0 and 1",
+            ))
+        });
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrapSettings {
+    pub spike_damage: i32,
+    /// Distance a pressure plate opens secret walls within
+    pub plate_radius: f32,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy)]
 pub enum Architect {
     Empty,
     #[default]
     Standard,
     Automata,
     Drunkard,
+    /// Loads the level in [`ArchitectSettings::fixed`]
+    Fixed,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 pub struct ArchitectSettings {
     pub architect: Architect,
     pub num_monsters: usize,
     pub num_items: usize,
     pub num_npcs: usize,
     pub entity_distance: f32,
+    #[serde(default)]
+    pub num_traps: usize,
+    /// Used when the architect is [`Architect::Automata`]
+    #[serde(default)]
+    pub caves: CaveSettings,
+    /// Steps run over the map once the architect has laid it out, empty
+    /// for the architect's own
+    #[serde(default)]
+    pub pipeline: Vec<MetaBuilder>,
+    /// Used when the architect is [`Architect::Fixed`]
+    #[serde(default)]
+    pub fixed: Option<FixedLevel>,
+}
+
+/// A hand-made level, in the text format maps are printed in
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct FixedLevel {
+    /// Text file, relative to the assets directory
+    pub path: String,
+    /// Glyphs that spawn a particular monster or item on floor
+    #[serde(default)]
+    pub glyphs: HashMap<char, FixedSpawn>,
+}
+
+impl FixedLevel {
+    pub fn read(&self) -> Result<String, String> {
+        fs::read_to_string(Path::new(ASSETS_DIR).join(&self.path))
+            .map_err(|e| format!("could not read {}: {}", self.path, e))
+    }
+}
+
+/// An entity from the settings, by name
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub enum FixedSpawn {
+    Monster(String),
+    Item(String),
+}
+
+/// A step run over a laid out map, the pipeline runs them in order
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub enum MetaBuilder {
+    /// Where the player starts, later steps measure from there
+    Start(StartPosition),
+    /// Where the win item goes
+    Exit(ExitPosition),
+    /// Wall in the floor the player can't reach
+    CullUnreachable,
+    /// Monsters, items and npcs anywhere on the floor further than the
+    /// entity distance from the start
+    AreaSpawns,
+    /// A monster and an item in every room but the first, for architects
+    /// that make rooms
+    RoomSpawns,
+    /// Monsters, items and npcs shared out over the rooms, or over
+    /// `regions` areas of the floor when there are none, by their size.
+    /// In every spawn step no two spawns share a tile or stand on the start
+    /// or the win item
+    RegionSpawns {
+        #[serde(default = "default_regions")]
+        regions: usize,
+    },
+    Prefab(PrefabPlacement),
+    /// Knock down walls with no wall beside them
+    WallCleanup,
+    /// Close the corridors leading out of rooms with doors, for architects
+    /// that make rooms
+    Doors,
+    /// Copy half of the map over the other half, best before spawning
+    Mirror(Symmetry),
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum StartPosition {
+    /// The floor nearest the middle of the map
+    Centre,
+    Random,
+    /// One end of the longest walk across the map
+    LongestPath,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ExitPosition {
+    /// The floor the longest walk from the start
+    MostDistant,
+    /// Any floor but the start
+    Random,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    /// The left half is copied onto the right
+    Horizontal,
+    /// The bottom half is copied onto the top
+    Vertical,
+    Both,
+}
+
+/// A prefab stamped somewhere its walk from the start is between the
+/// distances
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct PrefabPlacement {
+    pub prefab: Prefab,
+    #[serde(default = "default_prefab_min_distance")]
+    pub min_distance: i32,
+    #[serde(default = "default_prefab_max_distance")]
+    pub max_distance: i32,
+}
+
+fn default_regions() -> usize {
+    16
+}
+
+impl MetaBuilder {
+    /// Region spawning over the default number of regions
+    pub fn region_spawns() -> Self {
+        Self::RegionSpawns {
+            regions: default_regions(),
+        }
+    }
+}
+
+fn default_prefab_min_distance() -> i32 {
+    20
+}
+
+fn default_prefab_max_distance() -> i32 {
+    2000
+}
+
+impl From<Prefab> for PrefabPlacement {
+    fn from(prefab: Prefab) -> Self {
+        Self {
+            prefab,
+            min_distance: default_prefab_min_distance(),
+            max_distance: default_prefab_max_distance(),
+        }
+    }
+}
+
+/// What happens to caverns the cave generator leaves cut off from the rest
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Disconnected {
+    /// Dig tunnels to them
+    Join,
+    /// Fill them in
+    #[default]
+    Cull,
+}
+
+/// Cellular automata rules, counted in walls among the eight neighbours
+/// with the edge of the map counting as wall
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct CaveSettings {
+    /// Chance in percent a tile starts as floor
+    pub percent_floor: u32,
+    /// Wall counts that turn a floor tile into wall
+    pub birth: Vec<usize>,
+    /// Wall counts that keep a wall tile a wall
+    pub survival: Vec<usize>,
+    /// Smoothing passes of the rules
+    pub passes: usize,
+    pub disconnected: Disconnected,
+}
+
+impl Default for CaveSettings {
+    fn default() -> Self {
+        Self {
+            percent_floor: 55,
+            birth: vec![5, 6, 7, 8],
+            survival: vec![4, 5, 6, 7, 8],
+            passes: 4,
+            disconnected: Disconnected::Cull,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Default, Serialize, Clone, Copy, PartialEq, Eq)]
@@ -104,6 +432,7 @@ pub enum ItemType {
     #[default]
     DungeonMap,
     Weapon,
+    Digging,
 }
 
 #[derive(Debug, Deserialize)]
@@ -120,6 +449,218 @@ pub struct ItemsSettings {
     pub winitem: EntitySettings,
 }
 
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Prefab {
+    Fortress,
+    Pillars,
+    Vault,
+}
+
+/// A bundle of look and content for a dungeon level
+#[derive(Debug, Deserialize)]
+pub struct ThemeSettings {
+    pub name: String,
+    /// Map depths the theme is picked for, 0 is the first map
+    pub levels: Vec<u32>,
+    /// Overrides of [`MapSettings::tile_sprites`]
+    #[serde(default)]
+    pub tile_sprites: HashMap<TileType, usize>,
+    pub architect: Option<Architect>,
+    /// Overrides [`ArchitectSettings::pipeline`]
+    pub pipeline: Option<Vec<MetaBuilder>>,
+    /// Overrides [`ArchitectSettings::fixed`]
+    pub fixed: Option<FixedLevel>,
+    /// Names of the monsters that can spawn, empty for all
+    #[serde(default)]
+    pub monsters: Vec<String>,
+    /// Names of the items that can spawn, empty for all
+    #[serde(default)]
+    pub items: Vec<String>,
+    pub prefabs: Option<Vec<Prefab>>,
+    /// Looped music track, relative to the assets directory
+    pub music: Option<String>,
+    /// Overrides [`LightingSettings::palette`]
+    pub palette: Option<Palette>,
+}
+
+/// A physical input that can trigger a game control
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    GamepadButton(GamepadButtonType),
+    /// A stick pushed most of the way along an axis
+    GamepadAxis(GamepadAxisType, AxisDirection),
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+/// Inputs bound to each game control, any of them triggers it
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct BindingSettings {
+    pub up: Vec<InputBinding>,
+    pub down: Vec<InputBinding>,
+    pub left: Vec<InputBinding>,
+    pub right: Vec<InputBinding>,
+    pub pick_up: Vec<InputBinding>,
+    pub interact: Vec<InputBinding>,
+    pub explore: Vec<InputBinding>,
+    pub wait: Vec<InputBinding>,
+    pub rest: Vec<InputBinding>,
+    /// Starts a count, the digits typed after it repeat the next action
+    pub count: Vec<InputBinding>,
+    /// Bindings for each digit of a count, 0 to 9 in order
+    pub digits: Vec<Vec<InputBinding>>,
+    pub zoom_in: Vec<InputBinding>,
+    pub zoom_out: Vec<InputBinding>,
+    /// Starts and ends looking around with a cursor
+    pub look: Vec<InputBinding>,
+    /// Bindings for each inventory slot, in order
+    pub use_item: Vec<Vec<InputBinding>>,
+}
+
+impl BindingSettings {
+    /// The bindings shipped in the default profile
+    pub fn defaults() -> Result<Self, ConfigError> {
+        Config::builder()
+            .add_source(File::with_name(&format!("{}/default", CONFIG_DIR)))
+            .build()?
+            .get("bindings")
+    }
+
+    fn validate(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        if self.digits.len() != 10 {
+            errors.push(ValidationError::new(
+                format!("{}.digits", path),
+                "must have bindings for each of 0 to 9",
+            ));
+        }
+    }
+
+    /// Write the bindings where the next [`Settings::load`] picks them up
+    pub fn save(&self) -> Result<(), String> {
+        #[derive(Serialize)]
+        struct BindingsFile<'a> {
+            bindings: &'a BindingSettings,
+        }
+        let json = serde_json::to_string_pretty(&BindingsFile { bindings: self })
+            .map_err(|e| e.to_string())?;
+        fs::write(format!("{}/{}.json", CONFIG_DIR, BINDINGS_FILE), json).map_err(|e| e.to_string())
+    }
+}
+
+/// Sounds played on game events and the music
+#[derive(Debug, Deserialize)]
+pub struct AudioSettings {
+    pub volume: VolumeSettings,
+    /// Seconds one music track takes to fade into the next
+    pub crossfade: f32,
+    /// Looped when the theme has no music, relative to the assets directory
+    pub music: Option<String>,
+    #[serde(default)]
+    pub cues: HashMap<SoundCue, CueSettings>,
+}
+
+/// Volumes from 0 to 1, music and effects are scaled by the master volume
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct VolumeSettings {
+    pub master: f64,
+    pub music: f64,
+    pub effects: f64,
+}
+
+impl VolumeSettings {
+    fn validate(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        [
+            ("master", self.master),
+            ("music", self.music),
+            ("effects", self.effects),
+        ]
+        .iter()
+        .filter(|(_, volume)| !(0.0..=1.0).contains(volume))
+        .for_each(|(name, _)| {
+            errors.push(ValidationError::new(
+                format!("{}.{}", path, name),
+                "must be between 0 and 1",
+            ))
+        });
+    }
+}
+
+/// Game events that can play a sound
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundCue {
+    /// The player steps onto a tile
+    Step,
+    /// Anything alive is hit
+    Hit,
+    PickUp,
+    UseItem,
+    QuestComplete,
+    NextLevel,
+    GameOver,
+    Victory,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct CueSettings {
+    /// Relative to the assets directory
+    pub path: String,
+    #[serde(default = "default_cue_scale")]
+    pub volume: f64,
+    /// Above 1 plays faster and higher, so one sound can serve a few cues
+    #[serde(default = "default_cue_scale")]
+    pub rate: f64,
+}
+
+fn default_cue_scale() -> f64 {
+    1.0
+}
+
+/// A sound or music file under the assets directory
+fn check_audio_file(file: &str, path: &str, errors: &mut Vec<ValidationError>) {
+    if !Path::new(ASSETS_DIR).join(file).is_file() {
+        errors.push(ValidationError::new(path, format!("no file {}", file)));
+    }
+}
+
+impl AudioSettings {
+    fn validate(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        self.volume.validate(&format!("{}.volume", path), errors);
+        if self.crossfade < 0.0 {
+            errors.push(ValidationError::new(
+                format!("{}.crossfade", path),
+                "must not be negative",
+            ));
+        }
+        if let Some(music) = &self.music {
+            check_audio_file(music, &format!("{}.music", path), errors);
+        }
+        let mut cues = self.cues.iter().collect::<Vec<_>>();
+        cues.sort_by_key(|(cue, _)| format!("{:?}", cue));
+        cues.into_iter().for_each(|(cue, settings)| {
+            let path = format!("{}.cues.{:?}", path, cue);
+            check_audio_file(&settings.path, &format!("{}.path", path), errors);
+            if settings.volume < 0.0 {
+                errors.push(ValidationError::new(
+                    format!("{}.volume", path),
+                    "must not be negative",
+                ));
+            }
+            if settings.rate <= 0.0 {
+                errors.push(ValidationError::new(
+                    format!("{}.rate", path),
+                    "must be above 0",
+                ));
+            }
+        });
+    }
+}
+
 #[derive(Debug, Deserialize, Resource)]
 pub struct Settings {
     pub tile_size: i32,
@@ -131,22 +672,77 @@ pub struct Settings {
     pub map_settings: MapSettings,
     pub items_settings: ItemsSettings,
     pub player_settings: ActorSettings,
+    #[serde(default)]
+    pub themes: Vec<ThemeSettings>,
+    pub bindings: BindingSettings,
+    pub audio: AudioSettings,
+    pub window: WindowSettings,
+    /// Size of the HUD and the menu windows
+    pub hud_scale: f32,
+    /// Every run starts from this seed when set, otherwise from a random one
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 impl Settings {
-    pub fn new() -> Result<Self, ConfigError> {
-        let run_mode = env::var("RUN_MODE").unwrap_or_else(|_| "development".into());
+    /// Load the settings the game plays with and check them, reporting
+    /// every problem found
+    pub fn load() -> Result<Self, SettingsError> {
+        Self::load_saved(&run_mode())
+    }
+
+    /// Load a profile with what the player saved from the game layered over it
+    pub fn load_saved(run_mode: &str) -> Result<Self, SettingsError> {
+        Self::check(run_mode, || Self::from_sources(CONFIG_DIR, run_mode, true))
+    }
+
+    /// Load a profile as it is checked in, so tests and checks give the same
+    /// result whatever the player saved
+    pub fn load_profile(run_mode: &str) -> Result<Self, SettingsError> {
+        Self::check(run_mode, || Self::from_profile(run_mode))
+    }
+
+    fn check(
+        run_mode: &str,
+        load: impl FnOnce() -> Result<Self, ConfigError>,
+    ) -> Result<Self, SettingsError> {
+        if run_mode != DEFAULT_RUN_MODE && !profiles().iter().any(|p| p == run_mode) {
+            return Err(SettingsError::UnknownProfile(run_mode.to_string()));
+        }
+        let settings = load().map_err(SettingsError::Load)?;
+        let errors = settings.validate();
+        if errors.is_empty() {
+            Ok(settings)
+        } else {
+            Err(SettingsError::Invalid(errors))
+        }
+    }
+
+    pub fn from_profile(run_mode: &str) -> Result<Self, ConfigError> {
+        Self::from_sources(CONFIG_DIR, run_mode, false)
+    }
 
-        let s = Config::builder()
+    /// Layer the profiles in `dir`, and the files saved from the game when
+    /// `saved` is set
+    fn from_sources(dir: &str, run_mode: &str, saved: bool) -> Result<Self, ConfigError> {
+        let mut builder = Config::builder()
             // Start off by merging in the "default" configuration file
-            .add_source(File::with_name("config/default"))
+            .add_source(File::with_name(&format!("{}/default", dir)))
             // Add in the current environment file
             // Default to 'development' env
             // Note that this file is _optional_
-            .add_source(File::with_name(&format!("config/{}", run_mode)).required(false))
+            .add_source(File::with_name(&format!("{}/{}", dir, run_mode)).required(false))
             // Add in a local configuration file
             // This file shouldn't be checked in to git
-            .add_source(File::with_name("config/local").required(false))
+            .add_source(File::with_name(&format!("{}/local", dir)).required(false));
+        if saved {
+            // Add in the options and bindings saved from the menu
+            for file in [VOLUME_FILE, PREFERENCES_FILE, BINDINGS_FILE] {
+                builder = builder
+                    .add_source(File::with_name(&format!("{}/{}", dir, file)).required(false));
+            }
+        }
+        let s = builder
             // Add in settings from the environment (with a prefix of APP)
             // Eg.. `APP_DEBUG=1 ./target/app` would set the `debug` key
             .add_source(Environment::with_prefix("cake"))
@@ -155,16 +751,955 @@ impl Settings {
         // You can deserialize (and thus freeze) the entire configuration as
         s.try_deserialize()
     }
+
+    /// Check the values that would otherwise crash the game later on
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        if self.tile_size < 1 {
+            errors.push(ValidationError::new("tile_size", "must be at least 1"));
+        }
+        if self.end_level < 1 {
+            errors.push(ValidationError::new("end_level", "must be at least 1"));
+        }
+        self.map_settings.validate("map_settings", &mut errors);
+        self.validate_torch_sprite(&mut errors);
+        self.audio.validate("audio", &mut errors);
+        self.bindings.validate("bindings", &mut errors);
+        self.window.validate("window", &mut errors);
+        if !(MIN_HUD_SCALE..=MAX_HUD_SCALE).contains(&self.hud_scale) {
+            errors.push(ValidationError::new(
+                "hud_scale",
+                format!("must be between {} and {}", MIN_HUD_SCALE, MAX_HUD_SCALE),
+            ));
+        }
+        self.validate_fixed(
+            matches!(self.map_settings.architect.architect, Architect::Fixed),
+            self.map_settings.architect.fixed.as_ref(),
+            "map_settings.architect.fixed",
+            &mut errors,
+        );
+        self.player_settings
+            .validate("player_settings", &mut errors);
+        self.monsters_settings
+            .monsters
+            .iter()
+            .enumerate()
+            .for_each(|(i, m)| {
+                m.actor.validate(
+                    &format!("monsters_settings.monsters[{}].actor", i),
+                    &mut errors,
+                )
+            });
+        self.npcs_settings
+            .npcs
+            .iter()
+            .enumerate()
+            .for_each(|(i, n)| {
+                let path = format!("npcs_settings.npcs[{}]", i);
+                n.actor.validate(&format!("{}.actor", path), &mut errors);
+                if let Some(reward) = n.quest.as_ref().and_then(|q| q.reward.as_ref()) {
+                    reward.validate(&format!("{}.quest.reward", path), &mut errors);
+                }
+            });
+        self.items_settings
+            .items
+            .iter()
+            .enumerate()
+            .for_each(|(i, item)| {
+                item.validate(&format!("items_settings.items[{}]", i), &mut errors)
+            });
+        self.items_settings
+            .winitem
+            .validate("items_settings.winitem", &mut errors);
+
+        self.themes.iter().enumerate().for_each(|(i, theme)| {
+            let path = format!("themes[{}]", i);
+            if self.themes[..i].iter().any(|t| t.name == theme.name) {
+                errors.push(ValidationError::new(
+                    format!("{}.name", path),
+                    format!("duplicate theme name {}", theme.name),
+                ));
+            }
+            theme.tile_sprites.iter().for_each(|(t, index)| {
+                check_sprite_index(
+                    *index,
+                    &format!("{}.tile_sprites.{:?}", path, t),
+                    &mut errors,
+                )
+            });
+            if let Some(pipeline) = &theme.pipeline {
+                validate_pipeline(pipeline, &format!("{}.pipeline", path), &mut errors);
+            }
+            if let Some(palette) = &theme.palette {
+                palette.validate(&format!("{}.palette", path), &mut errors);
+            }
+            if let Some(music) = &theme.music {
+                check_audio_file(music, &format!("{}.music", path), &mut errors);
+            }
+            // The map settings' level is checked on its own
+            self.validate_fixed(
+                matches!(theme.architect, Some(Architect::Fixed))
+                    && self.map_settings.architect.fixed.is_none(),
+                theme.fixed.as_ref(),
+                &format!("{}.fixed", path),
+                &mut errors,
+            );
+            theme.monsters.iter().enumerate().for_each(|(j, name)| {
+                if !self
+                    .monsters_settings
+                    .monsters
+                    .iter()
+                    .any(|m| &m.actor.entity.name == name)
+                {
+                    errors.push(ValidationError::new(
+                        format!("{}.monsters[{}]", path, j),
+                        format!("unknown monster {}", name),
+                    ));
+                }
+            });
+            theme.items.iter().enumerate().for_each(|(j, name)| {
+                if !self
+                    .items_settings
+                    .items
+                    .iter()
+                    .any(|item| &item.entity.name == name)
+                {
+                    errors.push(ValidationError::new(
+                        format!("{}.items[{}]", path, j),
+                        format!("unknown item {}", name),
+                    ));
+                }
+            });
+        });
+
+        // Spawning an npc or item panics if nothing is configured for the level
+        let architect = &self.map_settings.architect;
+        (0..self.end_level).for_each(|level| {
+            if architect.num_npcs > 0
+                && !self
+                    .npcs_settings
+                    .npcs
+                    .iter()
+                    .any(|n| n.actor.entity.levels.contains(&level))
+            {
+                errors.push(ValidationError::new(
+                    "npcs_settings.npcs",
+                    format!("no npc available for level {}", level),
+                ));
+            }
+            if architect.num_items > 0
+                && !self
+                    .items_settings
+                    .items
+                    .iter()
+                    .any(|i| i.entity.levels.contains(&level))
+            {
+                errors.push(ValidationError::new(
+                    "items_settings.items",
+                    format!("no item available for level {}", level),
+                ));
+            }
+        });
+
+        errors
+    }
+}
+
+impl Settings {
+    /// The level has to load and name monsters and items that exist
+    fn validate_fixed(
+        &self,
+        required: bool,
+        fixed: Option<&FixedLevel>,
+        path: &str,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let Some(fixed) = fixed else {
+            if required {
+                errors.push(ValidationError::new(
+                    path,
+                    "required for the Fixed architect",
+                ));
+            }
+            return;
+        };
+        if let Err(e) = MapBuilder::from_fixed(fixed) {
+            errors.push(ValidationError::new(format!("{}.path", path), e));
+        }
+        fixed.glyphs.iter().for_each(|(glyph, spawn)| {
+            let known = match spawn {
+                FixedSpawn::Monster(name) => self
+                    .monsters_settings
+                    .monsters
+                    .iter()
+                    .any(|m| &m.actor.entity.name == name),
+                FixedSpawn::Item(name) => self
+                    .items_settings
+                    .items
+                    .iter()
+                    .any(|item| &item.entity.name == name),
+            };
+            if !known {
+                errors.push(ValidationError::new(
+                    format!("{}.glyphs.{}", path, glyph),
+                    format!("unknown {:?}", spawn),
+                ));
+            }
+        });
+    }
+}
+
+/// `RUN_MODE`, or else the profile chosen in the options screen
+pub fn run_mode() -> String {
+    env::var("RUN_MODE")
+        .ok()
+        .or_else(saved_profile)
+        .unwrap_or_else(|| DEFAULT_RUN_MODE.into())
+}
+
+/// Profile saved in the preferences file, if any
+fn saved_profile() -> Option<String> {
+    #[derive(Deserialize)]
+    struct ProfileFile {
+        profile: Option<String>,
+    }
+    let json = fs::read_to_string(format!("{}/{}.json", CONFIG_DIR, PREFERENCES_FILE)).ok()?;
+    serde_json::from_str::<ProfileFile>(&json).ok()?.profile
+}
+
+/// Names of the profiles in the config directory, sorted
+pub fn profiles() -> Vec<String> {
+    let mut profiles = fs::read_dir(CONFIG_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| {
+                    let path = entry.ok()?.path();
+                    match path.extension()?.to_str()? {
+                        "yml" => Some(path.file_stem()?.to_str()?.to_string()),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    profiles.sort();
+    profiles
+}
+
+/// Smallest and largest HUD scale
+pub const MIN_HUD_SCALE: f32 = 0.5;
+pub const MAX_HUD_SCALE: f32 = 2.0;
+
+/// Smallest window the HUD still fits in
+pub const MIN_WINDOW: (f32, f32) = (640.0, 480.0);
+
+/// Size of the window when the game starts
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct WindowSettings {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl WindowSettings {
+    fn validate(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        [
+            ("width", self.width, MIN_WINDOW.0),
+            ("height", self.height, MIN_WINDOW.1),
+        ]
+        .iter()
+        .filter(|(_, size, min)| size < min)
+        .for_each(|(name, _, min)| {
+            errors.push(ValidationError::new(
+                format!("{}.{}", path, name),
+                format!("must be at least {}", min),
+            ))
+        });
+    }
+}
+
+/// What the options screen changes, saved as a layer over the profiles
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preferences {
+    /// Profile loaded when `RUN_MODE` is not set
+    pub profile: Option<String>,
+    pub window: WindowSettings,
+    pub hud_scale: f32,
+    pub seed: Option<u64>,
+    pub volume: VolumeSettings,
+}
+
+impl Preferences {
+    /// The options as they are now
+    pub fn current(settings: &Settings) -> Self {
+        Self {
+            profile: saved_profile(),
+            window: settings.window,
+            hud_scale: settings.hud_scale,
+            seed: settings.seed,
+            volume: settings.audio.volume,
+        }
+    }
+
+    /// Write the options where the next [`Settings::load`] picks them up
+    pub fn save(&self) -> Result<(), String> {
+        #[derive(Serialize)]
+        struct AudioFile<'a> {
+            volume: &'a VolumeSettings,
+        }
+        #[derive(Serialize)]
+        struct PreferencesFile<'a> {
+            profile: &'a Option<String>,
+            window: &'a WindowSettings,
+            hud_scale: f32,
+            seed: &'a Option<u64>,
+            audio: AudioFile<'a>,
+        }
+        let json = serde_json::to_string_pretty(&PreferencesFile {
+            profile: &self.profile,
+            window: &self.window,
+            hud_scale: self.hud_scale,
+            seed: &self.seed,
+            audio: AudioFile {
+                volume: &self.volume,
+            },
+        })
+        .map_err(|e| e.to_string())?;
+        fs::write(format!("{}/{}.json", CONFIG_DIR, PREFERENCES_FILE), json)
+            .map_err(|e| e.to_string())?;
+        // The volumes are in the preferences now
+        match fs::remove_file(format!("{}/{}.json", CONFIG_DIR, VOLUME_FILE)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Settings {
+    /// A torch drawn with a tile's or an item's sprite would be mistaken
+    /// for one
+    fn validate_torch_sprite(&self, errors: &mut Vec<ValidationError>) {
+        let torch = self.map_settings.lighting.torch_sprite;
+        let tiles = self
+            .map_settings
+            .tile_sprites
+            .iter()
+            .chain(self.themes.iter().flat_map(|t| t.tile_sprites.iter()))
+            .filter(|(_, index)| **index == torch)
+            .map(|(t, _)| format!("the {:?} tile", t));
+        let items = self
+            .items_settings
+            .items
+            .iter()
+            .map(|item| &item.entity)
+            .chain(std::iter::once(&self.items_settings.winitem))
+            .filter(|entity| entity.sprite_index == torch)
+            .map(|entity| entity.name.clone());
+        let mut shared = tiles.chain(items).collect::<Vec<_>>();
+        shared.sort();
+        shared.dedup();
+        if !shared.is_empty() {
+            errors.push(ValidationError::new(
+                "map_settings.lighting.torch_sprite",
+                format!("same sprite as {}", shared.join(", ")),
+            ));
+        }
+    }
+}
+
+impl MapSettings {
+    fn validate(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        // Hidden tiles are drawn with the sprite of the tile they look like
+        let drawn_tiles = TileType::ALL.into_iter().filter(|t| t.appearance() == *t);
+        drawn_tiles.for_each(|t| match self.tile_sprites.get(&t) {
+            Some(index) => {
+                check_sprite_index(*index, &format!("{}.tile_sprites.{:?}", path, t), errors)
+            }
+            None => errors.push(ValidationError::new(
+                format!("{}.tile_sprites.{:?}", path, t),
+                "missing sprite for tile",
+            )),
+        });
+        if self.width < 1 {
+            errors.push(ValidationError::new(
+                format!("{}.width", path),
+                "must be at least 1",
+            ));
+        }
+        if self.height < 1 {
+            errors.push(ValidationError::new(
+                format!("{}.height", path),
+                "must be at least 1",
+            ));
+        }
+        if self.wall_health < 1 {
+            errors.push(ValidationError::new(
+                format!("{}.wall_health", path),
+                "must be at least 1",
+            ));
+        }
+        if self.traps.spike_damage < 0 {
+            errors.push(ValidationError::new(
+                format!("{}.traps.spike_damage", path),
+                "must not be negative",
+            ));
+        }
+        if self.lighting.torch_radius < 1 {
+            errors.push(ValidationError::new(
+                format!("{}.lighting.torch_radius", path),
+                "must be at least 1",
+            ));
+        }
+        check_sprite_index(
+            self.lighting.torch_sprite,
+            &format!("{}.lighting.torch_sprite", path),
+            errors,
+        );
+        self.lighting
+            .palette
+            .validate(&format!("{}.lighting.palette", path), errors);
+        self.architect
+            .caves
+            .validate(&format!("{}.architect.caves", path), errors);
+        validate_pipeline(
+            &self.architect.pipeline,
+            &format!("{}.architect.pipeline", path),
+            errors,
+        );
+        if let Architect::Standard = self.architect.architect {
+            // The standard architect uses the entity distance as the max room size
+            if self.architect.entity_distance <= 2.0 {
+                errors.push(ValidationError::new(
+                    format!("{}.architect.entity_distance", path),
+                    "must be greater than 2 for the Standard architect",
+                ));
+            }
+        }
+    }
+}
+
+/// A pipeline of its own has to place the start and the exit
+fn validate_pipeline(pipeline: &[MetaBuilder], path: &str, errors: &mut Vec<ValidationError>) {
+    if pipeline.is_empty() {
+        return;
+    }
+    if !pipeline.iter().any(|m| matches!(m, MetaBuilder::Start(_))) {
+        errors.push(ValidationError::new(path, "must have a Start step"));
+    }
+    if !pipeline.iter().any(|m| matches!(m, MetaBuilder::Exit(_))) {
+        errors.push(ValidationError::new(path, "must have an Exit step"));
+    }
+    pipeline
+        .iter()
+        .enumerate()
+        .for_each(|(i, step)| match step {
+            MetaBuilder::Prefab(placement) if placement.min_distance >= placement.max_distance => {
+                errors.push(ValidationError::new(
+                    format!("{}[{}].max_distance", path, i),
+                    "must be greater than min_distance",
+                ))
+            }
+            MetaBuilder::RegionSpawns { regions: 0 } => errors.push(ValidationError::new(
+                format!("{}[{}].regions", path, i),
+                "must be at least 1",
+            )),
+            _ => (),
+        });
+}
+
+impl CaveSettings {
+    fn validate(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        if !(1..100).contains(&self.percent_floor) {
+            errors.push(ValidationError::new(
+                format!("{}.percent_floor", path),
+                "must be between 1 and 99",
+            ));
+        }
+        [("birth", &self.birth), ("survival", &self.survival)]
+            .iter()
+            .for_each(|(name, counts)| {
+                if counts.iter().any(|c| *c > 8) {
+                    errors.push(ValidationError::new(
+                        format!("{}.{}", path, name),
+                        "neighbour counts must be between 0 and 8",
+                    ));
+                }
+            });
+    }
+}
+
+impl ActorSettings {
+    fn validate(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        self.entity.validate(&format!("{}.entity", path), errors);
+        if self.max_health < 1 {
+            errors.push(ValidationError::new(
+                format!("{}.max_health", path),
+                "must be at least 1",
+            ));
+        }
+        if self.fov_radius < 1 {
+            errors.push(ValidationError::new(
+                format!("{}.fov_radius", path),
+                "must be at least 1",
+            ));
+        }
+        if let Some(regeneration) = self.regeneration {
+            if regeneration.turns < 1 {
+                errors.push(ValidationError::new(
+                    format!("{}.regeneration.turns", path),
+                    "must be at least 1",
+                ));
+            }
+            if regeneration.amount < 1 {
+                errors.push(ValidationError::new(
+                    format!("{}.regeneration.amount", path),
+                    "must be at least 1",
+                ));
+            }
+        }
+    }
+}
+
+impl EntitySettings {
+    fn validate(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        check_sprite_index(self.sprite_index, &format!("{}.sprite_index", path), errors);
+    }
+}
+
+impl ItemSettings {
+    fn validate(&self, path: &str, errors: &mut Vec<ValidationError>) {
+        self.entity.validate(&format!("{}.entity", path), errors);
+        if matches!(self.item_type, ItemType::Healing | ItemType::Digging)
+            && self.effect_amount.is_none()
+        {
+            errors.push(ValidationError::new(
+                format!("{}.effect_amount", path),
+                format!("required for {:?} items", self.item_type),
+            ));
+        }
+    }
+}
+
+fn check_sprite_index(index: usize, path: &str, errors: &mut Vec<ValidationError>) {
+    if index >= ATLAS_SPRITES {
+        errors.push(ValidationError::new(
+            path,
+            format!(
+                "sprite index {} is outside the texture atlas (max {})",
+                index,
+                ATLAS_SPRITES - 1
+            ),
+        ));
+    }
+}
+
+/// A single problem found in the settings, with the yaml path to the value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub path: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}: {}", self.path, self.message))
+    }
+}
+
+/// Failure to load the settings
+#[derive(Debug)]
+pub enum SettingsError {
+    /// The yaml could not be read or deserialized
+    Load(ConfigError),
+    /// The settings loaded but have invalid values
+    Invalid(Vec<ValidationError>),
+    /// There is no file for the profile
+    UnknownProfile(String),
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::Load(e) => f.write_fmt(format_args!("Could not load config: {}", e)),
+            SettingsError::Invalid(errors) => {
+                f.write_fmt(format_args!(
+                    "Invalid config, {} problem(s) found:",
+                    errors.len()
+                ))?;
+                errors
+                    .iter()
+                    .try_for_each(|e| f.write_fmt(format_args!("\n  {}", e)))
+            }
+            SettingsError::UnknownProfile(profile) => f.write_fmt(format_args!(
+                "Unknown config profile '{}', expected {} or one of {} in {}",
+                profile,
+                DEFAULT_RUN_MODE,
+                profiles().join(", "),
+                CONFIG_DIR
+            )),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Settings;
+    use std::{fs, path::Path};
+
+    use bevy::prelude::{GamepadAxisType, GamepadButtonType, KeyCode};
+    use config::{Config, File, FileFormat};
+
+    use super::{
+        is_settings_file, latest_modified, profiles, AxisDirection, BindingSettings, InputBinding,
+        MetaBuilder, Prefab, Settings, SettingsError, Symmetry, CONFIG_DIR,
+    };
+
+    fn with_override(yaml: &str) -> Settings {
+        Config::builder()
+            .add_source(File::with_name("config/default"))
+            .add_source(File::from_str(yaml, FileFormat::Yaml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap()
+    }
 
     #[test]
     fn test_load() {
-        let config = Settings::new();
+        let config = Settings::load();
         dbg!(&config);
         assert!(&config.is_ok());
     }
+
+    #[test]
+    fn test_bindings_override() {
+        let settings = with_override(
+            "
+bindings:
+    up: [{Key: Z}]
+    use_item: [[{GamepadAxis: [RightStickY, Negative]}]]
+    digits: [[{Key: Key0}]]
+",
+        );
+        assert_eq!(settings.bindings.up, vec![InputBinding::Key(KeyCode::Z)]);
+        assert_eq!(
+            settings.bindings.use_item,
+            vec![vec![InputBinding::GamepadAxis(
+                GamepadAxisType::RightStickY,
+                AxisDirection::Negative
+            )]]
+        );
+        assert_eq!(settings.bindings.down[0], InputBinding::Key(KeyCode::S));
+        let paths = settings
+            .validate()
+            .into_iter()
+            .map(|e| e.path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["bindings.digits"]);
+    }
+
+    #[test]
+    fn test_bindings_json_round_trip() {
+        let mut bindings = BindingSettings::defaults().unwrap();
+        bindings.up = vec![
+            InputBinding::Key(KeyCode::Z),
+            InputBinding::GamepadButton(GamepadButtonType::Other(7)),
+        ];
+        let json = serde_json::json!({ "bindings": &bindings }).to_string();
+        let loaded: BindingSettings = Config::builder()
+            .add_source(File::from_str(&json, FileFormat::Json))
+            .build()
+            .unwrap()
+            .get("bindings")
+            .unwrap();
+        assert_eq!(loaded, bindings);
+    }
+
+    #[test]
+    fn test_saved_files_only_in_game() {
+        let dir = std::env::temp_dir().join("cake_addict_test_saved_config");
+        fs::create_dir_all(&dir).unwrap();
+        fs::copy("config/default.yml", dir.join("default.yml")).unwrap();
+        fs::write(
+            dir.join("bindings.json"),
+            r#"{ "bindings": { "up": [{ "Key": "Z" }] } }"#,
+        )
+        .unwrap();
+        fs::write(dir.join("preferences.json"), r#"{ "hud_scale": 1.5 }"#).unwrap();
+        fs::write(
+            dir.join("volume.json"),
+            r#"{ "audio": { "volume": { "master": 0.5, "music": 0.25, "effects": 1.0 } } }"#,
+        )
+        .unwrap();
+        let dir = dir.to_str().unwrap();
+
+        let profile = Settings::from_sources(dir, "development", false).unwrap();
+        assert_eq!(profile.bindings.up[0], InputBinding::Key(KeyCode::W));
+        assert_eq!(profile.hud_scale, 1.0);
+        let saved = Settings::from_sources(dir, "development", true).unwrap();
+        assert_eq!(saved.bindings.up, vec![InputBinding::Key(KeyCode::Z)]);
+        assert_eq!(saved.hud_scale, 1.5);
+        assert_eq!(saved.audio.volume.music, 0.25);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_latest_modified() {
+        assert!(latest_modified(CONFIG_DIR).is_some());
+        assert!(latest_modified("no_such_dir").is_none());
+
+        assert!(is_settings_file(Path::new("config/mini.yml")));
+        assert!(is_settings_file(Path::new("config/bindings.json")));
+        assert!(is_settings_file(Path::new("config/preferences.json")));
+        assert!(is_settings_file(Path::new("config/volume.json")));
+        assert!(!is_settings_file(Path::new("config/high_scores.json")));
+        assert!(!is_settings_file(Path::new("config/notes.txt")));
+    }
+
+    #[test]
+    fn test_profiles_valid() {
+        ["development", "mini", "forest"]
+            .iter()
+            .for_each(|profile| {
+                let errors = Settings::from_profile(profile).unwrap().validate();
+                assert!(errors.is_empty(), "{}: {:?}", profile, errors);
+            });
+    }
+
+    #[test]
+    fn test_unknown_profile() {
+        let error = Settings::load_profile("no_such_profile").unwrap_err();
+        assert!(matches!(error, SettingsError::UnknownProfile(_)));
+        assert!(error.to_string().contains("mini"));
+        assert!(Settings::load_profile("mini").is_ok());
+    }
+
+    #[test]
+    fn test_validate_reports_paths() {
+        let settings = with_override(
+            "
+end_level: 0
+player_settings:
+    entity:
+        sprite_index: 300
+items_settings:
+    items:
+        - entity: { sprite_index: 33, name: Fries, levels: [0] }
+          item_type: Healing
+          proportion: 50
+themes: []
+",
+        );
+        let paths = settings
+            .validate()
+            .into_iter()
+            .map(|e| e.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "end_level",
+                "player_settings.entity.sprite_index",
+                "items_settings.items[0].effect_amount",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cave_validation() {
+        let settings = with_override(
+            "
+map_settings:
+    architect:
+        caves: { percent_floor: 100, birth: [9] }
+",
+        );
+        let paths = settings
+            .validate()
+            .into_iter()
+            .map(|e| e.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "map_settings.architect.caves.percent_floor",
+                "map_settings.architect.caves.birth",
+            ]
+        );
+        assert_eq!(settings.map_settings.architect.caves.passes, 4);
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let settings = with_override(
+            "
+map_settings:
+    architect:
+        pipeline: [
+            CullUnreachable,
+            { Mirror: Both },
+            { Prefab: { prefab: Vault, min_distance: 30, max_distance: 10 } },
+            { Prefab: { prefab: Fortress } },
+        ]
+",
+        );
+        let pipeline = &settings.map_settings.architect.pipeline;
+        assert_eq!(pipeline[1], MetaBuilder::Mirror(Symmetry::Both));
+        assert_eq!(pipeline[3], MetaBuilder::Prefab(Prefab::Fortress.into()));
+        let paths = settings
+            .validate()
+            .into_iter()
+            .map(|e| e.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "map_settings.architect.pipeline",
+                "map_settings.architect.pipeline",
+                "map_settings.architect.pipeline[2].max_distance",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_palette_validation() {
+        let settings = with_override(
+            "
+map_settings:
+    lighting:
+        torch_radius: 0
+themes:
+    - name: Dark
+      levels: []
+      palette: {
+          player: [1.0, 1.0, 1.0],
+          torch: [1.0, 0.5, 2.0],
+          ambient: [0.0, 0.0, 0.0],
+          remembered: [-0.1, 0.0, 0.0],
+      }
+",
+        );
+        let paths = settings
+            .validate()
+            .into_iter()
+            .map(|e| e.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "map_settings.lighting.torch_radius",
+                "themes[0].palette.torch",
+                "themes[0].palette.remembered",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_torch_sprite_validation() {
+        // The open door's and the Super Sword's sprite
+        let settings = with_override(
+            "
+map_settings:
+    lighting:
+        torch_sprite: 47
+",
+        );
+        let errors = settings.validate();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "map_settings.lighting.torch_sprite");
+        assert_eq!(
+            errors[0].message,
+            "same sprite as Super Sword, the DoorOpen tile"
+        );
+    }
+
+    #[test]
+    fn test_window_and_hud_validation() {
+        let settings = with_override(
+            "
+window: { width: 320.0, height: 600.0 }
+hud_scale: 3.0
+seed: 42
+",
+        );
+        assert_eq!(settings.seed, Some(42));
+        let paths = settings
+            .validate()
+            .into_iter()
+            .map(|e| e.path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["window.width", "hud_scale"]);
+        assert!(profiles().contains(&"mini".to_string()));
+    }
+
+    #[test]
+    fn test_audio_validation() {
+        let settings = with_override(
+            "
+audio:
+    volume: { master: 1.5, music: 0.3, effects: 0.5 }
+    crossfade: -1.0
+    cues:
+        Hit: { path: audio/missing.ogg }
+        Step: { path: audio/flying.ogg, volume: -0.5 }
+",
+        );
+        let paths = settings
+            .validate()
+            .into_iter()
+            .map(|e| e.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "audio.volume.master",
+                "audio.crossfade",
+                "audio.cues.Hit.path",
+                "audio.cues.Step.volume",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixed_validation() {
+        let settings = with_override(
+            "
+map_settings:
+    architect:
+        fixed: {
+            path: levels/dinner.txt,
+            glyphs: { B: { Monster: FatSam }, F: { Item: Fries } },
+        }
+",
+        );
+        let paths = settings
+            .validate()
+            .into_iter()
+            .map(|e| e.path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["map_settings.architect.fixed.glyphs.B"]);
+
+        let settings = with_override(
+            "
+map_settings:
+    architect:
+        architect: Fixed
+",
+        );
+        let paths = settings
+            .validate()
+            .into_iter()
+            .map(|e| e.path)
+            .collect::<Vec<_>>();
+        assert!(paths.contains(&"map_settings.architect.fixed".to_string()));
+    }
 }
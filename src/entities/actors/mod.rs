@@ -4,14 +4,14 @@ mod monsters;
 mod npc;
 mod player;
 
-use iyes_loopless::prelude::ConditionSet;
+use iyes_loopless::prelude::{ConditionSet, IntoConditionalSystem};
 pub use monsters::Monster;
 pub use npc::AvailableQuest;
-pub use npc::Npc;
 pub use player::MapLevel;
 pub use player::Player;
 
-use crate::components::health::Health;
+use crate::components::glyph::Glyph;
+use crate::components::health::{Health, Regeneration};
 use crate::components::map_position::MapPosition;
 use crate::components::name::EntityName;
 use crate::config::ActorSettings;
@@ -21,10 +21,13 @@ use crate::stages::GameStage;
 use crate::stages::TurnState;
 use crate::systems::chasing_player::chase_player;
 use crate::systems::combat::combat;
+use crate::systems::digging::dig;
 use crate::systems::fov::fov;
 use crate::systems::fov::FieldOfView;
-use crate::systems::movement::movement;
+use crate::systems::movement::{movement, MOVEMENT_LABEL};
 use crate::systems::random_actor::random_move;
+use crate::systems::regeneration::regenerate;
+use crate::systems::tile_effects::tile_effects;
 
 use self::monsters::MonstersPlugin;
 use self::npc::NPCsPlugin;
@@ -53,13 +56,15 @@ impl Plugin for ActorsPlugin {
                     .run_if_resource_equals(TurnState::NPCsTurn)
                     .with_system(activate)
                     .with_system(combat)
+                    .with_system(dig)
                     .into(),
             )
             .add_system_set_to_stage(
                 GameStage::MoveNPCs,
                 ConditionSet::new()
                     .run_if_resource_equals(TurnState::NPCsTurn)
-                    .with_system(movement)
+                    .with_system(movement.into_conditional().label(MOVEMENT_LABEL))
+                    .with_system(tile_effects.into_conditional().after(MOVEMENT_LABEL))
                     .into(),
             )
             .add_system_set_to_stage(
@@ -67,6 +72,7 @@ impl Plugin for ActorsPlugin {
                 ConditionSet::new()
                     .run_if_resource_equals(TurnState::NPCsTurn)
                     .with_system(fov)
+                    .with_system(regenerate)
                     .with_system(end_turn)
                     .into(),
             );
@@ -80,18 +86,13 @@ pub struct ActorBundle {
     pub interactive: Interactive,
     pub fov: FieldOfView,
     pub health: Health,
-    #[bundle]
-    sprite: SpriteSheetBundle,
+    pub regeneration: Regeneration,
+    pub glyph: Glyph,
+    pub visibility: Visibility,
 }
 
 impl ActorBundle {
-    fn from_settings(
-        settings: &ActorSettings,
-        position: MapPosition,
-        texture_atlas: &Handle<TextureAtlas>,
-        z_level: f32,
-        tile_size: i32,
-    ) -> Self {
+    fn from_settings(settings: &ActorSettings, position: MapPosition) -> Self {
         Self {
             name: EntityName(settings.entity.name.clone()),
             position,
@@ -99,23 +100,13 @@ impl ActorBundle {
                 current: settings.max_health,
                 max: settings.max_health,
             },
+            regeneration: Regeneration::new(settings.regeneration),
             interactive: Interactive {
                 text: format!("{} hp:{}", &settings.entity.name, settings.max_health),
             },
             fov: FieldOfView::new(settings.fov_radius),
-            sprite: SpriteSheetBundle {
-                transform: Transform {
-                    translation: position.translation(z_level, tile_size),
-                    ..default()
-                },
-                texture_atlas: texture_atlas.clone(),
-                sprite: TextureAtlasSprite {
-                    index: settings.entity.sprite_index,
-                    ..default()
-                },
-
-                ..default()
-            },
+            glyph: Glyph(settings.entity.sprite_index),
+            visibility: default(),
         }
     }
 }
@@ -1,14 +1,15 @@
 use crate::cleanup::cleanup_components;
 use crate::components::damage::Damage;
 use crate::components::map_position::MapPosition;
-use crate::config::{Behaviour, MonsterSettings, MonstersSettings, Settings};
-use crate::entities::RESPAWN_LABEL;
-use crate::loading::TextureAtlasAssets;
+use crate::config::{Behaviour, FixedSpawn, MonsterSettings, Settings};
+use crate::entities::{RESPAWN_LABEL, SPAWN_MONSTERS_LABEL};
 use crate::map::map_builder::MapBuilder;
 use crate::map::GEN_MAP_LABEL;
 use crate::stages::TurnState;
 use crate::systems::chasing_player::ChasingPlayer;
+use crate::systems::digging::SmashesWalls;
 use crate::systems::random_actor::RandomMover;
+use crate::theme::ActiveTheme;
 use crate::GameState;
 
 use bevy::prelude::*;
@@ -23,25 +24,29 @@ pub struct MonstersPlugin;
 /// Player logic is only active during the State `GameState::Playing`
 impl Plugin for MonstersPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_monsters))
-            .add_system_set(
-                SystemSet::on_update(GameState::Playing).with_system(
-                    spawn_monsters
-                        .run_if_resource_equals(TurnState::NextLevel)
-                        .label(RESPAWN_LABEL)
-                        .after(GEN_MAP_LABEL),
-                ),
-            )
-            .add_system_set(
-                SystemSet::on_update(GameState::Playing).with_system(
-                    cleanup_components::<Monster>
-                        .run_if_resource_equals(TurnState::NextLevel)
-                        .before(GEN_MAP_LABEL),
-                ),
-            )
-            .add_system_set(
-                SystemSet::on_exit(GameState::Playing).with_system(cleanup_components::<Monster>),
-            );
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Playing)
+                .with_system(spawn_monsters.label(SPAWN_MONSTERS_LABEL)),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing).with_system(
+                spawn_monsters
+                    .run_if_resource_equals(TurnState::NextLevel)
+                    .label(RESPAWN_LABEL)
+                    .label(SPAWN_MONSTERS_LABEL)
+                    .after(GEN_MAP_LABEL),
+            ),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing).with_system(
+                cleanup_components::<Monster>
+                    .run_if_resource_equals(TurnState::NextLevel)
+                    .before(GEN_MAP_LABEL),
+            ),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Playing).with_system(cleanup_components::<Monster>),
+        );
     }
 }
 #[derive(Component, Default)]
@@ -57,29 +62,65 @@ pub struct MonsterBundle {
 
 fn spawn_monsters(
     mut commands: Commands,
-    textures: Res<TextureAtlasAssets>,
     map_builder: Res<MapBuilder>,
     mut rng: ResMut<GlobalRng>,
     settings: Res<Settings>,
+    theme: Res<ActiveTheme>,
     map_level: Query<&MapLevel>,
 ) {
-    let monster_settings = &settings.monsters_settings;
-    map_builder.monster_spawns.iter().for_each(|position| {
-        let rng_comp = RngComponent::from(&mut rng);
-        spawn_monster(
-            &mut commands,
-            *position,
-            &textures,
-            rng_comp,
-            monster_settings,
-            settings.tile_size,
-            settings.entity_z_level,
-            match map_level.get_single() {
-                Ok(res) => res.value,
-                Err(_) => 0,
-            },
-        );
-    });
+    let map_level = match map_level.get_single() {
+        Ok(res) => res.value,
+        Err(_) => 0,
+    };
+    let level_monsters = settings
+        .monsters_settings
+        .monsters
+        .iter()
+        .filter(|s| s.actor.entity.levels.contains(&map_level))
+        .collect::<Vec<_>>();
+    // Fall back to every monster for the level if the theme allows none of them
+    let theme_monsters = level_monsters
+        .iter()
+        .filter(|s| theme.allows_monster(&settings, &s.actor.entity.name))
+        .copied()
+        .collect::<Vec<_>>();
+    let level_monsters = if theme_monsters.is_empty() {
+        &level_monsters
+    } else {
+        &theme_monsters
+    };
+    MapBuilder::in_order(&map_builder.monster_spawns)
+        .into_iter()
+        .for_each(|position| {
+            let mut rng_comp = RngComponent::from(&mut rng);
+            if let Some(config) = rng_comp.weighted_sample(level_monsters, weights) {
+                spawn_monster(&mut commands, position, rng_comp, config);
+            }
+        });
+    // Hand-made levels name their monsters, whatever the level or theme
+    map_builder
+        .fixed_spawns
+        .iter()
+        .filter_map(|(position, spawn)| match spawn {
+            FixedSpawn::Monster(name) => Some((position, name)),
+            FixedSpawn::Item(_) => None,
+        })
+        .for_each(|(position, name)| {
+            let config = settings
+                .monsters_settings
+                .monsters
+                .iter()
+                .find(|m| &m.actor.entity.name == name);
+            match config {
+                Some(config) => spawn_monster(
+                    &mut commands,
+                    *position,
+                    RngComponent::from(&mut rng),
+                    config,
+                ),
+                None => error!("No monster called {}", name),
+            }
+        });
 }
 
 fn weights(setting: &&MonsterSettings) -> f64 {
@@ -89,33 +130,20 @@ fn weights(setting: &&MonsterSettings) -> f64 {
 fn spawn_monster(
     commands: &mut Commands,
     position: MapPosition,
-    textures: &Res<TextureAtlasAssets>,
-    mut rng: RngComponent,
-    settings: &MonstersSettings,
-    tile_size: i32,
-    z_level: f32,
-    map_level: u32,
+    rng: RngComponent,
+    config: &MonsterSettings,
 ) {
-    let level_monsters = &settings
-        .monsters
-        .iter()
-        .filter(|s| s.actor.entity.levels.contains(&map_level))
-        .collect::<Vec<_>>();
-    if let Some(config) = rng.weighted_sample(level_monsters, weights) {
-        let mut monster = commands.spawn(MonsterBundle {
-            actor: ActorBundle::from_settings(
-                &config.actor,
-                position,
-                &textures.texture_atlas,
-                z_level,
-                tile_size,
-            ),
-            damage: Damage(config.actor.entity.base_damage.unwrap_or(0)),
-            ..default()
-        });
-        match &config.behaviour {
-            Behaviour::Random => monster.insert(RandomMover { rng }),
-            Behaviour::Chasing => monster.insert(ChasingPlayer {}),
-        };
-    }
+    let damage = config.actor.entity.base_damage.unwrap_or(0);
+    let mut monster = commands.spawn(MonsterBundle {
+        actor: ActorBundle::from_settings(&config.actor, position),
+        damage: Damage(damage),
+        ..default()
+    });
+    match &config.behaviour {
+        Behaviour::Random => monster.insert(RandomMover { rng }),
+        Behaviour::Chasing => monster.insert(ChasingPlayer {}),
+        Behaviour::Smashing => monster
+            .insert(ChasingPlayer {})
+            .insert(SmashesWalls { power: damage }),
+    };
 }
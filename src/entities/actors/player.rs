@@ -4,15 +4,17 @@ use crate::components::map_position::MapPosition;
 use crate::config::Settings;
 use crate::entities::items::activate;
 use crate::entities::RESPAWN_LABEL;
-use crate::loading::TextureAtlasAssets;
 use crate::map::map_builder::MapBuilder;
 use crate::map::GEN_MAP_LABEL;
 use crate::stages::{end_turn, GameStage, TurnState};
 use crate::systems::combat::combat;
+use crate::systems::digging::dig;
 use crate::systems::fov::{fov, set_fov_visibility, FieldOfView};
+use crate::systems::lighting::LightSource;
 
-use crate::systems::movement::movement;
+use crate::systems::movement::{movement, MOVEMENT_LABEL};
 use crate::systems::quest_engine::interact_quest_giver;
+use crate::systems::tile_effects::tile_effects;
 use crate::GameState;
 
 use bevy::prelude::*;
@@ -45,7 +47,9 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_player))
             .add_system_set(
-                SystemSet::on_update(GameState::Playing).with_system(set_fov_visibility),
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(set_fov_visibility)
+                    .with_system(update_player_fov_radius),
             )
             .add_system_set_to_stage(
                 GameStage::PlayerCombat,
@@ -53,6 +57,7 @@ impl Plugin for PlayerPlugin {
                     .run_if_resource_equals(TurnState::PlayerTurn)
                     .with_system(activate)
                     .with_system(combat)
+                    .with_system(dig)
                     .with_system(interact_quest_giver)
                     .into(),
             )
@@ -60,7 +65,8 @@ impl Plugin for PlayerPlugin {
                 GameStage::MovePlayer,
                 ConditionSet::new()
                     .run_if_resource_equals(TurnState::PlayerTurn)
-                    .with_system(movement)
+                    .with_system(movement.into_conditional().label(MOVEMENT_LABEL))
+                    .with_system(tile_effects.into_conditional().after(MOVEMENT_LABEL))
                     .into(),
             )
             .add_system_set_to_stage(
@@ -85,43 +91,44 @@ impl Plugin for PlayerPlugin {
     }
 }
 
-fn spawn_player(
-    mut commands: Commands,
-    textures: Res<TextureAtlasAssets>,
-    map_builder: Res<MapBuilder>,
-    settings: Res<Settings>,
-) {
+fn spawn_player(mut commands: Commands, map_builder: Res<MapBuilder>, settings: Res<Settings>) {
     let player_start = map_builder.player_start;
-    let mut fov = FieldOfView::new(settings.player_settings.fov_radius);
-    fov.update(player_start, &map_builder.map);
-    commands.spawn(PlayerBundle {
-        damage: Damage(settings.player_settings.entity.base_damage.unwrap_or(0)),
-        actor: ActorBundle::from_settings(
-            &settings.player_settings,
-            player_start,
-            &textures.texture_atlas,
-            settings.entity_z_level,
-            settings.tile_size,
-        ),
-        ..default()
-    });
+    // Seen from the start, not only after the first turn
+    let mut actor = ActorBundle::from_settings(&settings.player_settings, player_start);
+    actor.fov.update(player_start, &map_builder.map);
+    commands
+        .spawn(PlayerBundle {
+            damage: Damage(settings.player_settings.entity.base_damage.unwrap_or(0)),
+            actor,
+            ..default()
+        })
+        .insert(LightSource::Player);
 }
 
 fn player_next_level(
-    mut player_location: Query<(
-        &mut MapPosition,
-        &mut Transform,
-        &mut FieldOfView,
-        With<Player>,
-    )>,
+    mut player_location: Query<(&mut MapPosition, &mut FieldOfView), With<Player>>,
     map_builder: Res<MapBuilder>,
     settings: Res<Settings>,
 ) {
-    let (mut pos, mut trans, mut fov, _) = player_location.single_mut();
+    let (mut pos, mut fov) = player_location.single_mut();
     *pos = map_builder.player_start;
-    trans.translation = map_builder
-        .player_start
-        .translation(trans.translation.z, settings.tile_size);
     *fov = FieldOfView::new(settings.player_settings.fov_radius);
     fov.update(*pos, &map_builder.map);
 }
+
+/// Apply a reloaded fov radius to the player straight away
+fn update_player_fov_radius(
+    mut player: Query<(&MapPosition, &mut FieldOfView), With<Player>>,
+    map_builder: Res<MapBuilder>,
+    settings: Res<Settings>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    player.iter_mut().for_each(|(pos, mut fov)| {
+        if fov.radius != settings.player_settings.fov_radius {
+            fov.radius = settings.player_settings.fov_radius;
+            fov.update(*pos, &map_builder.map);
+        }
+    });
+}
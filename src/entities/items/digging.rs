@@ -0,0 +1,7 @@
+use bevy::prelude::Component;
+
+/// Lets the carrier dig through walls, taking `power` wall health per turn
+#[derive(Component)]
+pub struct ProvidesDigging {
+    pub power: i32,
+}
@@ -5,25 +5,27 @@ use iyes_loopless::prelude::IntoConditionalSystem;
 use crate::{
     cleanup::cleanup_components,
     components::{damage::Damage, health::Health, map_position::MapPosition},
-    config::{ItemSettings, ItemType, Settings},
-    loading::TextureAtlasAssets,
+    config::{FixedSpawn, ItemSettings, ItemType, Settings},
     map::{map_builder::MapBuilder, GEN_MAP_LABEL},
     stages::TurnState,
+    theme::ActiveTheme,
     GameState,
 };
 
 use self::winitem::spawn_wintitem;
+mod digging;
 mod dungeonmap;
 mod healing;
 mod weapon;
 mod winitem;
 
+pub use digging::ProvidesDigging;
 pub use dungeonmap::ProvidesMap;
 pub use healing::ProvidesHealing;
 pub use weapon::Weapon;
 pub use winitem::WinItem;
 
-use super::{GameEntityBundle, MapLevel, RESPAWN_LABEL};
+use super::{GameEntityBundle, MapLevel, RESPAWN_LABEL, SPAWN_NPCS_LABEL};
 
 pub struct ItemsPlugin;
 
@@ -32,7 +34,7 @@ impl Plugin for ItemsPlugin {
         app.add_system_set(
             SystemSet::on_enter(GameState::Playing)
                 .with_system(spawn_wintitem)
-                .with_system(spawn_items),
+                .with_system(spawn_items.after(SPAWN_NPCS_LABEL)),
         )
         .add_system_set(
             SystemSet::on_exit(GameState::Playing).with_system(cleanup_components::<Item>),
@@ -49,6 +51,7 @@ impl Plugin for ItemsPlugin {
                     spawn_items
                         .run_if_resource_equals(TurnState::NextLevel)
                         .label(RESPAWN_LABEL)
+                        .after(SPAWN_NPCS_LABEL)
                         .after(GEN_MAP_LABEL),
                 ),
         )
@@ -75,13 +78,13 @@ pub struct ItemBundle {
 
 fn spawn_items(
     mut commands: Commands,
-    textures: Res<TextureAtlasAssets>,
     map_builder: Res<MapBuilder>,
     mut rng: ResMut<GlobalRng>,
     settings: Res<Settings>,
+    theme: Res<ActiveTheme>,
     map_level: Query<&MapLevel>,
 ) {
-    let level_items = &settings
+    let level_items = settings
         .items_settings
         .items
         .iter()
@@ -92,39 +95,53 @@ fn spawn_items(
             })
         })
         .collect::<Vec<_>>();
-    map_builder.item_spawns.iter().for_each(|position| {
-        let config = rng.weighted_sample(level_items, weights).unwrap();
-        spawn_item(
-            &mut commands,
-            *position,
-            &textures,
-            config,
-            settings.tile_size,
-            settings.entity_z_level,
-        );
-    });
+    // Fall back to every item for the level if the theme allows none of them
+    let theme_items = level_items
+        .iter()
+        .filter(|s| theme.allows_item(&settings, &s.entity.name))
+        .copied()
+        .collect::<Vec<_>>();
+    let level_items = if theme_items.is_empty() {
+        &level_items
+    } else {
+        &theme_items
+    };
+    MapBuilder::in_order(&map_builder.item_spawns)
+        .into_iter()
+        .for_each(|position| {
+            let config = rng.weighted_sample(level_items, weights).unwrap();
+            spawn_item(&mut commands, position, config);
+        });
+    // Hand-made levels name their items, whatever the level or theme
+    map_builder
+        .fixed_spawns
+        .iter()
+        .filter_map(|(position, spawn)| match spawn {
+            FixedSpawn::Item(name) => Some((position, name)),
+            FixedSpawn::Monster(_) => None,
+        })
+        .for_each(|(position, name)| {
+            match settings
+                .items_settings
+                .items
+                .iter()
+                .find(|item| &item.entity.name == name)
+            {
+                Some(config) => {
+                    spawn_item(&mut commands, *position, config);
+                }
+                None => error!("No item called {}", name),
+            }
+        });
 }
 
 fn weights(setting: &&ItemSettings) -> f64 {
     0.01 * setting.proportion
 }
 
-fn spawn_item(
-    commands: &mut Commands,
-    position: MapPosition,
-    textures: &Res<TextureAtlasAssets>,
-    config: &ItemSettings,
-    tile_size: i32,
-    z_level: f32,
-) -> Entity {
+fn spawn_item(commands: &mut Commands, position: MapPosition, config: &ItemSettings) -> Entity {
     let mut item = commands.spawn(ItemBundle {
-        entity: GameEntityBundle::from_settings(
-            &config.entity,
-            position,
-            &textures.texture_atlas,
-            z_level,
-            tile_size,
-        ),
+        entity: GameEntityBundle::from_settings(&config.entity, position),
         ..default()
     });
     insert_item_type(
@@ -148,6 +165,9 @@ pub fn insert_item_type(
         }),
         ItemType::DungeonMap => item.insert(ProvidesMap),
         ItemType::Weapon => item.insert(Weapon).insert(Damage(base_damage.unwrap_or(0))),
+        ItemType::Digging => item.insert(ProvidesDigging {
+            power: effect_amount.unwrap(),
+        }),
     };
 }
 
@@ -163,12 +183,14 @@ pub fn activate(
     mut healths: Query<&mut Health>,
     provides_healing: Query<&ProvidesHealing>,
     provides_map: Query<&ProvidesMap>,
+    provides_digging: Query<&ProvidesDigging>,
     items: Query<&Item>,
     mut visibility_query: Query<(&mut Visibility, With<MapPosition>)>,
 ) {
     let mut to_heal = HashMap::new();
     activation_events.iter().for_each(|event| {
-        if items.contains(event.item) {
+        // Digging tools are used by walking into walls, not used up
+        if items.contains(event.item) && !provides_digging.contains(event.item) {
             // healing
             if let Ok(healing) = provides_healing.get(event.item) {
                 info!("used healing");
@@ -191,7 +213,7 @@ pub fn activate(
 
     to_heal.iter().for_each(|(entity, heal_amount)| {
         if let Ok(mut health) = healths.get_mut(*entity) {
-            health.current = health.max.min(health.current + heal_amount);
+            health.heal(*heal_amount);
         }
     });
 }
@@ -3,7 +3,6 @@ use bevy::prelude::*;
 use crate::{
     config::Settings,
     entities::{GameEntityBundle, MapLevel},
-    loading::TextureAtlasAssets,
     map::map_builder::MapBuilder,
 };
 
@@ -14,7 +13,6 @@ pub struct WinItem;
 
 pub fn spawn_wintitem(
     mut commands: Commands,
-    textures: Res<TextureAtlasAssets>,
     map_builder: Res<MapBuilder>,
     settings: Res<Settings>,
     player_level: Query<&MapLevel>,
@@ -29,9 +27,6 @@ pub fn spawn_wintitem(
             entity: GameEntityBundle::from_settings(
                 &settings.items_settings.winitem,
                 map_builder.winitem_start,
-                &textures.texture_atlas,
-                settings.entity_z_level,
-                settings.tile_size,
             ),
             ..default()
         })
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 
+use crate::components::glyph::Glyph;
 use crate::components::map_position::MapPosition;
 use crate::components::name::EntityName;
 use crate::config::EntitySettings;
@@ -17,19 +18,19 @@ mod tile;
 pub use actors::AvailableQuest;
 pub use actors::MapLevel;
 pub use actors::Monster;
-pub use actors::Npc;
 pub use actors::Player;
 pub use items::ActivateItem;
 pub use items::Item;
+pub use items::ProvidesDigging;
 pub use items::ProvidesHealing;
 pub use items::ProvidesMap;
 pub use items::Weapon;
 pub use items::WinItem;
 pub use quest::FetchItem;
-pub use quest::Quest;
 pub use quest::QuestState;
 pub use quest::Reward;
 pub use tile::Tile;
+pub use tile::TileEffect;
 pub use tile::TileType;
 
 pub struct EntitiesPlugin;
@@ -48,39 +49,27 @@ pub struct GameEntityBundle {
     pub name: EntityName,
     pub position: MapPosition,
     pub interactive: Interactive,
-    #[bundle]
-    sprite: SpriteSheetBundle,
+    pub glyph: Glyph,
+    pub visibility: Visibility,
 }
 
 pub const RESPAWN_LABEL: &str = "RespawnEntities";
 
+/// Labels keeping the spawns that draw from the shared rng in order,
+/// so a seed always gives the same level
+pub const SPAWN_MONSTERS_LABEL: &str = "SpawnMonsters";
+pub const SPAWN_NPCS_LABEL: &str = "SpawnNPCs";
+
 impl GameEntityBundle {
-    fn from_settings(
-        settings: &EntitySettings,
-        position: MapPosition,
-        texture_atlas: &Handle<TextureAtlas>,
-        z_level: f32,
-        tile_size: i32,
-    ) -> Self {
+    fn from_settings(settings: &EntitySettings, position: MapPosition) -> Self {
         Self {
             name: EntityName(settings.name.clone()),
             position,
             interactive: Interactive {
                 text: settings.name.clone(),
             },
-            sprite: SpriteSheetBundle {
-                transform: Transform {
-                    translation: position.translation(z_level, tile_size),
-                    ..default()
-                },
-                texture_atlas: texture_atlas.clone(),
-                sprite: TextureAtlasSprite {
-                    index: settings.sprite_index,
-                    ..default()
-                },
-
-                ..default()
-            },
+            glyph: Glyph(settings.sprite_index),
+            visibility: default(),
         }
     }
 }
@@ -13,7 +13,7 @@ impl Plugin for QuestPlugin {
     fn build(&self, _app: &mut App) {}
 }
 
-#[derive(Debug, Component, PartialEq, Eq)]
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq)]
 pub enum QuestState {
     Todo,
     Updated,
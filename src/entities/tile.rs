@@ -6,11 +6,11 @@ use serde::Deserialize;
 
 use crate::{
     cleanup::cleanup_components,
-    components::map_position::MapPosition,
+    components::{glyph::Glyph, health::Health, map_position::MapPosition},
     config::Settings,
-    loading::TextureAtlasAssets,
     map::{map_builder::MapBuilder, GEN_MAP_LABEL},
     stages::TurnState,
+    theme::ActiveTheme,
     GameState,
 };
 
@@ -21,6 +21,9 @@ pub struct TilePlugin;
 impl Plugin for TilePlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_map))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(update_tile_sprites),
+            )
             .add_system_set(
                 SystemSet::on_update(GameState::Playing).with_system(
                     spawn_map
@@ -48,23 +51,106 @@ pub enum TileType {
     #[default]
     Floor,
     Exit,
+    DoorClosed,
+    DoorOpen,
+    /// Spike trap that looks like floor until it is triggered
+    HiddenSpikes,
+    Spikes,
+    /// Opens the secret walls around it when entered
+    PressurePlate,
+    /// Wall that a pressure plate can open
+    SecretWall,
 }
 
-impl Display for TileType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// What happens when an entity enters a tile
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TileEffect {
+    OpenDoor,
+    Spikes,
+    OpenSecretWalls,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TileProperties {
+    pub walkable: bool,
+    pub blocks_sight: bool,
+    pub on_enter: Option<TileEffect>,
+}
+
+impl TileType {
+    pub const ALL: [TileType; 9] = [
+        TileType::Wall,
+        TileType::Floor,
+        TileType::Exit,
+        TileType::DoorClosed,
+        TileType::DoorOpen,
+        TileType::HiddenSpikes,
+        TileType::Spikes,
+        TileType::PressurePlate,
+        TileType::SecretWall,
+    ];
+
+    pub fn properties(&self) -> TileProperties {
+        let (walkable, blocks_sight, on_enter) = match self {
+            TileType::Wall | TileType::SecretWall => (false, true, None),
+            TileType::Floor | TileType::Exit | TileType::DoorOpen => (true, false, None),
+            TileType::DoorClosed => (true, true, Some(TileEffect::OpenDoor)),
+            TileType::HiddenSpikes | TileType::Spikes => (true, false, Some(TileEffect::Spikes)),
+            TileType::PressurePlate => (true, false, Some(TileEffect::OpenSecretWalls)),
+        };
+        TileProperties {
+            walkable,
+            blocks_sight,
+            on_enter,
+        }
+    }
+
+    /// The tile this one is drawn as, hidden tiles look like ordinary ones
+    pub fn appearance(&self) -> TileType {
         match self {
-            TileType::Wall => f.write_fmt(format_args!("#")),
-            TileType::Floor => f.write_fmt(format_args!(".")),
-            TileType::Exit => f.write_fmt(format_args!(">")),
+            TileType::HiddenSpikes => TileType::Floor,
+            TileType::SecretWall => TileType::Wall,
+            _ => *self,
+        }
+    }
+
+    pub fn glyph(&self) -> char {
+        match self {
+            TileType::Wall => '#',
+            TileType::Floor => '.',
+            TileType::Exit => '>',
+            TileType::DoorClosed => '+',
+            TileType::DoorOpen => '/',
+            TileType::HiddenSpikes => ',',
+            TileType::Spikes => '^',
+            TileType::PressurePlate => '_',
+            TileType::SecretWall => '%',
         }
     }
 }
 
+impl TryFrom<char> for TileType {
+    type Error = char;
+
+    fn try_from(glyph: char) -> Result<Self, Self::Error> {
+        TileType::ALL
+            .into_iter()
+            .find(|t| t.glyph() == glyph)
+            .ok_or(glyph)
+    }
+}
+
+impl Display for TileType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}", self.glyph()))
+    }
+}
+
 pub fn spawn_map(
     mut commands: Commands,
-    textures: Res<TextureAtlasAssets>,
     map_builder: Res<MapBuilder>,
     settings: Res<Settings>,
+    theme: Res<ActiveTheme>,
 ) {
     map_builder
         .map
@@ -72,14 +158,39 @@ pub fn spawn_map(
         .indexed_iter()
         .for_each(|((y, x), t)| {
             let position = MapPosition::new(x.try_into().unwrap(), y.try_into().unwrap());
-            commands.spawn(TileBundle::new(
+            let mut tile = commands.spawn(TileBundle::new(
                 position,
-                textures.as_ref(),
                 *t,
-                settings.tile_size,
-                settings.map_settings.z_level,
-                settings.map_settings.tile_sprites[t],
+                theme.tile_sprite(&settings, t),
             ));
+            if map_builder.map.is_diggable(position) {
+                let wall_health = settings.map_settings.wall_health;
+                tile.insert(Health {
+                    current: wall_health,
+                    max: wall_health,
+                });
+            }
+        });
+}
+
+/// Apply reloaded tile sprites to the current level, and redraw
+/// tiles changed during play
+fn update_tile_sprites(
+    settings: Res<Settings>,
+    theme: Res<ActiveTheme>,
+    mut tiles: Query<(&TileType, ChangeTrackers<TileType>, &mut Glyph), With<Tile>>,
+) {
+    let reload = settings.is_changed();
+    tiles
+        .iter_mut()
+        .for_each(|(tile_type, tracker, mut glyph)| {
+            if !reload && !tracker.is_changed() {
+                return;
+            }
+            let index = theme.tile_sprite(&settings, tile_type);
+            if glyph.0 != index {
+                glyph.0 = index;
+            }
         });
 }
 
@@ -91,33 +202,33 @@ pub struct TileBundle {
     _t: Tile,
     tile_type: TileType,
     position: MapPosition,
-    #[bundle]
-    sprite: SpriteSheetBundle,
+    glyph: Glyph,
+    visibility: Visibility,
 }
 
 impl TileBundle {
-    pub fn new(
-        position: MapPosition,
-        textures: &TextureAtlasAssets,
-        tile_type: TileType,
-        tile_size: i32,
-        z_level: f32,
-        index: usize,
-    ) -> Self {
+    /// Hidden until the player sees it
+    pub fn new(position: MapPosition, tile_type: TileType, index: usize) -> Self {
         Self {
             position,
             tile_type,
-            sprite: SpriteSheetBundle {
-                visibility: Visibility { is_visible: false },
-                transform: Transform {
-                    translation: position.translation(z_level, tile_size),
-                    ..default()
-                },
-                texture_atlas: textures.texture_atlas.clone(),
-                sprite: TextureAtlasSprite { index, ..default() },
-                ..default()
-            },
+            glyph: Glyph(index),
+            visibility: Visibility { is_visible: false },
             ..default()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyph_round_trip() {
+        TileType::ALL.iter().for_each(|t| {
+            assert_eq!(TileType::try_from(t.glyph()), Ok(*t));
+            assert_eq!(format!("{}", t), t.glyph().to_string());
+        });
+        assert_eq!(TileType::try_from('M'), Err('M'));
+    }
+}
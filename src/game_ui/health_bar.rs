@@ -4,12 +4,6 @@ use crate::{components::health::Health, entities::Player};
 
 use super::hud::UiState;
 
-#[derive(Component)]
-pub struct HealthText;
-
-#[derive(Component)]
-pub struct HealthBar;
-
 fn calc_health_percentage(health: Health) -> f32 {
     (100 * health.current / health.max) as f32
 }
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContext};
 
-use crate::GameState;
+use crate::{actions::Actions, GameState};
 
 use super::{
     health_bar::update_hud_health, inventory::update_inventory_hud, quests::update_quests_hud,
@@ -16,6 +16,7 @@ impl Plugin for HUDPlugin {
                 SystemSet::on_update(GameState::Playing)
                     .with_system(hud_update)
                     .with_system(update_hud_health)
+                    .with_system(update_hud_count)
                     .with_system(update_inventory_hud)
                     .with_system(update_quests_hud),
             )
@@ -46,6 +47,8 @@ pub struct UiState {
     pub player_health_percentage: f32,
     pub quests: QuestsStrings,
     pub inventory: Vec<String>,
+    /// Count being typed to repeat the next action
+    pub count: Option<u32>,
 }
 
 fn hud_setup(mut commands: Commands, mut egui_context: ResMut<EguiContext>) {
@@ -64,6 +67,12 @@ fn hud_setup(mut commands: Commands, mut egui_context: ResMut<EguiContext>) {
     commands.insert_resource(ui_status);
 }
 
+fn update_hud_count(actions: Res<Actions>, mut ui_status: ResMut<UiState>) {
+    if ui_status.count != actions.count {
+        ui_status.count = actions.count;
+    }
+}
+
 fn hud_update(mut egui_context: ResMut<EguiContext>, ui_status: Res<UiState>) {
     if !ui_status.is_changed() {
         return;
@@ -107,6 +116,9 @@ fn hud_update(mut egui_context: ResMut<EguiContext>, ui_status: Res<UiState>) {
                 .show_percentage()
                 .text(format!("Health: {}", ui_status.player_health_percentage));
             ui.add(progress_bar);
+            if let Some(count) = ui_status.count {
+                ui.label(format!("Repeat: {}", count));
+            }
         });
     });
 }
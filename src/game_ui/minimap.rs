@@ -0,0 +1,211 @@
+use bevy::{prelude::*, utils::HashSet};
+use bevy_egui::{egui, EguiContext};
+
+use crate::{
+    camera::CameraController,
+    components::map_position::MapPosition,
+    config::Settings,
+    entities::{Item, Player, Tile, TileType, WinItem},
+    map::{grid_map::base_map::BaseMap, map_builder::MapBuilder, tile_map::TileMap},
+    systems::fov::FieldOfView,
+    GameState,
+};
+
+/// Width of the minimap on screen, the height follows the map's shape
+const MINIMAP_WIDTH: f32 = 160.0;
+
+/// Seconds the camera stays panned to a clicked spot
+const PAN_SECONDS: f32 = 3.0;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MinimapPan>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(draw_minimap)
+                    .with_system(end_pan),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(reset_pan));
+    }
+}
+
+/// What a spot on the minimap shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Unknown,
+    Wall { visible: bool },
+    Floor { visible: bool },
+    Exit,
+    Item,
+    WinItem,
+    Player,
+}
+
+impl Cell {
+    fn color(self) -> egui::Color32 {
+        match self {
+            Cell::Unknown => egui::Color32::TRANSPARENT,
+            Cell::Wall { visible: false } => egui::Color32::from_gray(60),
+            Cell::Wall { visible: true } => egui::Color32::from_gray(110),
+            Cell::Floor { visible: false } => egui::Color32::from_gray(130),
+            Cell::Floor { visible: true } => egui::Color32::from_gray(200),
+            Cell::Exit => egui::Color32::YELLOW,
+            Cell::Item => egui::Color32::LIGHT_BLUE,
+            Cell::WinItem => egui::Color32::from_rgb(255, 0, 255),
+            Cell::Player => egui::Color32::RED,
+        }
+    }
+}
+
+/// Cells row by row from the top of the map, for the tiles explored, the
+/// ones in view and the items on explored tiles
+fn minimap_cells(
+    map: &TileMap,
+    explored: &HashSet<MapPosition>,
+    visible: &HashSet<MapPosition>,
+    items: &[(MapPosition, bool)],
+    player: MapPosition,
+) -> Vec<Cell> {
+    (0..map.height)
+        .rev()
+        .flat_map(|y| (0..map.width).map(move |x| MapPosition::new(x as i32, y as i32)))
+        .map(|p| {
+            if p == player {
+                return Cell::Player;
+            }
+            if !explored.contains(&p) && !visible.contains(&p) {
+                return Cell::Unknown;
+            }
+            if let Some((_, win)) = items.iter().find(|(i, _)| *i == p) {
+                return if *win { Cell::WinItem } else { Cell::Item };
+            }
+            let tile = map.value(p).appearance();
+            let visible = visible.contains(&p);
+            match tile {
+                TileType::Exit => Cell::Exit,
+                _ if tile.properties().walkable => Cell::Floor { visible },
+                _ => Cell::Wall { visible },
+            }
+        })
+        .collect()
+}
+
+/// Time left before the camera goes back to the player
+#[derive(Resource, Default)]
+struct MinimapPan(Option<Timer>);
+
+#[allow(clippy::too_many_arguments)]
+fn draw_minimap(
+    mut egui_context: ResMut<EguiContext>,
+    mut pan: ResMut<MinimapPan>,
+    mut controller: ResMut<CameraController>,
+    map_builder: Res<MapBuilder>,
+    settings: Res<Settings>,
+    player: Query<(&MapPosition, &FieldOfView), With<Player>>,
+    tiles: Query<(&MapPosition, &Visibility), With<Tile>>,
+    items: Query<(&MapPosition, Option<&WinItem>), With<Item>>,
+) {
+    let Ok((position, fov)) = player.get_single() else {
+        return;
+    };
+    // Tiles stay drawn once seen
+    let explored = tiles
+        .iter()
+        .filter(|(_, v)| v.is_visible)
+        .map(|(p, _)| *p)
+        .collect::<HashSet<_>>();
+    let items = items
+        .iter()
+        .filter(|(p, _)| explored.contains(p))
+        .map(|(p, win)| (*p, win.is_some()))
+        .collect::<Vec<_>>();
+    let map = &map_builder.map;
+    let cells = minimap_cells(map, &explored, &fov.visible_positions, &items, *position);
+
+    let cell_size = MINIMAP_WIDTH / map.width as f32;
+    let size = egui::vec2(MINIMAP_WIDTH, cell_size * map.height as f32);
+    egui::Window::new("Map")
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            let (response, painter) = ui.allocate_painter(size, egui::Sense::click());
+            painter.rect_filled(response.rect, 0.0, egui::Color32::from_black_alpha(180));
+            cells.iter().enumerate().for_each(|(i, cell)| {
+                let x = (i % map.width) as f32;
+                let y = (i / map.width) as f32;
+                let min = response.rect.min + egui::vec2(x, y) * cell_size;
+                painter.rect_filled(
+                    egui::Rect::from_min_size(min, egui::vec2(cell_size, cell_size)),
+                    0.0,
+                    cell.color(),
+                );
+            });
+            let clicked = response
+                .clicked()
+                .then(|| response.interact_pointer_pos())
+                .flatten();
+            if let Some(pointer) = clicked {
+                let offset = (pointer - response.rect.min) / cell_size;
+                let target = MapPosition::new(
+                    offset.x.floor() as i32,
+                    map.height as i32 - 1 - offset.y.floor() as i32,
+                );
+                controller.pan = Some(target.translation(0.0, settings.tile_size).truncate());
+                pan.0 = Some(Timer::from_seconds(PAN_SECONDS, TimerMode::Once));
+            }
+        });
+}
+
+/// Put the camera back on the player once a pan is over
+fn end_pan(time: Res<Time>, mut pan: ResMut<MinimapPan>, mut controller: ResMut<CameraController>) {
+    let Some(timer) = pan.0.as_mut() else {
+        return;
+    };
+    if !timer.tick(time.delta()).finished() {
+        return;
+    }
+    pan.0 = None;
+    controller.pan = None;
+}
+
+fn reset_pan(mut pan: ResMut<MinimapPan>, mut controller: ResMut<CameraController>) {
+    pan.0 = None;
+    controller.pan = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimap_cells() {
+        let map_builder = "#####
+                           #...#
+                           #.>.#
+                           #####"
+            .parse::<MapBuilder>()
+            .unwrap();
+        let explored = [(1, 1), (2, 1), (3, 1), (1, 2), (2, 2)]
+            .iter()
+            .map(|(x, y)| MapPosition::new(*x, *y))
+            .collect::<HashSet<_>>();
+        let visible = HashSet::from_iter([MapPosition::new(0, 1)]);
+        let cells = minimap_cells(
+            &map_builder.map,
+            &explored,
+            &visible,
+            &[(MapPosition::new(3, 1), false)],
+            MapPosition::new(1, 1),
+        );
+        // The bottom row of the map is drawn first
+        let cell = |x: usize, y: usize| cells[(3 - y) * 5 + x];
+        assert_eq!(cell(1, 1), Cell::Player);
+        assert_eq!(cell(2, 1), Cell::Floor { visible: false });
+        assert_eq!(cell(3, 1), Cell::Item);
+        assert_eq!(cell(0, 1), Cell::Wall { visible: true });
+        assert_eq!(cell(2, 2), Cell::Exit);
+        assert_eq!(cell(3, 2), Cell::Unknown);
+    }
+}
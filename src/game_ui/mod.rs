@@ -1,19 +1,20 @@
 use bevy::prelude::*;
 
-use self::{hud::HUDPlugin, tooltip::TooltipPlugin};
+use self::{hud::HUDPlugin, minimap::MinimapPlugin, tooltip::TooltipPlugin};
 
 mod health_bar;
 mod hud;
 mod inventory;
+mod minimap;
 mod quests;
 pub mod tooltip;
 
-pub use tooltip::ToolTip;
-
 pub struct GameUiPlugin;
 
 impl Plugin for GameUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(HUDPlugin).add_plugin(TooltipPlugin);
+        app.add_plugin(HUDPlugin)
+            .add_plugin(MinimapPlugin)
+            .add_plugin(TooltipPlugin);
     }
 }
@@ -1,7 +1,12 @@
 use bevy::prelude::*;
 
 use crate::{
-    actions::Actions, cleanup::cleanup_components, config::Settings, loading::FontAssets, GameState,
+    actions::{Actions, MousePosition},
+    camera::CameraController,
+    cleanup::cleanup_components,
+    config::Settings,
+    loading::FontAssets,
+    GameState,
 };
 
 pub struct TooltipPlugin;
@@ -35,17 +40,34 @@ struct ToolTipInfo {
     position: Option<Vec2>,
 }
 
+/// Where the look cursor is, in the game and on the screen
+fn look_position(
+    controller: &CameraController,
+    camera: &Query<(&Camera, &GlobalTransform)>,
+    tile_size: i32,
+) -> Option<MousePosition> {
+    let game_position = controller.look?.translation(0.0, tile_size);
+    let (camera, transform) = camera.get_single().ok()?;
+    Some(MousePosition {
+        game_position: game_position.truncate(),
+        screen_position: camera.world_to_viewport(transform, game_position)?,
+    })
+}
+
 fn mouse_rollover(
     actions: Res<Actions>,
+    controller: Res<CameraController>,
+    camera: Query<(&Camera, &GlobalTransform)>,
     interactives: Query<(&Transform, &Interactive)>,
     mut tooltip_event: EventWriter<ToolTipInfo>,
     settings: Res<Settings>,
 ) {
-    if actions.mouse_rollover.is_none() {
+    // The look cursor stands in for the mouse while looking around
+    let look = look_position(&controller, &camera, settings.tile_size);
+    let Some(mouse_position) = look.as_ref().or(actions.mouse_rollover.as_ref()) else {
         return;
-    }
+    };
     let mut overlap = false;
-    let mouse_position = actions.mouse_rollover.as_ref().unwrap();
     interactives.iter().for_each(|(transform, interactive)| {
         if transform.translation.truncate().abs_diff_eq(
             mouse_position.game_position,
@@ -118,15 +140,12 @@ fn update_tooltip(
                 visibility.is_visible = false;
             }
         }
-        match &poss_info.position {
-            Some(position) => {
-                style.position = UiRect {
-                    left: Val::Px(position.x),
-                    bottom: Val::Px(position.y),
-                    ..default()
-                };
-            }
-            None => {}
+        if let Some(position) = &poss_info.position {
+            style.position = UiRect {
+                left: Val::Px(position.x),
+                bottom: Val::Px(position.y),
+                ..default()
+            };
         }
     });
 }
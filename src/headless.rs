@@ -0,0 +1,37 @@
+//! Builds the game without a window, textures, audio or input devices,
+//! for tests, bots and the terminal, which set the player's [`Actions`]
+//! themselves. The rules only give entities a glyph, the window adds sprites.
+
+use bevy::prelude::*;
+use bevy_turborand::RngPlugin;
+
+use crate::{
+    actions::Actions,
+    config::Settings,
+    entities::EntitiesPlugin,
+    map::{map_builder::MapBuilder, MapPlugin},
+    stages::StagePlugin,
+    systems::SystemsPlugin,
+    theme::ThemePlugin,
+    GameState,
+};
+
+/// The game rules with a seeded rng. Plays on `map` when given, otherwise
+/// generates levels as a normal run does
+pub fn headless_app(settings: Settings, seed: u64, map: Option<MapBuilder>) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(RngPlugin::new().with_rng_seed(seed));
+    match map {
+        Some(map) => app.insert_resource(map).add_state(GameState::Playing),
+        None => app.add_state(GameState::Generation),
+    };
+    app.insert_resource(settings)
+        .init_resource::<Actions>()
+        .add_plugin(StagePlugin)
+        .add_plugin(ThemePlugin)
+        .add_plugin(SystemsPlugin)
+        .add_plugin(MapPlugin)
+        .add_plugin(EntitiesPlugin);
+    app
+}
@@ -14,24 +14,44 @@
 //#![forbid(clippy::missing_docs_in_private_items)]
 
 mod actions;
+mod animation;
 mod audio;
+mod bot;
 mod camera;
 mod cleanup;
 mod components;
 mod config;
 mod entities;
 mod game_ui;
+mod headless;
 mod loading;
 mod map;
 mod menu;
+mod morgue;
+mod options;
+mod rebind;
+mod replay;
+mod sprites;
 mod stages;
 mod systems;
+#[cfg(test)]
+mod test_harness;
+mod theme;
+mod tui;
 
 use crate::actions::ActionsPlugin;
+use crate::animation::AnimationPlugin;
 use crate::audio::InternalAudioPlugin;
-use crate::config::ConfigPlugin;
+use crate::config::{ConfigPlugin, Settings, CONFIG_DIR};
 use crate::loading::LoadingPlugin;
 use crate::menu::MenuPlugin;
+use crate::morgue::MorguePlugin;
+use crate::options::OptionsPlugin;
+use crate::rebind::RebindPlugin;
+pub use crate::replay::ReplayMode;
+use crate::replay::ReplayPlugin;
+use crate::sprites::SpritesPlugin;
+use crate::systems::stats::{HighScores, HIGH_SCORES_FILE};
 
 use bevy::app::App;
 #[cfg(debug_assertions)]
@@ -44,6 +64,7 @@ use game_ui::GameUiPlugin;
 use map::MapPlugin;
 use stages::StagePlugin;
 use systems::SystemsPlugin;
+use theme::ThemePlugin;
 
 /// This game uses States to separate logic
 /// See https://bevy-cheatbook.github.io/programming/states.html
@@ -58,6 +79,85 @@ enum GameState {
     Playing,
     /// Here the menu is drawn and waiting for player interaction
     Menu,
+    /// The recap of a lost run, before going back to the menu
+    Morgue,
+}
+
+/// Load and validate a config profile (as named by `RUN_MODE`) without
+/// starting the game. On failure returns a report of every problem found.
+pub fn check_config(run_mode: &str) -> Result<(), String> {
+    Settings::load_profile(run_mode)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// The profile named on the command line, or else the one the game starts
+/// with: `RUN_MODE`, then the profile saved in the options
+pub fn profile_or_run_mode(profile: Option<&str>) -> String {
+    profile.map_or_else(config::run_mode, str::to_string)
+}
+
+/// Size the window opens at, from the settings and the saved options
+pub fn window_size() -> Option<(f32, f32)> {
+    Settings::load()
+        .ok()
+        .map(|settings| (settings.window.width, settings.window.height))
+}
+
+/// Play a run of a config profile in the terminal, drawn as text instead
+/// of in a window
+pub fn run_terminal(run_mode: &str) -> Result<(), String> {
+    let settings = Settings::load_saved(run_mode).map_err(|e| e.to_string())?;
+    tui::run_tui(settings).map_err(|e| e.to_string())
+}
+
+/// The first screen of a run of a config profile as the terminal would
+/// draw it, for checking the game still plays without a window
+pub fn terminal_snapshot(run_mode: &str, seed: u64) -> Result<String, String> {
+    let settings = Settings::load_profile(run_mode).map_err(|e| e.to_string())?;
+    tui::snapshot(settings, seed).ok_or_else(|| "No level was made".to_string())
+}
+
+/// Turns a bot may take before its run is counted as out of turns
+const BOT_MAX_TURNS: u32 = 5000;
+
+/// Play `runs` headless games with the greedy bot on a config profile,
+/// seeded 0 to `runs - 1`. Returns a line per run and the win rate.
+pub fn run_bots(run_mode: &str, runs: u64) -> Result<String, String> {
+    let reports = (0..runs)
+        .map(|seed| {
+            let mut settings = Settings::load_profile(run_mode).map_err(|e| e.to_string())?;
+            // Each bot run plays its own seed, not the one picked in the options
+            settings.seed = None;
+            Ok(bot::run_bot(
+                settings,
+                seed,
+                bot::GreedyBot::default(),
+                BOT_MAX_TURNS,
+            ))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let wins = reports
+        .iter()
+        .filter(|r| r.outcome == bot::Outcome::Won)
+        .count();
+    let mut report = reports
+        .iter()
+        .map(|r| {
+            format!(
+                "seed {}: {:?} on level {} after {} turns\n",
+                r.seed, r.outcome, r.level, r.turns
+            )
+        })
+        .collect::<String>();
+    report += &format!(
+        "Won {} of {} runs ({:.0}%) on '{}'",
+        wins,
+        runs,
+        100.0 * wins as f64 / runs.max(1) as f64,
+        run_mode
+    );
+    Ok(report)
 }
 
 /// Main plugin for the games internals
@@ -66,15 +166,25 @@ pub struct GamePlugin;
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_state(GameState::Loading)
+            .insert_resource(HighScores::load(
+                [CONFIG_DIR, HIGH_SCORES_FILE].iter().collect(),
+            ))
             .add_plugin(ConfigPlugin)
             .add_plugin(LoadingPlugin)
             .add_plugin(MenuPlugin)
+            .add_plugin(MorguePlugin)
+            .add_plugin(OptionsPlugin)
+            .add_plugin(RebindPlugin)
+            .add_plugin(ReplayPlugin)
             .add_plugin(ActionsPlugin)
             .add_plugin(InternalAudioPlugin)
             .add_plugin(StagePlugin)
+            .add_plugin(ThemePlugin)
             .add_plugin(SystemsPlugin)
             .add_plugin(GameUiPlugin)
+            .add_plugin(SpritesPlugin)
             .add_plugin(CameraPlugin)
+            .add_plugin(AnimationPlugin)
             .add_plugin(MapPlugin)
             .add_plugin(EntitiesPlugin);
 
@@ -1,5 +1,6 @@
+use crate::config::{AudioSettings, SoundCue};
 use crate::GameState;
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use bevy_asset_loader::prelude::*;
 use bevy_kira_audio::AudioSource;
 
@@ -14,7 +15,6 @@ impl Plugin for LoadingPlugin {
         app.add_loading_state(
             LoadingState::new(GameState::Loading)
                 .with_collection::<FontAssets>()
-                .with_collection::<AudioAssets>()
                 .with_collection::<TextureAssets>()
                 .with_collection::<TextureAtlasAssets>()
                 .continue_to_state(GameState::Menu),
@@ -33,18 +33,32 @@ pub struct FontAssets {
     pub fira_sans: Handle<Font>,
 }
 
-/// Audio assets
-#[derive(AssetCollection, Resource)]
+/// Audio assets, the sounds are named in the config so they are loaded
+/// from it rather than by the loading state
+#[derive(Resource, Default)]
 pub struct AudioAssets {
-    /// A flying sound
-    #[asset(path = "audio/flying.ogg")]
-    pub flying: Handle<AudioSource>,
+    /// Sound of each cue that has one
+    pub cues: HashMap<SoundCue, Handle<AudioSource>>,
+}
+
+impl AudioAssets {
+    /// Start loading the sounds of the cues
+    pub fn load(settings: &AudioSettings, asset_server: &AssetServer) -> Self {
+        Self {
+            cues: settings
+                .cues
+                .iter()
+                .map(|(cue, sound)| (*cue, asset_server.load(sound.path.as_str())))
+                .collect(),
+        }
+    }
 }
 
 /// Textures
 #[derive(AssetCollection, Resource)]
 pub struct TextureAssets {
     /// Logo asset
+    #[allow(dead_code)]
     #[asset(path = "textures/bevy.png")]
     pub texture_bevy: Handle<Image>,
 }
@@ -7,22 +7,48 @@ use bevy::winit::WinitWindows;
 use bevy::DefaultPlugins;
 use bevy_egui::EguiPlugin;
 use bevy_turborand::RngPlugin;
-use cake_addict::GamePlugin;
+use cake_addict::{
+    check_config, profile_or_run_mode, run_bots, run_terminal, terminal_snapshot, window_size,
+    GamePlugin, ReplayMode,
+};
+use std::env;
 use std::io::Cursor;
+use std::process::ExitCode;
 use winit::window::Icon;
 
+// Window size when the settings cannot be read, the game reports why
 const WIDTH: f32 = 800.0;
 const HEIGHT: f32 = 600.0;
 // const RNG_SEED: u64 = 1000;
 
-fn main() {
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) == Some("--check-config") {
+        return run_check_config(args.get(1).map(String::as_str));
+    }
+    if args.first().map(String::as_str) == Some("--bot") {
+        return run_bot_games(args.get(1).map(String::as_str), args.get(2));
+    }
+    if args.first().map(String::as_str) == Some("--tui") {
+        return run_in_terminal(args.get(1).map(String::as_str));
+    }
+    if args.first().map(String::as_str) == Some("--tui-snapshot") {
+        return print_snapshot(args.get(1).map(String::as_str));
+    }
+    let replay_mode = match (args.first().map(String::as_str), args.get(1)) {
+        (Some("--record"), Some(path)) => ReplayMode::Record(path.into()),
+        (Some("--replay"), Some(path)) => ReplayMode::Playback(path.into()),
+        _ => ReplayMode::Off,
+    };
+
+    let (width, height) = window_size().unwrap_or((WIDTH, HEIGHT));
     App::new()
         .insert_resource(Msaa { samples: 1 })
         .insert_resource(ClearColor(Color::rgb(0.4, 0.4, 0.4)))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             window: WindowDescriptor {
-                width: WIDTH,
-                height: HEIGHT,
+                width,
+                height,
                 title: "Cake Addict".to_string(),
                 canvas: Some("#bevy".to_owned()),
                 ..default()
@@ -31,9 +57,72 @@ fn main() {
         }))
         .add_plugin(EguiPlugin)
         .add_plugin(RngPlugin::new())
+        .insert_resource(replay_mode)
         .add_plugin(GamePlugin)
         .add_startup_system(set_window_icon)
         .run();
+    ExitCode::SUCCESS
+}
+
+// Validates a config profile and exits without opening a window
+fn run_check_config(profile: Option<&str>) -> ExitCode {
+    let run_mode = profile_or_run_mode(profile);
+    match check_config(&run_mode) {
+        Ok(()) => {
+            println!("Config profile '{}' is valid", run_mode);
+            ExitCode::SUCCESS
+        }
+        Err(report) => {
+            eprintln!("{}", report);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Plays headless games with the greedy bot and prints the win rate
+fn run_bot_games(profile: Option<&str>, runs: Option<&String>) -> ExitCode {
+    let run_mode = profile_or_run_mode(profile);
+    let Some(runs) = runs.map_or(Some(20), |r| r.parse().ok()) else {
+        eprintln!("Number of runs must be a whole number");
+        return ExitCode::FAILURE;
+    };
+    match run_bots(&run_mode, runs) {
+        Ok(report) => {
+            println!("{}", report);
+            ExitCode::SUCCESS
+        }
+        Err(report) => {
+            eprintln!("{}", report);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Plays in the terminal instead of a window
+fn run_in_terminal(profile: Option<&str>) -> ExitCode {
+    let run_mode = profile_or_run_mode(profile);
+    match run_terminal(&run_mode) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(report) => {
+            eprintln!("{}", report);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Prints the first screen of a seeded run as the terminal draws it
+fn print_snapshot(profile: Option<&str>) -> ExitCode {
+    let run_mode = profile_or_run_mode(profile);
+    match terminal_snapshot(&run_mode, 0) {
+        Ok(screen) => {
+            println!("{}", screen);
+            ExitCode::SUCCESS
+        }
+        Err(report) => {
+            eprintln!("{}", report);
+            ExitCode::FAILURE
+        }
+    }
 }
 
 // Sets the icon on windows and X11
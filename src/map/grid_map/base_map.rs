@@ -8,9 +8,8 @@ pub trait BaseMap {
     fn width(&self) -> usize;
     fn can_enter_tile(&self, p: MapPosition) -> bool;
     fn value(&self, p: MapPosition) -> Self::Output;
-    fn set(&mut self, p: MapPosition, value: Self::Output);
     fn neighbours(&self, p: MapPosition) -> Vec<MapPosition> {
-        vec![ivec2(-1, 0), ivec2(1, 0), ivec2(0, -1), ivec2(0, 1)]
+        [ivec2(-1, 0), ivec2(1, 0), ivec2(0, -1), ivec2(0, 1)]
             .iter()
             .map(|iv| MapPosition::from_ivec2(*iv + p.position))
             .filter(|mp| self.can_enter_tile(*mp))
@@ -23,3 +22,8 @@ pub trait BaseMap {
         )
     }
 }
+
+/// A map whose tiles can be changed, views over another map only read it
+pub trait BaseMapMut: BaseMap {
+    fn set(&mut self, p: MapPosition, value: Self::Output);
+}
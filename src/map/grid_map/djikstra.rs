@@ -1,13 +1,32 @@
 use crate::components::map_position::MapPosition;
 
-use super::{base_map::BaseMap, djikstra_map::DjikstraMap};
+use super::{
+    base_map::{BaseMap, BaseMapMut},
+    djikstra_map::DjikstraMap,
+};
 
 pub trait DjikstraMapCalc: BaseMap {
     fn depth_djikstra_map(&self, start_node: MapPosition, max_depth: Option<i32>) -> DjikstraMap {
-        let mut dmap = DjikstraMap::new(self.height(), self.width(), start_node.as_utuple());
+        let dmap = DjikstraMap::new(self.height(), self.width(), start_node.as_utuple());
+        self.fill_djikstra_map(dmap, vec![start_node], max_depth)
+    }
 
-        let mut frontier: Vec<MapPosition> = vec![start_node];
+    /// Distances to the nearest of several start nodes
+    fn multi_djikstra_map(&self, start_nodes: &[MapPosition]) -> DjikstraMap {
+        let start = start_nodes.first().map_or((0, 0), |s| s.as_utuple());
+        let mut dmap = DjikstraMap::new(self.height(), self.width(), start);
+        // Only the given nodes are starts, even if there are none
+        dmap.set(MapPosition::from_utuple(&start), None);
+        start_nodes.iter().for_each(|s| dmap.set(*s, Some(0)));
+        self.fill_djikstra_map(dmap, start_nodes.to_vec(), None)
+    }
 
+    fn fill_djikstra_map(
+        &self,
+        mut dmap: DjikstraMap,
+        mut frontier: Vec<MapPosition>,
+        max_depth: Option<i32>,
+    ) -> DjikstraMap {
         while !frontier.is_empty() {
             let mut new_frontier: Vec<MapPosition> = vec![];
 
@@ -2,7 +2,10 @@ use ndarray::{Array, Ix2};
 
 use crate::components::map_position::MapPosition;
 
-use super::{base_map::BaseMap, djikstra::DjikstraMapCalc};
+use super::{
+    base_map::{BaseMap, BaseMapMut},
+    djikstra::DjikstraMapCalc,
+};
 
 #[derive(Debug)]
 pub struct DjikstraMap {
@@ -87,7 +90,9 @@ impl BaseMap for DjikstraMap {
     fn value(&self, p: MapPosition) -> Option<i32> {
         *self.result.get(p.as_utuple()).unwrap_or(&None)
     }
+}
 
+impl BaseMapMut for DjikstraMap {
     fn set(&mut self, p: MapPosition, value: Option<i32>) {
         self.result[p.as_utuple()] = value;
     }
@@ -130,6 +135,9 @@ mod tests {
         fn value(&self, p: MapPosition) -> Self::Output {
             self.result[p.as_utuple()]
         }
+    }
+
+    impl BaseMapMut for TestMap {
         fn set(&mut self, p: MapPosition, value: Self::Output) {
             self.result[p.as_utuple()] = value;
         }
@@ -167,6 +175,16 @@ mod tests {
             ]
         );
     }
+    #[test]
+    fn test_multi_djikstra_map() {
+        let map = TestMap::new(10, 1);
+        let dmap = map.multi_djikstra_map(&[MapPosition::new(0, 0), MapPosition::new(0, 9)]);
+        assert_eq!(dmap.value(MapPosition::new(0, 2)), Some(2));
+        assert_eq!(dmap.value(MapPosition::new(0, 7)), Some(2));
+        let empty = map.multi_djikstra_map(&[]);
+        assert_eq!(empty.value(MapPosition::new(0, 0)), None);
+    }
+
     #[test]
     fn test_calculate_longest_path() {
         let map = TestMap::new(10, 1);
@@ -3,4 +3,3 @@ mod djikstra;
 mod djikstra_map;
 
 pub use djikstra::DjikstraMapCalc;
-pub use djikstra_map::DjikstraMap;
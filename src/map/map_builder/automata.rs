@@ -1,101 +1,74 @@
-use bevy::{
-    math::ivec2,
-    prelude::{default, IVec2},
-};
-use bevy_turborand::DelegatedRng;
+use std::collections::VecDeque;
+
+use bevy::{prelude::default, utils::HashSet};
+use bevy_turborand::{DelegatedRng, RngComponent};
 
 use crate::{
     components::map_position::MapPosition,
+    config::{CaveSettings, Disconnected, ExitPosition, MetaBuilder, StartPosition},
     entities::TileType,
-    map::{grid_map::base_map::BaseMap, tile_map::TileMap},
+    map::{
+        grid_map::base_map::{BaseMap, BaseMapMut},
+        tile_map::TileMap,
+    },
 };
 
 use super::{MapArchitect, MapBuilder};
 
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
 pub struct CellularAutomataArchitect {
-    percent_floor: i32,
-    max_neighbours: usize,
-    min_neighbours: usize,
-    num_monsters: usize,
-    num_items: usize,
-    num_npcs: usize,
-    entity_distance: f32,
+    caves: CaveSettings,
 }
 
 impl MapArchitect for CellularAutomataArchitect {
-    fn entity_distance(&self) -> f32 {
-        self.entity_distance
-    }
-
-    fn num_monsters(&self) -> usize {
-        self.num_monsters
-    }
-    fn num_items(&self) -> usize {
-        self.num_items
-    }
-    fn num_npcs(&self) -> usize {
-        self.num_npcs
-    }
-
-    fn builder(
-        &mut self,
-        height: usize,
-        width: usize,
-        rng: &mut bevy_turborand::RngComponent,
-    ) -> super::MapBuilder {
+    fn builder(&mut self, height: usize, width: usize, rng: &mut RngComponent) -> MapBuilder {
         let mut mb = MapBuilder {
             map: TileMap::new(height, width),
             ..default()
         };
         self.random_noise_map(rng, &mut mb.map);
-        self.iteration(&mut mb.map);
-        mb.player_start = self.find_start(&mb.map);
-        mb.fill_in_unreachable();
-        mb.monster_spawns = self.entity_spawns(mb.player_start, &mb.map, rng, self.num_monsters);
-
-        mb.item_spawns = self.entity_spawns(mb.player_start, &mb.map, rng, self.num_items);
-        mb.npc_spawns = self.entity_spawns(mb.player_start, &mb.map, rng, self.num_npcs);
-        mb.winitem_start = mb.find_most_distant();
+        (0..self.caves.passes).for_each(|_| self.iteration(&mut mb.map));
+        seal_edges(&mut mb.map);
+        match self.caves.disconnected {
+            Disconnected::Join => join_caverns(&mut mb.map),
+            Disconnected::Cull => cull_caverns(&mut mb.map),
+        }
+        // Rules that leave no floor still need somewhere to start
+        if !mb.map.tiles.iter().any(|t| *t == TileType::Floor) {
+            mb.map.set(mb.map.centre(), TileType::Floor);
+        }
         mb
     }
+
+    fn pipeline(&self) -> Vec<MetaBuilder> {
+        vec![
+            MetaBuilder::Start(StartPosition::Centre),
+            MetaBuilder::CullUnreachable,
+            MetaBuilder::Exit(ExitPosition::MostDistant),
+            MetaBuilder::region_spawns(),
+        ]
+    }
 }
+
 impl CellularAutomataArchitect {
-    pub fn new(
-        num_monsters: usize,
-        num_items: usize,
-        num_npcs: usize,
-        entity_distance: f32,
-    ) -> Self {
-        Self {
-            percent_floor: 45,
-            max_neighbours: 4,
-            min_neighbours: 0,
-            num_items,
-            num_monsters,
-            num_npcs,
-            entity_distance,
-        }
-    }
-    fn find_start(&self, map: &TileMap) -> MapPosition {
-        let center = map.centre();
-        let closest_point = map
-            .tiles
-            .indexed_iter()
-            .filter(|(_, t)| **t == TileType::Floor)
-            .map(|(idx, _)| {
-                let pos = MapPosition::new(idx.1 as i32, idx.0 as i32);
-                (pos, center.distance(pos))
-            })
-            .min_by(|(_, d), (_, d2)| d.partial_cmp(d2).unwrap())
-            .map(|(idx, _)| idx)
-            .unwrap();
-        closest_point
+    pub fn new(caves: CaveSettings) -> Self {
+        Self { caves }
     }
 
-    fn random_noise_map(&mut self, rng: &mut bevy_turborand::RngComponent, map: &mut TileMap) {
+    fn random_noise_map(&mut self, rng: &mut RngComponent, map: &mut TileMap) {
         map.tiles.iter_mut().for_each(|tile| {
-            let roll = rng.i32(0..100);
-            if roll < self.percent_floor {
+            let roll = rng.u32(0..100);
+            if roll < self.caves.percent_floor {
                 *tile = TileType::Floor;
             } else {
                 *tile = TileType::Wall;
@@ -103,45 +76,198 @@ impl CellularAutomataArchitect {
         });
     }
 
-    fn count_neighbours(&self, v: IVec2, map: &TileMap) -> usize {
-        let mut count = 0;
-        (-1..1).for_each(|xi| {
-            (-1..1).for_each(|yi| {
-                if !(xi == 0 && yi == 0) && map.value(MapPosition::from_ivec2(v)) == TileType::Wall
-                {
-                    count += 1
-                }
-            })
-        });
-        count
-    }
-    fn iteration(&mut self, map: &mut TileMap) {
+    /// One pass of the birth and survival rules over every tile at once
+    fn iteration(&self, map: &mut TileMap) {
         let mut new_tiles = map.tiles.clone();
-        (1..map.height - 1).for_each(|y| {
-            (1..map.width - 1).for_each(|x| {
-                let neighbours = self.count_neighbours(ivec2(x as i32, y as i32), map);
-                if neighbours > self.max_neighbours || neighbours == self.min_neighbours {
-                    new_tiles[(y, x)] = TileType::Wall;
-                } else {
-                    new_tiles[(y, x)] = TileType::Floor;
-                }
-            })
+        new_tiles.indexed_iter_mut().for_each(|(idx, tile)| {
+            let walls = count_wall_neighbours(MapPosition::from_utuple(&idx), map);
+            let rule = match *tile {
+                TileType::Wall => &self.caves.survival,
+                _ => &self.caves.birth,
+            };
+            *tile = if rule.contains(&walls) {
+                TileType::Wall
+            } else {
+                TileType::Floor
+            };
         });
         map.tiles = new_tiles;
     }
 }
 
+/// Walls in the Moore neighbourhood, the edge of the map counts as wall
+fn count_wall_neighbours(p: MapPosition, map: &TileMap) -> usize {
+    NEIGHBOURS
+        .iter()
+        .map(|(x, y)| MapPosition::new(p.position.x + x, p.position.y + y))
+        .filter(|n| !map.in_bounds(*n) || map.value(*n) == TileType::Wall)
+        .count()
+}
+
+/// Wall off the border so the level stays closed
+fn seal_edges(map: &mut TileMap) {
+    let (width, height) = (map.width as i32, map.height as i32);
+    (0..width).for_each(|x| {
+        map.set(MapPosition::new(x, 0), TileType::Wall);
+        map.set(MapPosition::new(x, height - 1), TileType::Wall);
+    });
+    (0..height).for_each(|y| {
+        map.set(MapPosition::new(0, y), TileType::Wall);
+        map.set(MapPosition::new(width - 1, y), TileType::Wall);
+    });
+}
+
+/// Floor tiles grouped by the cavern they are connected in, largest first
+fn caverns(map: &TileMap) -> Vec<Vec<MapPosition>> {
+    let mut seen = HashSet::new();
+    let mut caverns = map
+        .tiles
+        .indexed_iter()
+        .map(|(idx, _)| MapPosition::from_utuple(&idx))
+        .filter_map(|start| {
+            if map.value(start) != TileType::Floor || !seen.insert(start) {
+                return None;
+            }
+            let mut cavern = vec![];
+            let mut queue = VecDeque::from([start]);
+            while let Some(p) = queue.pop_front() {
+                cavern.push(p);
+                NEIGHBOURS
+                    .iter()
+                    .filter(|(x, y)| *x == 0 || *y == 0)
+                    .map(|(x, y)| MapPosition::new(p.position.x + x, p.position.y + y))
+                    .filter(|n| map.in_bounds(*n) && map.value(*n) == TileType::Floor)
+                    .for_each(|n| {
+                        if seen.insert(n) {
+                            queue.push_back(n);
+                        }
+                    });
+            }
+            Some(cavern)
+        })
+        .collect::<Vec<_>>();
+    // Stable, so equal sizes keep the order they were found in
+    caverns.sort_by_key(|c| std::cmp::Reverse(c.len()));
+    caverns
+}
+
+/// Fill in every cavern but the largest
+fn cull_caverns(map: &mut TileMap) {
+    caverns(map)
+        .iter()
+        .skip(1)
+        .flatten()
+        .for_each(|p| map.set(*p, TileType::Wall));
+}
+
+/// Dig a tunnel from each smaller cavern to the nearest tile of the largest
+fn join_caverns(map: &mut TileMap) {
+    let caverns = caverns(map);
+    let Some((main, others)) = caverns.split_first() else {
+        return;
+    };
+    others.iter().for_each(|cavern| {
+        let (from, to) = cavern
+            .iter()
+            .flat_map(|a| main.iter().map(move |b| (*a, *b)))
+            .min_by_key(|(a, b)| {
+                let d = (a.position - b.position).abs();
+                d.x + d.y
+            })
+            .unwrap();
+        dig_tunnel(map, from, to);
+    });
+}
+
+/// Dig across then along to join two tiles
+fn dig_tunnel(map: &mut TileMap, from: MapPosition, to: MapPosition) {
+    let (x1, x2) = (
+        from.position.x.min(to.position.x),
+        from.position.x.max(to.position.x),
+    );
+    let (y1, y2) = (
+        from.position.y.min(to.position.y),
+        from.position.y.max(to.position.y),
+    );
+    (x1..=x2).for_each(|x| map.set(MapPosition::new(x, from.position.y), TileType::Floor));
+    (y1..=y2).for_each(|y| map.set(MapPosition::new(to.position.x, y), TileType::Floor));
+}
+
 #[cfg(test)]
 mod tests {
     use bevy_turborand::RngComponent;
 
     use super::*;
 
+    fn build(caves: CaveSettings, seed: u64) -> MapBuilder {
+        let mut arch = CellularAutomataArchitect::new(caves);
+        let mut rng = RngComponent::with_seed(seed);
+        arch.builder(40, 80, &mut rng)
+    }
+
+    fn floor_ratio(map: &TileMap) -> f32 {
+        let floors = map.tiles.iter().filter(|t| **t == TileType::Floor).count();
+        floors as f32 / map.tiles.len() as f32
+    }
+
+    #[test]
+    fn test_count_wall_neighbours() {
+        let mb = "#..
+                  ...
+                  ..#"
+        .parse::<MapBuilder>()
+        .unwrap();
+        assert_eq!(count_wall_neighbours(MapPosition::new(1, 1), &mb.map), 2);
+        // Five of the corner's neighbours are off the map
+        assert_eq!(count_wall_neighbours(MapPosition::new(0, 0), &mb.map), 5);
+    }
+
+    #[test]
+    fn test_caves_connected() {
+        [Disconnected::Join, Disconnected::Cull]
+            .into_iter()
+            .for_each(|disconnected| {
+                (0..5).for_each(|seed| {
+                    let mb = build(
+                        CaveSettings {
+                            disconnected,
+                            ..default()
+                        },
+                        seed,
+                    );
+                    assert_eq!(
+                        caverns(&mb.map).len(),
+                        1,
+                        "{:?} seed {}",
+                        disconnected,
+                        seed
+                    );
+                    let ratio = floor_ratio(&mb.map);
+                    assert!((0.2..0.8).contains(&ratio), "{}\n{}", ratio, mb);
+                });
+            });
+    }
+
     #[test]
-    fn build() {
-        let mut arch = CellularAutomataArchitect::new(50, 20, 10, 10.0);
-        let mut rng = RngComponent::new();
-        let mb = arch.builder(40, 80, &mut rng);
-        println!("{}", mb);
+    fn test_passes_smooth() {
+        // More smoothing leaves fewer walls standing alone
+        let lone_walls = |passes| {
+            let mb = build(
+                CaveSettings {
+                    passes,
+                    ..default()
+                },
+                1,
+            );
+            mb.map
+                .tiles
+                .indexed_iter()
+                .map(|(idx, _)| MapPosition::from_utuple(&idx))
+                .filter(|p| {
+                    mb.map.value(*p) == TileType::Wall && count_wall_neighbours(*p, &mb.map) == 0
+                })
+                .count()
+        };
+        assert!(lone_walls(4) < lone_walls(0));
     }
 }
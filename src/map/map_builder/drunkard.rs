@@ -5,7 +5,10 @@ use crate::{
     components::map_position::MapPosition,
     entities::TileType,
     map::{
-        grid_map::{base_map::BaseMap, DjikstraMapCalc},
+        grid_map::{
+            base_map::{BaseMap, BaseMapMut},
+            DjikstraMapCalc,
+        },
         tile_map::TileMap,
     },
 };
@@ -15,27 +18,14 @@ use super::{MapArchitect, MapBuilder};
 pub struct DrunkardArchitect {
     stagger_distance: usize,
     ratio: f32,
-    num_monsters: usize,
-    num_items: usize,
-    num_npcs: usize,
-    entity_distance: f32,
     max_distance: i32,
 }
 
 impl DrunkardArchitect {
-    pub fn new(
-        num_monsters: usize,
-        num_items: usize,
-        num_npcs: usize,
-        entity_distance: f32,
-    ) -> Self {
+    pub fn new() -> Self {
         Self {
             stagger_distance: 400,
             ratio: 0.3,
-            num_monsters,
-            num_items,
-            num_npcs,
-            entity_distance,
             max_distance: 2000,
         }
     }
@@ -97,26 +87,8 @@ impl MapArchitect for DrunkardArchitect {
                     mb.map.set(*p, TileType::Wall);
                 });
         }
-        mb.monster_spawns = self.entity_spawns(mb.player_start, &mb.map, rng, self.num_monsters);
-        mb.item_spawns = self.entity_spawns(mb.player_start, &mb.map, rng, self.num_items);
-        mb.npc_spawns = self.entity_spawns(mb.player_start, &mb.map, rng, self.num_npcs);
-        mb.winitem_start = mb.find_most_distant();
         mb
     }
-
-    fn entity_distance(&self) -> f32 {
-        self.entity_distance
-    }
-
-    fn num_monsters(&self) -> usize {
-        self.num_monsters
-    }
-    fn num_items(&self) -> usize {
-        self.num_items
-    }
-    fn num_npcs(&self) -> usize {
-        self.num_npcs
-    }
 }
 
 #[cfg(test)]
@@ -124,7 +96,7 @@ mod tests {
     use super::*;
     #[test]
     fn build() {
-        let mut arch = DrunkardArchitect::new(50, 20, 10, 10.0);
+        let mut arch = DrunkardArchitect::new();
         let mut rng = RngComponent::new();
         let mb = arch.builder(40, 80, &mut rng);
         println!("{}", mb);
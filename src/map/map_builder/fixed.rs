@@ -0,0 +1,96 @@
+use bevy::prelude::error;
+use bevy_turborand::RngComponent;
+
+use crate::config::{FixedLevel, MetaBuilder};
+
+use super::{empty::EmptyArchitect, MapArchitect, MapBuilder};
+
+/// Loads a hand-made level, an open floor stands in if it can't be read
+pub struct FixedArchitect {
+    level: Option<FixedLevel>,
+    loaded: bool,
+}
+
+impl FixedArchitect {
+    pub fn new(level: Option<FixedLevel>) -> Self {
+        Self {
+            level,
+            loaded: false,
+        }
+    }
+}
+
+impl MapArchitect for FixedArchitect {
+    fn builder(&mut self, height: usize, width: usize, rng: &mut RngComponent) -> MapBuilder {
+        let loaded = self
+            .level
+            .as_ref()
+            .ok_or_else(|| "no level given".to_string())
+            .and_then(MapBuilder::from_fixed);
+        match loaded {
+            Ok(mb) => {
+                self.loaded = true;
+                mb
+            }
+            Err(e) => {
+                error!("Could not load fixed level: {}", e);
+                EmptyArchitect.builder(height, width, rng)
+            }
+        }
+    }
+
+    fn pipeline(&self) -> Vec<MetaBuilder> {
+        match self.loaded {
+            true => vec![],
+            false => EmptyArchitect.pipeline(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::default;
+
+    use crate::{
+        components::map_position::MapPosition, config::FixedSpawn, entities::TileType,
+        map::grid_map::base_map::BaseMap,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_dinner() {
+        let level = FixedLevel {
+            path: "levels/dinner.txt".to_string(),
+            glyphs: [
+                ('B', FixedSpawn::Monster("Personal Trainer".to_string())),
+                ('F', FixedSpawn::Item("Deep Fried Fish".to_string())),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let mut arch = FixedArchitect::new(Some(level));
+        let mb = arch.builder(0, 0, &mut RngComponent::new());
+        assert!(arch.pipeline().is_empty());
+        let boss = mb
+            .fixed_spawns
+            .iter()
+            .find(|(_, spawn)| matches!(spawn, FixedSpawn::Monster(_)))
+            .map(|(p, _)| *p)
+            .unwrap();
+        assert_eq!(mb.map.value(boss), TileType::Floor);
+        assert!(mb.map.can_enter_tile(mb.player_start));
+        assert_ne!(mb.winitem_start, MapPosition::default());
+    }
+
+    #[test]
+    fn test_missing_level() {
+        let mut arch = FixedArchitect::new(Some(FixedLevel {
+            path: "levels/missing.txt".to_string(),
+            glyphs: default(),
+        }));
+        let mb = arch.builder(10, 20, &mut RngComponent::new());
+        assert_eq!(mb.map.width, 20);
+        assert!(!arch.pipeline().is_empty());
+    }
+}
@@ -0,0 +1,276 @@
+use bevy_turborand::{DelegatedRng, RngComponent};
+
+use crate::{
+    components::map_position::MapPosition,
+    config::{ArchitectSettings, ExitPosition, MetaBuilder, StartPosition, Symmetry},
+    entities::TileType,
+    map::grid_map::{
+        base_map::{BaseMap, BaseMapMut},
+        DjikstraMapCalc,
+    },
+};
+
+use super::{prefab::apply_prefab, MapBuilder};
+
+const ORTHOGONAL: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+impl MapBuilder {
+    /// Run one step of the pipeline
+    pub(super) fn apply(
+        &mut self,
+        step: &MetaBuilder,
+        architect: &ArchitectSettings,
+        rng: &mut RngComponent,
+    ) {
+        match step {
+            MetaBuilder::Start(start) => self.player_start = self.pick_start(*start, rng),
+            MetaBuilder::Exit(exit) => self.winitem_start = self.pick_exit(*exit, rng),
+            MetaBuilder::CullUnreachable => self.fill_in_unreachable(),
+            MetaBuilder::AreaSpawns => self.area_spawns(architect, rng),
+            MetaBuilder::RoomSpawns => self.room_spawns(architect, rng),
+            MetaBuilder::RegionSpawns { regions } => self.region_spawns(architect, *regions, rng),
+            MetaBuilder::Prefab(placement) => apply_prefab(
+                self,
+                &placement.prefab,
+                rng,
+                placement.min_distance,
+                placement.max_distance,
+            ),
+            MetaBuilder::WallCleanup => self.clean_up_walls(),
+            MetaBuilder::Doors => self.place_doors(),
+            MetaBuilder::Mirror(symmetry) => self.mirror(*symmetry),
+        }
+    }
+
+    pub(super) fn floor(&self) -> Vec<MapPosition> {
+        self.map
+            .tiles
+            .indexed_iter()
+            .filter(|(_, t)| **t == TileType::Floor)
+            .map(|(idx, _)| MapPosition::from_utuple(&idx))
+            .collect()
+    }
+
+    fn pick_start(&self, start: StartPosition, rng: &mut RngComponent) -> MapPosition {
+        let centre = self.map.centre();
+        let nearest_centre = self
+            .floor()
+            .into_iter()
+            .min_by(|a, b| centre.distance(*a).total_cmp(&centre.distance(*b)))
+            .unwrap_or(centre);
+        match start {
+            StartPosition::Centre => nearest_centre,
+            StartPosition::Random => rng.sample(&self.floor()).copied().unwrap_or(nearest_centre),
+            StartPosition::LongestPath => {
+                // Start from the architect's pick when it made one
+                let from = match self.map.can_enter_tile(self.player_start) {
+                    true => self.player_start,
+                    false => nearest_centre,
+                };
+                self.map.djikstra_map(from).calculate_longest_path()[0]
+            }
+        }
+    }
+
+    fn pick_exit(&self, exit: ExitPosition, rng: &mut RngComponent) -> MapPosition {
+        match exit {
+            ExitPosition::MostDistant => self.find_most_distant(),
+            ExitPosition::Random => {
+                let reachable = self.map.djikstra_map(self.player_start);
+                let tiles = self
+                    .floor()
+                    .into_iter()
+                    .filter(|p| *p != self.player_start && reachable.value(*p).is_some())
+                    .collect::<Vec<_>>();
+                rng.sample(&tiles)
+                    .copied()
+                    .unwrap_or_else(|| self.find_most_distant())
+            }
+        }
+    }
+
+    fn orthogonal(&self, p: MapPosition) -> impl Iterator<Item = Option<TileType>> + '_ {
+        ORTHOGONAL.iter().map(move |(x, y)| {
+            let n = MapPosition::new(p.position.x + x, p.position.y + y);
+            self.map.in_bounds(n).then(|| self.map.value(n))
+        })
+    }
+
+    /// Walls standing alone, the edge of the map counts as wall
+    fn clean_up_walls(&mut self) {
+        let lone = self
+            .map
+            .tiles
+            .indexed_iter()
+            .map(|(idx, _)| MapPosition::from_utuple(&idx))
+            .filter(|p| {
+                self.map.value(*p) == TileType::Wall
+                    && self
+                        .orthogonal(*p)
+                        .all(|t| t.is_some_and(|t| t != TileType::Wall))
+            })
+            .collect::<Vec<_>>();
+        lone.into_iter()
+            .for_each(|p| self.map.set(p, TileType::Floor));
+    }
+
+    /// Floor just outside each room that is walled in on both sides
+    fn place_doors(&mut self) {
+        let doors = self
+            .rooms
+            .iter()
+            .flat_map(|room| {
+                let (left, right) = (room.left() as i32, room.right() as i32);
+                let (bottom, top) = (room.bottom() as i32, room.top() as i32);
+                let across = (left..right)
+                    .flat_map(move |x| [MapPosition::new(x, bottom - 1), MapPosition::new(x, top)]);
+                let along = (bottom..top)
+                    .flat_map(move |y| [MapPosition::new(left - 1, y), MapPosition::new(right, y)]);
+                across.chain(along)
+            })
+            .filter(|p| {
+                self.map.in_bounds(*p)
+                    && self.map.value(*p) == TileType::Floor
+                    && !self.is_occupied(*p)
+                    && self.is_corridor(*p)
+            })
+            .collect::<Vec<_>>();
+        doors
+            .into_iter()
+            .for_each(|p| self.map.set(p, TileType::DoorClosed));
+    }
+
+    /// Floor with walls on two opposite sides and floor on the other two
+    fn is_corridor(&self, p: MapPosition) -> bool {
+        let sides = self.orthogonal(p).collect::<Vec<_>>();
+        let wall = |t: Option<TileType>| t.map_or(true, |t| t == TileType::Wall);
+        let open = |t: Option<TileType>| t.is_some_and(|t| t.properties().walkable);
+        // Above and below, then left and right
+        let (vertical, horizontal) = ((sides[0], sides[3]), (sides[1], sides[2]));
+        (wall(vertical.0) && wall(vertical.1) && open(horizontal.0) && open(horizontal.1))
+            || (wall(horizontal.0) && wall(horizontal.1) && open(vertical.0) && open(vertical.1))
+    }
+
+    fn mirror(&mut self, symmetry: Symmetry) {
+        let (width, height) = (self.map.width as i32, self.map.height as i32);
+        if matches!(symmetry, Symmetry::Horizontal | Symmetry::Both) {
+            (0..height).for_each(|y| {
+                (0..width / 2).for_each(|x| {
+                    let tile = self.map.value(MapPosition::new(x, y));
+                    self.map.set(MapPosition::new(width - 1 - x, y), tile);
+                })
+            });
+        }
+        if matches!(symmetry, Symmetry::Vertical | Symmetry::Both) {
+            (0..height / 2).for_each(|y| {
+                (0..width).for_each(|x| {
+                    let tile = self.map.value(MapPosition::new(x, y));
+                    self.map.set(MapPosition::new(x, height - 1 - y), tile);
+                })
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Architect, CaveSettings};
+
+    fn architect() -> ArchitectSettings {
+        ArchitectSettings {
+            architect: Architect::Empty,
+            num_monsters: 3,
+            num_items: 2,
+            num_npcs: 1,
+            entity_distance: 1.0,
+            num_traps: 0,
+            caves: CaveSettings::default(),
+            pipeline: vec![],
+            fixed: None,
+        }
+    }
+
+    #[test]
+    fn test_start_and_exit() {
+        let mut mb = "#######
+                      #.....#
+                      #.###.#
+                      #.#...#
+                      #######"
+            .parse::<MapBuilder>()
+            .unwrap();
+        let mut rng = RngComponent::with_seed(1);
+        [
+            MetaBuilder::Start(StartPosition::Centre),
+            MetaBuilder::Exit(ExitPosition::MostDistant),
+        ]
+        .iter()
+        .for_each(|step| mb.apply(step, &architect(), &mut rng));
+        assert_eq!(mb.player_start, MapPosition::new(3, 1));
+        assert_eq!(mb.winitem_start, MapPosition::new(3, 3));
+
+        mb.apply(
+            &MetaBuilder::Start(StartPosition::LongestPath),
+            &architect(),
+            &mut rng,
+        );
+        assert!([MapPosition::new(1, 3), MapPosition::new(3, 3)].contains(&mb.player_start));
+    }
+
+    #[test]
+    fn test_clean_up_and_doors() {
+        let mut mb = "#######
+                      #.....#
+                      #..#..#
+                      #.....#
+                      ###.###
+                      #.....#
+                      #######"
+            .parse::<MapBuilder>()
+            .unwrap();
+        mb.apply(
+            &MetaBuilder::WallCleanup,
+            &architect(),
+            &mut RngComponent::new(),
+        );
+        assert_eq!(mb.map.value(MapPosition::new(3, 2)), TileType::Floor);
+        assert_eq!(mb.map.value(MapPosition::new(2, 4)), TileType::Wall);
+        // The room is the three rows at the top, its x and y are the centre
+        mb.rooms = vec![nannou_core::prelude::Rect::from_x_y_w_h(3.5, 2.5, 5.0, 3.0)];
+        mb.apply(&MetaBuilder::Doors, &architect(), &mut RngComponent::new());
+        assert_eq!(mb.map.value(MapPosition::new(3, 4)), TileType::DoorClosed);
+        assert_eq!(
+            mb.map
+                .tiles
+                .iter()
+                .filter(|t| **t == TileType::DoorClosed)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_mirror_and_spawns() {
+        let mut mb = "#...#.
+                      ..#...
+                      ......"
+            .parse::<MapBuilder>()
+            .unwrap();
+        mb.apply(
+            &MetaBuilder::Mirror(Symmetry::Horizontal),
+            &architect(),
+            &mut RngComponent::new(),
+        );
+        assert_eq!(mb.map.to_string(), "#....#\n..##..\n......");
+        let mut rng = RngComponent::with_seed(3);
+        mb.apply(&MetaBuilder::AreaSpawns, &architect(), &mut rng);
+        assert_eq!(mb.monster_spawns.len(), 3);
+        assert_eq!(mb.item_spawns.len(), 2);
+        assert_eq!(mb.npc_spawns.len(), 1);
+        assert!(mb
+            .monster_spawns
+            .iter()
+            .all(|p| mb.map.value(*p) == TileType::Floor && p.distance(mb.player_start) > 1.0));
+    }
+}
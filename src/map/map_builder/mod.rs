@@ -1,58 +1,46 @@
 use std::fmt::Display;
+use std::str::FromStr;
 
 use crate::components::map_position::MapPosition;
-use crate::config::{Architect, ArchitectSettings};
+use crate::config::{
+    Architect, ArchitectSettings, ExitPosition, FixedLevel, FixedSpawn, MetaBuilder, Prefab,
+    StartPosition,
+};
 use crate::entities::TileType;
 use bevy::prelude::Resource;
-use bevy::utils::HashSet;
+use bevy::utils::{HashMap, HashSet};
 use bevy_turborand::{DelegatedRng, RngComponent};
+use nannou_core::prelude::Rect;
 
 use self::automata::CellularAutomataArchitect;
 use self::drunkard::DrunkardArchitect;
 use self::empty::EmptyArchitect;
-use self::prefab::apply_prefab;
+use self::fixed::FixedArchitect;
 use self::standard::StandardArchitect;
 
-use super::grid_map::base_map::BaseMap;
+use super::grid_map::base_map::BaseMapMut;
 use super::grid_map::DjikstraMapCalc;
 use super::tile_map::TileMap;
 
 mod automata;
 mod drunkard;
 mod empty;
+mod fixed;
+mod meta;
 mod prefab;
+mod regions;
 mod standard;
-const MAX_ATTEMPTS: usize = 10;
 trait MapArchitect {
-    fn entity_distance(&self) -> f32;
-    fn num_monsters(&self) -> usize;
-    fn num_items(&self) -> usize;
-    fn num_npcs(&self) -> usize;
+    /// Lay out the tiles, the pipeline places everything else
     fn builder(&mut self, height: usize, width: usize, rng: &mut RngComponent) -> MapBuilder;
 
-    fn entity_spawns(
-        &self,
-        start: MapPosition,
-        map: &TileMap,
-        rng: &mut RngComponent,
-        amount: usize,
-    ) -> HashSet<MapPosition> {
-        let tiles = map
-            .tiles
-            .indexed_iter()
-            .map(|(idx, t)| (MapPosition::from_utuple(&idx), t))
-            .filter(|(idx, t)| {
-                **t == TileType::Floor && idx.distance(start) > self.entity_distance()
-            })
-            .map(|(idx, _)| idx)
-            .collect::<Vec<MapPosition>>();
-
-        let spawns = rng
-            .sample_multiple(&tiles, amount)
-            .iter()
-            .map(|f| **f)
-            .collect();
-        spawns
+    /// Steps run when the settings don't give a pipeline
+    fn pipeline(&self) -> Vec<MetaBuilder> {
+        vec![
+            MetaBuilder::Start(StartPosition::Centre),
+            MetaBuilder::Exit(ExitPosition::MostDistant),
+            MetaBuilder::region_spawns(),
+        ]
     }
 }
 
@@ -64,43 +52,42 @@ pub struct MapBuilder {
     pub npc_spawns: HashSet<MapPosition>,
     pub player_start: MapPosition,
     pub winitem_start: MapPosition,
+    /// Rooms the architect made, for steps that work on rooms
+    pub rooms: Vec<Rect>,
+    /// Particular monsters and items placed by a hand-made level
+    pub fixed_spawns: Vec<(MapPosition, FixedSpawn)>,
+    /// Lights standing on floor
+    pub torches: HashSet<MapPosition>,
 }
 
 fn pick_architect(architect: &ArchitectSettings) -> Box<dyn MapArchitect> {
     match architect.architect {
-        Architect::Empty => Box::new(EmptyArchitect::new(
-            architect.num_monsters,
-            architect.num_items,
-            architect.num_npcs,
-            architect.entity_distance,
-        )),
+        Architect::Empty => Box::new(EmptyArchitect),
         Architect::Standard => Box::new(StandardArchitect::new(
-            architect.num_monsters,
-            architect.num_items,
-            architect.num_npcs,
-            architect.entity_distance,
-        )),
-        Architect::Automata => Box::new(CellularAutomataArchitect::new(
-            architect.num_monsters,
-            architect.num_items,
-            architect.num_npcs,
-            architect.entity_distance,
-        )),
-        Architect::Drunkard => Box::new(DrunkardArchitect::new(
-            architect.num_monsters,
             architect.num_items,
-            architect.num_npcs,
-            architect.entity_distance,
+            architect.entity_distance as usize,
         )),
+        Architect::Automata => Box::new(CellularAutomataArchitect::new(architect.caves.clone())),
+        Architect::Drunkard => Box::new(DrunkardArchitect::new()),
+        Architect::Fixed => Box::new(FixedArchitect::new(architect.fixed.clone())),
     }
 }
 
 impl MapBuilder {
+    /// Spawn positions in a fixed order, the sets iterate differently
+    /// each run, so seeded runs would spawn different entities
+    pub fn in_order(spawns: &HashSet<MapPosition>) -> Vec<MapPosition> {
+        let mut spawns = spawns.iter().copied().collect::<Vec<_>>();
+        spawns.sort_by_key(|p| (p.position.y, p.position.x));
+        spawns
+    }
+
     pub fn new(
         mut rng: RngComponent,
         height: usize,
         width: usize,
         architect: &ArchitectSettings,
+        prefabs: &[Prefab],
     ) -> Self
     where
         Self: Sized,
@@ -108,7 +95,26 @@ impl MapBuilder {
         let mut map_arch = pick_architect(architect);
         let mut mb = map_arch.builder(height, width, &mut rng);
 
-        apply_prefab(&mut mb, MAX_ATTEMPTS, &mut rng, 20, 2000);
+        let pipeline = match architect.pipeline.is_empty() {
+            true => map_arch.pipeline(),
+            false => architect.pipeline.clone(),
+        };
+        // Hand-made levels stay as they were drawn
+        let (prefabs, num_traps) = match architect.architect {
+            Architect::Fixed => (&[][..], 0),
+            _ => (prefabs, architect.num_traps),
+        };
+        // The theme's prefabs go on last, clearing whatever was placed under them
+        pipeline
+            .iter()
+            .cloned()
+            .chain(
+                prefabs
+                    .iter()
+                    .map(|prefab| MetaBuilder::Prefab((*prefab).into())),
+            )
+            .for_each(|step| mb.apply(&step, architect, &mut rng));
+        mb.place_traps(&mut rng, num_traps);
         mb
     }
 
@@ -120,6 +126,29 @@ impl MapBuilder {
         self.map.tiles.iter_mut().for_each(|t| *t = tile);
     }
 
+    /// Hide spike traps on floor tiles nothing else is placed on
+    fn place_traps(&mut self, rng: &mut RngComponent, amount: usize) {
+        let tiles = self
+            .map
+            .tiles
+            .indexed_iter()
+            .map(|(idx, t)| (MapPosition::from_utuple(&idx), t))
+            .filter(|(p, t)| **t == TileType::Floor && !self.is_occupied(*p))
+            .map(|(p, _)| p)
+            .collect::<Vec<MapPosition>>();
+        rng.sample_multiple(&tiles, amount)
+            .into_iter()
+            .for_each(|p| self.map.set(*p, TileType::HiddenSpikes));
+    }
+
+    fn is_occupied(&self, p: MapPosition) -> bool {
+        self.player_start == p
+            || self.winitem_start == p
+            || self.monster_spawns.contains(&p)
+            || self.item_spawns.contains(&p)
+            || self.npc_spawns.contains(&p)
+    }
+
     fn fill_in_unreachable(&mut self) {
         self.map
             .djikstra_map(self.player_start)
@@ -129,12 +158,11 @@ impl MapBuilder {
                 self.map.set(*p, TileType::Wall);
             });
     }
-}
 
-impl Display for MapBuilder {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let str_tiles = self
-            .map
+    /// The map as text, with `mark` giving the glyph of anything standing
+    /// on a tile
+    pub fn render(&self, mark: impl Fn(MapPosition) -> Option<char>) -> String {
+        self.map
             .tiles
             .rows()
             .into_iter()
@@ -144,31 +172,146 @@ impl Display for MapBuilder {
                     .enumerate()
                     .map(|(y, tile)| {
                         let mp = MapPosition::from_utuple(&(x, y));
-                        if self.player_start == mp {
-                            "@".to_string()
-                        } else if self.winitem_start == mp {
-                            "?".to_string()
-                        } else if self.monster_spawns.contains(&mp) {
-                            "M".to_string()
-                        } else if self.npc_spawns.contains(&mp) {
-                            "N".to_string()
-                        } else if self.item_spawns.contains(&mp) {
-                            "I".to_string()
-                        } else {
-                            format!("{}", tile)
+                        match mark(mp) {
+                            Some(glyph) => glyph.to_string(),
+                            None => format!("{}", tile),
                         }
                     })
                     .collect::<String>()
             })
             .collect::<Vec<String>>()
-            .join("\n");
+            .join("\n")
+    }
+}
+
+impl Display for MapBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str_tiles = self.render(|mp| {
+            if self.player_start == mp {
+                Some('@')
+            } else if self.winitem_start == mp {
+                Some('?')
+            } else if self.monster_spawns.contains(&mp) {
+                Some('M')
+            } else if self.npc_spawns.contains(&mp) {
+                Some('N')
+            } else if self.item_spawns.contains(&mp) {
+                Some('I')
+            } else if self.torches.contains(&mp) {
+                Some('T')
+            } else {
+                None
+            }
+        });
         f.write_fmt(format_args!("{}", str_tiles))
     }
 }
 
+/// Reads the [`Display`] format back, the entities stand on floor
+impl FromStr for MapBuilder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, &HashMap::new())
+    }
+}
+
+impl MapBuilder {
+    /// Read a hand-made level, it needs a start and somewhere to end
+    pub fn from_fixed(level: &FixedLevel) -> Result<Self, String> {
+        let text = level.read()?;
+        let mut mb = Self::parse_with(&text, &level.glyphs)?;
+        if !text.contains('@') {
+            return Err("no @ to start at".to_string());
+        }
+        if !text.contains('?') {
+            mb.winitem_start = mb
+                .map
+                .tiles
+                .indexed_iter()
+                .find(|(_, t)| **t == TileType::Exit)
+                .map(|(idx, _)| MapPosition::from_utuple(&idx))
+                .ok_or("no ? or > to end at")?;
+        }
+        Ok(mb)
+    }
+
+    /// Read the [`Display`] format, with `glyphs` standing for particular
+    /// monsters and items on floor
+    fn parse_with(s: &str, glyphs: &HashMap<char, FixedSpawn>) -> Result<Self, String> {
+        let rows = s
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .collect::<Vec<_>>();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        let mut mb = MapBuilder {
+            map: TileMap::new(rows.len(), width),
+            ..Default::default()
+        };
+        rows.iter().enumerate().try_for_each(|(y, row)| {
+            if row.chars().count() != width {
+                return Err(format!("row {} is not {} tiles wide", y, width));
+            }
+            row.chars().enumerate().try_for_each(|(x, glyph)| {
+                let mp = MapPosition::from_utuple(&(y, x));
+                if let Some(spawn) = glyphs.get(&glyph) {
+                    mb.fixed_spawns.push((mp, spawn.clone()));
+                    return Ok(());
+                }
+                match glyph {
+                    '@' => mb.player_start = mp,
+                    '?' => mb.winitem_start = mp,
+                    'M' => {
+                        mb.monster_spawns.insert(mp);
+                    }
+                    'N' => {
+                        mb.npc_spawns.insert(mp);
+                    }
+                    'I' => {
+                        mb.item_spawns.insert(mp);
+                    }
+                    'T' => {
+                        mb.torches.insert(mp);
+                    }
+                    _ => mb.map.set(
+                        mp,
+                        TileType::try_from(glyph)
+                            .map_err(|g| format!("unknown glyph {:?} at {}, {}", g, x, y))?,
+                    ),
+                }
+                Ok(())
+            })
+        })?;
+        Ok(mb)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::{config::CaveSettings, map::grid_map::base_map::BaseMap};
+
     use super::*;
+
+    #[test]
+    fn test_from_str() {
+        let text = "#####\n#@.M#\n#I_?#\n#T###";
+        let mb = text.parse::<MapBuilder>().unwrap();
+        assert_eq!(mb.player_start, MapPosition::new(1, 1));
+        assert_eq!(mb.winitem_start, MapPosition::new(3, 2));
+        assert!(mb.monster_spawns.contains(&MapPosition::new(3, 1)));
+        assert!(mb.item_spawns.contains(&MapPosition::new(1, 2)));
+        assert_eq!(
+            mb.map.value(MapPosition::new(2, 2)),
+            TileType::PressurePlate
+        );
+        assert_eq!(mb.map.value(MapPosition::new(3, 1)), TileType::Floor);
+        assert!(mb.torches.contains(&MapPosition::new(1, 3)));
+        assert_eq!(mb.to_string(), text);
+
+        assert!("##\n#".parse::<MapBuilder>().is_err());
+        assert!("#x".parse::<MapBuilder>().is_err());
+    }
     #[test]
     fn build() {
         let rng = RngComponent::new();
@@ -182,7 +325,12 @@ mod tests {
                 num_items: 10,
                 num_npcs: 5,
                 entity_distance: 10.0,
+                num_traps: 10,
+                caves: CaveSettings::default(),
+                pipeline: vec![],
+                fixed: None,
             },
+            &[Prefab::Fortress],
         );
         println!("{}", mb);
     }
@@ -200,7 +348,12 @@ mod tests {
                     num_items: 10,
                     num_npcs: 5,
                     entity_distance: 10.0,
+                    num_traps: 10,
+                    caves: CaveSettings::default(),
+                    pipeline: vec![],
+                    fixed: None,
                 },
+                &[Prefab::Fortress, Prefab::Pillars],
             );
         });
     }
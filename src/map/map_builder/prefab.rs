@@ -3,31 +3,45 @@ use bevy_turborand::{DelegatedRng, RngComponent};
 
 use crate::{
     components::map_position::MapPosition,
+    config::Prefab,
     entities::TileType,
-    map::grid_map::{base_map::BaseMap, DjikstraMapCalc},
+    map::grid_map::{
+        base_map::{BaseMap, BaseMapMut},
+        DjikstraMapCalc,
+    },
 };
 
 use super::MapBuilder;
 
 #[derive(Debug, Clone)]
-struct Fortress {
+struct PrefabLayout {
     input: String,
     height: usize,
     width: usize,
 }
 
-impl Default for Fortress {
-    fn default() -> Self {
+impl From<&Prefab> for PrefabLayout {
+    fn from(prefab: &Prefab) -> Self {
+        match prefab {
+            Prefab::Fortress => Self::fortress(),
+            Prefab::Pillars => Self::pillars(),
+            Prefab::Vault => Self::vault(),
+        }
+    }
+}
+
+impl PrefabLayout {
+    fn fortress() -> Self {
         Self {
             input: "
         .........
         ..#####..
-        ..#...#..
+        ..#.T.#..
         ..#.M.#..
         .##...##.
         .M.....M.
         .##...##.
-        ..#...#..
+        ..#.T.#..
         ..#.M.#..
         ..#####..
         .........
@@ -37,70 +51,178 @@ impl Default for Fortress {
             width: 9,
         }
     }
+
+    fn pillars() -> Self {
+        Self {
+            input: "
+        .........
+        .#..#..#.
+        .........
+        ...#M#...
+        .........
+        .#..#..#.
+        .........
+        "
+            .to_string(),
+            height: 7,
+            width: 9,
+        }
+    }
+
+    /// Treasure behind a door, with a plate that opens the inner walls
+    fn vault() -> Self {
+        Self {
+            input: "
+        ...........
+        .####+####.
+        .#..,.,..#.
+        .#.#%%%#.#.
+        .#_#.M.#.#.
+        .#.#%I%#.#.
+        .#...T...#.
+        .#########.
+        ...........
+        "
+            .to_string(),
+            height: 9,
+            width: 11,
+        }
+    }
 }
 
+/// Stamp the prefab on a random spot where every tile it covers is reachable
+/// at a walk between `min` and `max`, away from both starts. The level is left
+/// alone when there is no such spot
 pub fn apply_prefab(
     map_builder: &mut MapBuilder,
-    max_attempts: usize,
+    prefab: &Prefab,
     rng: &mut RngComponent,
     min: i32,
     max: i32,
 ) {
-    let fortress = Fortress::default();
-    let mut placed = false;
+    let layout = PrefabLayout::from(prefab);
     let dmap = map_builder.map.djikstra_map(map_builder.player_start);
+    if dmap.width() < layout.width || dmap.height() < layout.height {
+        return;
+    }
 
-    let mut attempts = 0;
-    while !placed && attempts < max_attempts {
-        let poss_rect = (
-            rng.usize(0..(dmap.width() - fortress.width)),
-            rng.usize(0..(dmap.height() - fortress.height)),
-        );
-        let mut can_place = false;
-        (poss_rect.0..poss_rect.0 + fortress.width + 1).for_each(|x| {
-            (poss_rect.1..poss_rect.1 + fortress.height + 1).for_each(|y| {
+    let fits = |&(left, top): &(usize, usize)| {
+        (left..left + layout.width).all(|x| {
+            (top..top + layout.height).all(|y| {
                 let mp = MapPosition::from_utuple(&(y, x));
-                let distance = dmap.value(mp);
-                if distance.is_some()
-                    && distance.map(|v| v > min && v < max).unwrap()
+                dmap.value(mp).is_some_and(|v| v > min && v < max)
+                    && map_builder.player_start != mp
                     && map_builder.winitem_start != mp
-                {
-                    can_place = true;
-                }
             })
-        });
-        if can_place {
-            place_fortress(map_builder, &fortress, &poss_rect);
-            placed = true;
-        }
-        attempts += 1;
+        })
+    };
+    let spots = (0..=(dmap.height() - layout.height))
+        .flat_map(|top| (0..=(dmap.width() - layout.width)).map(move |left| (left, top)))
+        .filter(fits)
+        .collect::<Vec<_>>();
+    if let Some(spot) = rng.sample(&spots).copied() {
+        place_prefab(map_builder, &layout, &spot);
     }
 }
 
-fn place_fortress(map_builder: &mut MapBuilder, fortress: &Fortress, placement: &(usize, usize)) {
-    fortress
+fn place_prefab(map_builder: &mut MapBuilder, layout: &PrefabLayout, placement: &(usize, usize)) {
+    layout
         .input
         .trim()
         .split('\n')
-        .into_iter()
         .enumerate()
         .for_each(|(y, s)| {
-            s.trim()
-                .split("")
-                .into_iter()
-                .enumerate()
-                .for_each(|(x, cs)| {
-                    let p = MapPosition::from_utuple(&(y + placement.1, x + placement.0));
-                    map_builder.monster_spawns.remove(&p);
-                    match cs {
-                        "." => map_builder.map.set(p, TileType::Floor),
-                        "M" => {
-                            map_builder.map.set(p, TileType::Floor);
-                            map_builder.monster_spawns.insert(p);
-                        }
-                        "#" => map_builder.map.set(p, TileType::Wall),
-                        _ => info!("nothing to match on {}", cs),
-                    };
-                });
+            s.trim().chars().enumerate().for_each(|(x, c)| {
+                let p = MapPosition::from_utuple(&(y + placement.1, x + placement.0));
+                map_builder.monster_spawns.remove(&p);
+                map_builder.item_spawns.remove(&p);
+                map_builder.npc_spawns.remove(&p);
+                map_builder.torches.remove(&p);
+                match c {
+                    'M' => {
+                        map_builder.map.set(p, TileType::Floor);
+                        map_builder.monster_spawns.insert(p);
+                    }
+                    'I' => {
+                        map_builder.map.set(p, TileType::Floor);
+                        map_builder.item_spawns.insert(p);
+                    }
+                    'N' => {
+                        map_builder.map.set(p, TileType::Floor);
+                        map_builder.npc_spawns.insert(p);
+                    }
+                    'T' => {
+                        map_builder.map.set(p, TileType::Floor);
+                        map_builder.torches.insert(p);
+                    }
+                    _ => match TileType::try_from(c) {
+                        Ok(tile) => map_builder.map.set(p, tile),
+                        Err(_) => info!("nothing to match on {}", c),
+                    },
+                };
+            });
         });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{Architect, ArchitectSettings, CaveSettings},
+        map::tile_map::TileMap,
+    };
+
+    #[test]
+    fn test_place_prefab_round_trip() {
+        let layout = PrefabLayout::from(&Prefab::Vault);
+        let mut mb = MapBuilder {
+            map: TileMap::new(layout.height + 1, layout.width + 1),
+            player_start: MapPosition::new(layout.width as i32, layout.height as i32),
+            winitem_start: MapPosition::new(layout.width as i32, layout.height as i32),
+            ..Default::default()
+        };
+        place_prefab(&mut mb, &layout, &(0, 0));
+        let drawn = format!("{}", mb);
+        drawn
+            .lines()
+            .zip(layout.input.trim().lines())
+            .for_each(|(drawn_row, prefab_row)| {
+                assert_eq!(&drawn_row[..layout.width], prefab_row.trim());
+            });
+    }
+
+    #[test]
+    fn test_prefabs_keep_starts_reachable() {
+        [
+            Architect::Standard,
+            Architect::Automata,
+            Architect::Drunkard,
+        ]
+        .into_iter()
+        .for_each(|architect| {
+            (0..50).for_each(|seed| {
+                let mb = MapBuilder::new(
+                    RngComponent::with_seed(seed),
+                    40,
+                    80,
+                    &ArchitectSettings {
+                        architect,
+                        num_monsters: 10,
+                        num_items: 5,
+                        num_npcs: 2,
+                        entity_distance: 10.0,
+                        num_traps: 5,
+                        caves: CaveSettings::default(),
+                        pipeline: vec![],
+                        fixed: None,
+                    },
+                    &[Prefab::Fortress, Prefab::Pillars, Prefab::Vault],
+                );
+                assert!(mb.map.can_enter_tile(mb.player_start), "{}", mb);
+                assert!(mb.map.can_enter_tile(mb.winitem_start), "{}", mb);
+                let dmap = mb.map.djikstra_map(mb.player_start);
+                assert!(dmap.value(mb.winitem_start).is_some(), "{}", mb);
+            })
+        });
+    }
+}
@@ -0,0 +1,249 @@
+use bevy::utils::HashSet;
+use bevy_turborand::{DelegatedRng, RngComponent};
+use nannou_core::prelude::Rect;
+
+use crate::{
+    components::map_position::MapPosition,
+    config::ArchitectSettings,
+    map::grid_map::{base_map::BaseMap, DjikstraMapCalc},
+};
+
+use super::MapBuilder;
+
+impl MapBuilder {
+    /// Share the spawns out over the regions by their size, taking each
+    /// tile once
+    pub(super) fn region_spawns(
+        &mut self,
+        architect: &ArchitectSettings,
+        regions: usize,
+        rng: &mut RngComponent,
+    ) {
+        let candidates = self.spawn_candidates(architect.entity_distance);
+        let mut free = self.regions(&candidates, regions, rng);
+        let sizes = free.iter().map(Vec::len).collect::<Vec<_>>();
+        self.monster_spawns = take_spawns(&mut free, &sizes, architect.num_monsters, rng);
+        self.item_spawns = take_spawns(&mut free, &sizes, architect.num_items, rng);
+        self.npc_spawns = take_spawns(&mut free, &sizes, architect.num_npcs, rng);
+    }
+
+    /// Spawns anywhere on the floor away from the start, taking each tile once
+    pub(super) fn area_spawns(&mut self, architect: &ArchitectSettings, rng: &mut RngComponent) {
+        let mut free = vec![self.spawn_candidates(architect.entity_distance)];
+        let sizes = vec![free[0].len()];
+        self.monster_spawns = take_spawns(&mut free, &sizes, architect.num_monsters, rng);
+        self.item_spawns = take_spawns(&mut free, &sizes, architect.num_items, rng);
+        self.npc_spawns = take_spawns(&mut free, &sizes, architect.num_npcs, rng);
+    }
+
+    /// A monster and an item in every room but the first, on their own tiles
+    pub(super) fn room_spawns(&mut self, architect: &ArchitectSettings, rng: &mut RngComponent) {
+        let candidates = self.spawn_candidates(architect.entity_distance);
+        let mut free = self
+            .rooms
+            .iter()
+            .skip(1)
+            .map(|room| in_room(&candidates, room))
+            .filter(|region| !region.is_empty())
+            .collect::<Vec<_>>();
+        // Counting every room the same takes one from each in turn
+        let sizes = vec![1; free.len()];
+        self.monster_spawns = take_spawns(&mut free, &sizes, sizes.len(), rng);
+        self.item_spawns = take_spawns(&mut free, &sizes, sizes.len(), rng);
+        self.npc_spawns = HashSet::new();
+    }
+
+    /// Floor the player can walk to, further than `entity_distance` from the
+    /// start and off the win item
+    fn spawn_candidates(&self, entity_distance: f32) -> Vec<MapPosition> {
+        let dmap = self.map.djikstra_map(self.player_start);
+        self.floor()
+            .into_iter()
+            .filter(|p| {
+                *p != self.player_start
+                    && *p != self.winitem_start
+                    && dmap.value(*p).is_some()
+                    && p.distance(self.player_start) > entity_distance
+            })
+            .collect()
+    }
+
+    /// The candidates in each room, or around `regions` random seeds when
+    /// none are in a room
+    fn regions(
+        &self,
+        candidates: &[MapPosition],
+        regions: usize,
+        rng: &mut RngComponent,
+    ) -> Vec<Vec<MapPosition>> {
+        let in_rooms = self
+            .rooms
+            .iter()
+            .map(|room| in_room(candidates, room))
+            .filter(|region| !region.is_empty())
+            .collect::<Vec<_>>();
+        if !in_rooms.is_empty() {
+            return in_rooms;
+        }
+        let seeds = rng.sample_multiple(candidates, regions);
+        let mut areas = vec![vec![]; seeds.len()];
+        candidates.iter().for_each(|p| {
+            let nearest = seeds
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| p.distance(***a).total_cmp(&p.distance(***b)))
+                .map(|(i, _)| i)
+                .unwrap();
+            areas[nearest].push(*p);
+        });
+        areas
+    }
+}
+
+/// The candidates inside a room
+fn in_room(candidates: &[MapPosition], room: &Rect) -> Vec<MapPosition> {
+    let (left, right) = (room.left() as i32, room.right() as i32);
+    let (bottom, top) = (room.bottom() as i32, room.top() as i32);
+    candidates
+        .iter()
+        .filter(|p| (left..right).contains(&p.position.x) && (bottom..top).contains(&p.position.y))
+        .copied()
+        .collect()
+}
+
+/// Take `amount` tiles, each from the region its next spawn leaves least
+/// crowded for its size
+fn take_spawns(
+    free: &mut [Vec<MapPosition>],
+    sizes: &[usize],
+    amount: usize,
+    rng: &mut RngComponent,
+) -> HashSet<MapPosition> {
+    let mut taken = vec![0; free.len()];
+    let crowding = |taken: &[usize], i: usize| (taken[i] + 1) as f32 / sizes[i] as f32;
+    (0..amount)
+        .filter_map(|_| {
+            let region = (0..free.len())
+                .filter(|i| !free[*i].is_empty())
+                .min_by(|a, b| crowding(&taken, *a).total_cmp(&crowding(&taken, *b)))?;
+            taken[region] += 1;
+            let tile = rng.usize(0..free[region].len());
+            Some(free[region].swap_remove(tile))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Architect, CaveSettings, MetaBuilder};
+
+    fn architect() -> ArchitectSettings {
+        ArchitectSettings {
+            architect: Architect::Empty,
+            num_monsters: 20,
+            num_items: 10,
+            num_npcs: 4,
+            entity_distance: 2.0,
+            num_traps: 0,
+            caves: CaveSettings::default(),
+            pipeline: vec![],
+            fixed: None,
+        }
+    }
+
+    fn open_map() -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: crate::map::tile_map::TileMap::new(12, 30),
+            player_start: MapPosition::new(1, 1),
+            winitem_start: MapPosition::new(28, 10),
+            ..Default::default()
+        };
+        mb.fill(crate::entities::TileType::Floor);
+        mb
+    }
+
+    fn all_spawns(mb: &MapBuilder) -> Vec<MapPosition> {
+        mb.monster_spawns
+            .iter()
+            .chain(mb.item_spawns.iter())
+            .chain(mb.npc_spawns.iter())
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn test_region_spawns_apart() {
+        // Three rooms for the room spawns, the first is left out
+        let rooms = vec![
+            Rect::from_x_y_w_h(2.0, 2.5, 4.0, 3.0),
+            Rect::from_x_y_w_h(8.0, 4.5, 4.0, 3.0),
+            Rect::from_x_y_w_h(27.0, 9.5, 4.0, 3.0),
+        ];
+        [
+            (MetaBuilder::AreaSpawns, vec![], (20, 10, 4)),
+            (MetaBuilder::RoomSpawns, rooms, (2, 2, 0)),
+            (MetaBuilder::region_spawns(), vec![], (20, 10, 4)),
+        ]
+        .into_iter()
+        .for_each(|(step, rooms, counts)| {
+            let mut mb = open_map();
+            mb.rooms = rooms;
+            let mut rng = RngComponent::with_seed(5);
+            mb.apply(&step, &architect(), &mut rng);
+            assert_eq!(
+                (
+                    mb.monster_spawns.len(),
+                    mb.item_spawns.len(),
+                    mb.npc_spawns.len()
+                ),
+                counts,
+                "{:?}",
+                step
+            );
+            let spawns = all_spawns(&mb);
+            assert_eq!(spawns.iter().collect::<HashSet<_>>().len(), spawns.len());
+            assert!(spawns
+                .iter()
+                .all(|p| *p != mb.winitem_start && p.distance(mb.player_start) > 2.0));
+        });
+    }
+
+    #[test]
+    fn test_regions_share() {
+        let mb = open_map();
+        let mut rng = RngComponent::with_seed(2);
+        let candidates = mb.spawn_candidates(2.0);
+        let mut regions = mb.regions(&candidates, 4, &mut rng);
+        assert_eq!(regions.len(), 4);
+        assert_eq!(
+            regions.iter().map(Vec::len).sum::<usize>(),
+            candidates.len()
+        );
+        // Each region gets at least its share by size, rounded down
+        let sizes = regions.iter().map(Vec::len).collect::<Vec<_>>();
+        take_spawns(&mut regions, &sizes, 8, &mut rng);
+        assert!(sizes
+            .iter()
+            .zip(regions.iter())
+            .all(|(size, free)| size - free.len() >= 8 * size / candidates.len()));
+    }
+
+    #[test]
+    fn test_room_spawns() {
+        let mut mb = open_map();
+        // Two rooms of 4 by 3 tiles, the spawns only fit in those
+        mb.rooms = vec![
+            Rect::from_x_y_w_h(8.0, 4.5, 4.0, 3.0),
+            Rect::from_x_y_w_h(20.0, 7.5, 4.0, 3.0),
+        ];
+        let mut rng = RngComponent::with_seed(1);
+        mb.apply(&MetaBuilder::region_spawns(), &architect(), &mut rng);
+        let spawns = all_spawns(&mb);
+        assert_eq!(spawns.len(), 24);
+        assert!(spawns.iter().all(|p| {
+            ((6..10).contains(&p.position.x) && (3..6).contains(&p.position.y))
+                || ((18..22).contains(&p.position.x) && (6..9).contains(&p.position.y))
+        }));
+    }
+}
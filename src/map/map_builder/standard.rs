@@ -1,4 +1,4 @@
-use bevy::{prelude::*, utils::HashSet};
+use bevy::prelude::*;
 use bevy_turborand::{
     rng::{Rng, TurboRand},
     DelegatedRng, RngComponent,
@@ -7,11 +7,9 @@ use nannou_core::prelude::Rect;
 
 use crate::{
     components::map_position::MapPosition,
+    config::{ExitPosition, MetaBuilder, StartPosition},
     entities::TileType,
-    map::{
-        grid_map::DjikstraMapCalc,
-        tile_map::{in_bounds, TileMap},
-    },
+    map::tile_map::{in_bounds, TileMap},
 };
 
 use super::{MapArchitect, MapBuilder};
@@ -28,31 +26,6 @@ pub struct StandardArchitect {
 }
 
 impl MapArchitect for StandardArchitect {
-    fn entity_distance(&self) -> f32 {
-        0.0
-    }
-    fn num_monsters(&self) -> usize {
-        self.num_rooms
-    }
-    fn num_items(&self) -> usize {
-        self.num_rooms
-    }
-    fn num_npcs(&self) -> usize {
-        self.num_rooms
-    }
-    fn entity_spawns(
-        &self,
-        _: MapPosition,
-        _: &TileMap,
-        _: &mut RngComponent,
-        _: usize,
-    ) -> HashSet<MapPosition> {
-        self.rooms
-            .iter()
-            .skip(1)
-            .map(|room| MapPosition::new(room.x() as i32, room.y() as i32))
-            .collect()
-    }
     fn builder(&mut self, height: usize, width: usize, rng: &mut RngComponent) -> MapBuilder {
         let mut mb = MapBuilder {
             map: TileMap::new(height, width),
@@ -69,30 +42,26 @@ impl MapArchitect for StandardArchitect {
             self.num_rooms,
         );
         self.build_corridors(&self.rooms.clone(), &mut mb.map, rng);
-        mb.monster_spawns =
-            self.entity_spawns(MapPosition::ZERO, &mb.map, rng, self.num_monsters());
-        mb.item_spawns = self.entity_spawns(MapPosition::ZERO, &mb.map, rng, self.num_items());
-        let dmap = mb.map.djikstra_map(MapPosition::new(
-            self.rooms[0].x() as i32,
-            self.rooms[0].y() as i32,
-        ));
-        let longest_path = dmap.calculate_longest_path();
-        mb.player_start = longest_path[0];
-        mb.winitem_start = *longest_path.last().unwrap();
+        // The longest path is measured from the first room
+        mb.player_start = MapPosition::new(self.rooms[0].x() as i32, self.rooms[0].y() as i32);
+        mb.rooms = self.rooms.clone();
         mb
     }
+
+    fn pipeline(&self) -> Vec<MetaBuilder> {
+        vec![
+            MetaBuilder::Start(StartPosition::LongestPath),
+            MetaBuilder::Exit(ExitPosition::MostDistant),
+            MetaBuilder::region_spawns(),
+        ]
+    }
 }
 
 impl StandardArchitect {
-    pub fn new(
-        _num_monsters: usize,
-        num_items: usize,
-        _num_npcs: usize,
-        entity_distance: f32,
-    ) -> Self {
+    pub fn new(num_rooms: usize, max_room_size: usize) -> Self {
         Self {
-            max_room_size: entity_distance as usize,
-            num_rooms: num_items,
+            max_room_size,
+            num_rooms,
             rooms: Vec::new(),
         }
     }
@@ -121,17 +90,13 @@ impl StandardArchitect {
                 }
             }
             if !overlap {
-                (room.left() as i32..room.right() as i32)
-                    .into_iter()
-                    .for_each(|x| {
-                        (room.bottom() as i32..room.top() as i32)
-                            .into_iter()
-                            .for_each(|y| {
-                                if in_bounds(IVec2::from_array([x, y]), width, height) {
-                                    map.tiles[[y as usize, x as usize]] = TileType::Floor;
-                                }
-                            });
+                (room.left() as i32..room.right() as i32).for_each(|x| {
+                    (room.bottom() as i32..room.top() as i32).for_each(|y| {
+                        if in_bounds(IVec2::from_array([x, y]), width, height) {
+                            map.tiles[[y as usize, x as usize]] = TileType::Floor;
+                        }
                     });
+                });
 
                 rooms.push(room);
             }
@@ -162,7 +127,7 @@ impl StandardArchitect {
 
     fn build_corridors(&mut self, in_rooms: &[Rect], map: &mut TileMap, rng: &mut RngComponent) {
         let mut rooms = Vec::from(in_rooms);
-        rooms.sort_by(|a, b| (a.xy().x as i32).cmp(&(b.xy().x as i32)));
+        rooms.sort_by_key(|a| a.xy().x as i32);
         for (i, room) in rooms.iter().enumerate().skip(1) {
             let prev = rooms[i - 1].xy();
             let new = room.xy();
@@ -207,7 +172,7 @@ mod tests {
     use super::*;
     #[test]
     fn build() {
-        let mut arch = StandardArchitect::new(50, 20, 10, 10.0);
+        let mut arch = StandardArchitect::new(20, 10);
         let mut rng = RngComponent::new();
         let mb = arch.builder(40, 80, &mut rng);
         println!("{}", mb);
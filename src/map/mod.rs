@@ -10,10 +10,11 @@ use crate::{
     config::Settings,
     entities::{MapLevel, TileType},
     stages::TurnState,
+    theme::{ActiveTheme, THEME_LABEL},
     GameState,
 };
 
-use self::{grid_map::base_map::BaseMap, map_builder::MapBuilder};
+use self::{grid_map::base_map::BaseMapMut, map_builder::MapBuilder};
 
 pub struct MapPlugin;
 
@@ -22,7 +23,8 @@ pub const GEN_MAP_LABEL: &str = "GenearateMapBuilder";
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(
-            SystemSet::on_enter(GameState::Generation).with_system(insert_mapbuilder),
+            SystemSet::on_enter(GameState::Generation)
+                .with_system(insert_mapbuilder.after(THEME_LABEL)),
         )
         .add_system_set(
             SystemSet::on_update(GameState::Playing)
@@ -37,13 +39,15 @@ fn respawn_map(
     mut map_builder: ResMut<MapBuilder>,
     mut rng: ResMut<GlobalRng>,
     settings: Res<Settings>,
+    theme: Res<ActiveTheme>,
     level: Query<&MapLevel>,
 ) {
     let mut mb = MapBuilder::new(
         RngComponent::from(&mut rng),
         settings.map_settings.height,
         settings.map_settings.width,
-        &settings.map_settings.architect,
+        &theme.architect(&settings),
+        &theme.prefabs(&settings),
     );
 
     #[cfg(debug_assertions)]
@@ -57,12 +61,18 @@ fn respawn_map(
     *map_builder = mb;
 }
 
-fn insert_mapbuilder(mut commands: Commands, mut rng: ResMut<GlobalRng>, settings: Res<Settings>) {
+fn insert_mapbuilder(
+    mut commands: Commands,
+    mut rng: ResMut<GlobalRng>,
+    settings: Res<Settings>,
+    theme: Res<ActiveTheme>,
+) {
     let mut mb = MapBuilder::new(
         RngComponent::from(&mut rng),
         settings.map_settings.height,
         settings.map_settings.width,
-        &settings.map_settings.architect,
+        &theme.architect(&settings),
+        &theme.prefabs(&settings),
     );
     mb.map.set(mb.winitem_start, TileType::Exit);
     commands.insert_resource(mb);
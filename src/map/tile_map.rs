@@ -1,11 +1,13 @@
 use bevy::prelude::*;
 use ndarray::{Array, Ix2};
 use std::fmt::Display;
-use std::usize;
 
 use crate::{components::map_position::MapPosition, entities::TileType};
 
-use super::grid_map::{base_map::BaseMap, DjikstraMapCalc};
+use super::grid_map::{
+    base_map::{BaseMap, BaseMapMut},
+    DjikstraMapCalc,
+};
 
 #[derive(Default, Debug)]
 pub struct TileMap {
@@ -28,7 +30,7 @@ impl BaseMap for TileMap {
             && self
                 .tiles
                 .get(point.as_utuple())
-                .map_or(false, |&s| s == TileType::Floor || s == TileType::Exit)
+                .is_some_and(|s| s.properties().walkable)
     }
     fn height(&self) -> usize {
         self.height
@@ -41,7 +43,9 @@ impl BaseMap for TileMap {
     fn value(&self, p: MapPosition) -> Self::Output {
         self.tiles[p.as_utuple()]
     }
+}
 
+impl BaseMapMut for TileMap {
     fn set(&mut self, p: MapPosition, value: Self::Output) {
         self.tiles[p.as_utuple()] = value;
     }
@@ -78,8 +82,46 @@ impl TileMap {
     pub fn in_bounds(&self, point: MapPosition) -> bool {
         in_bounds(point.position, self.width, self.height)
     }
+
+    pub fn blocks_sight(&self, point: MapPosition) -> bool {
+        !self.in_bounds(point) || self.value(point).properties().blocks_sight
+    }
+
+    /// Walls can be dug out, except for the border that keeps the level closed
+    pub fn is_diggable(&self, point: MapPosition) -> bool {
+        let p = point.position;
+        p.x > 0
+            && p.y > 0
+            && p.x < self.width as i32 - 1
+            && p.y < self.height as i32 - 1
+            && self.value(point) == TileType::Wall
+    }
 }
 
+/// View of a [`TileMap`] where diggable walls can be entered, for
+/// actors that smash their way through
+pub struct DiggableMap<'a>(pub &'a TileMap);
+
+impl BaseMap for DiggableMap<'_> {
+    type Output = TileType;
+    fn can_enter_tile(&self, point: MapPosition) -> bool {
+        self.0.can_enter_tile(point) || self.0.is_diggable(point)
+    }
+    fn height(&self) -> usize {
+        self.0.height
+    }
+
+    fn width(&self) -> usize {
+        self.0.width
+    }
+
+    fn value(&self, p: MapPosition) -> Self::Output {
+        self.0.value(p)
+    }
+}
+
+impl DjikstraMapCalc for DiggableMap<'_> {}
+
 #[test]
 fn test_djikstra() {
     let map = TileMap::new(10, 20);
@@ -88,3 +130,15 @@ fn test_djikstra() {
     let dmap = map.djikstra_map(start);
     assert_eq!(dmap.value(MapPosition::new(1, 1)), Some(2));
 }
+
+#[test]
+fn test_diggable_djikstra() {
+    let mut map = TileMap::new(3, 5);
+    (0..5).for_each(|x| map.set(MapPosition::new(x, 1), TileType::Wall));
+    let start = MapPosition::new(2, 0);
+    assert!(map.is_diggable(MapPosition::new(2, 1)));
+    assert!(!map.is_diggable(MapPosition::new(0, 1)));
+    assert_eq!(map.djikstra_map(start).value(MapPosition::new(2, 2)), None);
+    let dmap = DiggableMap(&map).djikstra_map(start);
+    assert_eq!(dmap.value(MapPosition::new(2, 2)), Some(2));
+}
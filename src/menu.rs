@@ -1,4 +1,9 @@
+use crate::config::Settings;
 use crate::loading::FontAssets;
+use crate::options::OptionsScreen;
+use crate::rebind::RebindScreen;
+use crate::systems::stats::{HighScores, RunStats};
+use crate::theme::ThemeChoice;
 use crate::GameState;
 use bevy::prelude::*;
 
@@ -20,7 +25,13 @@ impl Plugin for MenuPlugin {
         app.init_resource::<ButtonColors>()
             .init_resource::<PlayerMessage>()
             .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(setup_menu))
-            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(click_play_button))
+            .add_system_set(
+                SystemSet::on_update(GameState::Menu)
+                    .with_system(click_play_button)
+                    .with_system(click_theme_button)
+                    .with_system(click_controls_button)
+                    .with_system(click_options_button),
+            )
             .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(cleanup_menu));
     }
 }
@@ -42,11 +53,11 @@ impl Default for PlayerMessage {
 
 /// Colors of the button
 #[derive(Resource)]
-struct ButtonColors {
+pub struct ButtonColors {
     /// Color with no hover
-    normal: BackgroundColor,
+    pub normal: BackgroundColor,
     /// color with hover
-    hovered: BackgroundColor,
+    pub hovered: BackgroundColor,
 }
 
 impl Default for ButtonColors {
@@ -62,19 +73,72 @@ impl Default for ButtonColors {
 #[derive(Component)]
 struct Menu;
 
+/// Button that starts the game
+#[derive(Component)]
+struct PlayButton;
+
+/// Button that cycles through the dungeon themes
+#[derive(Component)]
+struct ThemeButton;
+
+/// Button that opens the rebinding screen
+#[derive(Component)]
+struct ControlsButton;
+
+/// Button that opens the options screen
+#[derive(Component)]
+struct OptionsButton;
+
+/// Label for the theme button
+fn theme_label(choice: &ThemeChoice) -> String {
+    format!("Theme: {}", choice.0.as_deref().unwrap_or("Per level"))
+}
+
+/// The last run's score and statistics, and the high-score table
+fn high_scores_text(high_scores: &HighScores, stats: &RunStats) -> String {
+    let last = high_scores
+        .last
+        .as_ref()
+        .map(|s| {
+            format!(
+                "Score: {}\n{} turns, {} kills, {} damage dealt, {} taken, \
+                 {} items used, {} quests\n\n",
+                s.describe(),
+                stats.turns,
+                stats.total_kills(),
+                stats.damage_dealt,
+                stats.damage_taken,
+                stats.items_used,
+                stats.quests_completed
+            )
+        })
+        .unwrap_or_default();
+    let table = high_scores
+        .scores
+        .iter()
+        .enumerate()
+        .map(|(i, s)| format!("{}. {}", i + 1, s.describe()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("{}High scores\n{}", last, table)
+}
+
 /// Set up the menu/ spawn into the game
 fn setup_menu(
     mut commands: Commands,
     font_assets: Res<FontAssets>,
     button_colors: Res<ButtonColors>,
     message: Res<PlayerMessage>,
+    theme_choice: Res<ThemeChoice>,
+    high_scores: Res<HighScores>,
+    stats: Res<RunStats>,
 ) {
     commands.spawn(Camera2dBundle::default());
 
     commands
         .spawn(NodeBundle {
             style: Style {
-                size: Size::new(Val::Px(120.0), Val::Px(100.0)),
+                size: Size::new(Val::Px(200.0), Val::Px(200.0)),
                 margin: UiRect::all(Val::Auto),
                 flex_direction: FlexDirection::ColumnReverse,
                 align_self: AlignSelf::Center,
@@ -104,34 +168,70 @@ fn setup_menu(
                 },
                 ..default()
             });
-            parent
-                .spawn(ButtonBundle {
+            let font = &font_assets;
+            let colors = &button_colors;
+            spawn_button(parent, "Play", 40.0, PlayButton, font, colors);
+            let theme = theme_label(&theme_choice);
+            spawn_button(parent, theme, 20.0, ThemeButton, font, colors);
+            spawn_button(parent, "Controls", 20.0, ControlsButton, font, colors);
+            spawn_button(parent, "Options", 20.0, OptionsButton, font, colors);
+            if !high_scores.scores.is_empty() {
+                parent.spawn(TextBundle {
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: high_scores_text(&high_scores, &stats),
+                            style: TextStyle {
+                                font_size: 14.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                font: font_assets.fira_sans.clone(),
+                            },
+                        }],
+                        alignment: TextAlignment::CENTER,
+                    },
                     style: Style {
-                        size: Size::new(Val::Undefined, Val::Px(50.0)),
                         margin: UiRect::all(Val::Auto),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..Default::default()
+                        ..default()
                     },
-                    background_color: button_colors.normal,
-                    ..Default::default()
-                })
-                .with_children(|parent| {
-                    parent.spawn(TextBundle {
-                        text: Text {
-                            sections: vec![TextSection {
-                                value: "Play".to_string(),
-                                style: TextStyle {
-                                    font: font_assets.fira_sans.clone(),
-                                    font_size: 40.0,
-                                    color: Color::rgb(0.9, 0.9, 0.9),
-                                },
-                            }],
-                            alignment: Default::default(),
-                        },
-                        ..Default::default()
-                    });
+                    ..default()
                 });
+            }
+        });
+}
+
+/// A button in the menu column, sized to fit its label
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    label: impl Into<String>,
+    font_size: f32,
+    marker: impl Component,
+    font_assets: &FontAssets,
+    button_colors: &ButtonColors,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Undefined, Val::Px(font_size + 10.0)),
+                margin: UiRect::all(Val::Auto),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: button_colors.normal,
+            ..default()
+        })
+        .insert(marker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    label,
+                    TextStyle {
+                        font: font_assets.fira_sans.clone(),
+                        font_size,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                ),
+                ..default()
+            });
         });
 }
 
@@ -141,7 +241,7 @@ fn click_play_button(
     mut state: ResMut<State<GameState>>,
     mut interaction_query: Query<
         (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<Button>),
+        (Changed<Interaction>, With<PlayButton>),
     >,
 ) {
     interaction_query
@@ -159,8 +259,96 @@ fn click_play_button(
         });
 }
 
-/// Remove the menu from the app after started playing
+/// Cycle the theme between per level and each configured theme
+fn click_theme_button(
+    button_colors: Res<ButtonColors>,
+    settings: Res<Settings>,
+    mut theme_choice: ResMut<ThemeChoice>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &Children),
+        (Changed<Interaction>, With<ThemeButton>),
+    >,
+    mut text_query: Query<&mut Text>,
+) {
+    interaction_query.iter_mut().for_each(
+        |(interaction, mut color, children)| match *interaction {
+            Interaction::Clicked => {
+                let names = settings.themes.iter().map(|t| &t.name).collect::<Vec<_>>();
+                let next = match &theme_choice.0 {
+                    None => names.first(),
+                    Some(current) => names
+                        .iter()
+                        .position(|n| *n == current)
+                        .and_then(|i| names.get(i + 1)),
+                };
+                theme_choice.0 = next.map(|n| n.to_string());
+                children.iter().for_each(|child| {
+                    if let Ok(mut text) = text_query.get_mut(*child) {
+                        text.sections[0].value = theme_label(&theme_choice);
+                    }
+                });
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+        },
+    );
+}
 
+/// Open the rebinding screen
+fn click_controls_button(
+    button_colors: Res<ButtonColors>,
+    settings: Res<Settings>,
+    mut rebind_screen: ResMut<RebindScreen>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ControlsButton>),
+    >,
+) {
+    interaction_query
+        .iter_mut()
+        .for_each(|(interaction, mut color)| match *interaction {
+            Interaction::Clicked => {
+                rebind_screen.open(&settings.bindings);
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+        });
+}
+
+/// Open the options screen
+fn click_options_button(
+    button_colors: Res<ButtonColors>,
+    settings: Res<Settings>,
+    mut options_screen: ResMut<OptionsScreen>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<OptionsButton>),
+    >,
+) {
+    interaction_query
+        .iter_mut()
+        .for_each(|(interaction, mut color)| match *interaction {
+            Interaction::Clicked => {
+                options_screen.open(&settings);
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+        });
+}
+
+/// Remove the menu from the app after started playing
 fn cleanup_menu(
     mut commands: Commands,
     menu: Query<Entity, With<Menu>>,
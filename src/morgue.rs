@@ -0,0 +1,354 @@
+use std::{fmt::Display, fs};
+
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{
+    components::{map_position::MapPosition, name::EntityName},
+    entities::{AvailableQuest, Item, MapLevel, Monster, Player, QuestState, WinItem},
+    loading::FontAssets,
+    map::map_builder::MapBuilder,
+    menu::ButtonColors,
+    stages::TurnState,
+    systems::{
+        combat::CombatLog,
+        inventory::Carried,
+        quest_engine::AssignedQuest,
+        stats::{HighScore, RunStats},
+    },
+    GameState,
+};
+
+/// Plugin for the recap of a lost run, shown between the game and the menu
+pub struct MorguePlugin;
+
+impl Plugin for MorguePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recap>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(write_recap.run_if_resource_equals(TurnState::GameOver)),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Morgue).with_system(setup_morgue))
+            .add_system_set(
+                SystemSet::on_update(GameState::Morgue)
+                    .with_system(click_save_button)
+                    .with_system(click_continue_button),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Morgue).with_system(cleanup_morgue));
+    }
+}
+
+/// How the run ended, kept after the level is cleaned up
+#[derive(Resource, Default, Debug, Clone)]
+pub struct Recap {
+    pub name: String,
+    pub killer: Option<String>,
+    pub level: u32,
+    pub turns: u32,
+    pub score: u32,
+    pub seed: u64,
+    pub inventory: Vec<String>,
+    pub quests: Vec<(String, QuestState)>,
+    /// Latest combat messages, oldest first
+    pub combat: Vec<String>,
+    /// The level as text, with everything on it where it ended
+    pub map: String,
+}
+
+impl Recap {
+    /// Where the recap is saved to share
+    fn file_name(&self) -> String {
+        format!("morgue-{}.txt", self.seed)
+    }
+}
+
+/// Everything but the map
+struct Summary<'a>(&'a Recap);
+
+impl Display for Summary<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let recap = self.0;
+        writeln!(
+            f,
+            "{} was killed by {} on level {} after {} turns",
+            recap.name,
+            recap.killer.as_deref().unwrap_or("something unknown"),
+            recap.level,
+            recap.turns
+        )?;
+        writeln!(f, "Score {}, seed {}", recap.score, recap.seed)?;
+        writeln!(f)?;
+        match recap.inventory.is_empty() {
+            true => writeln!(f, "Carrying nothing")?,
+            false => writeln!(f, "Carrying: {}", recap.inventory.join(", "))?,
+        }
+        if !recap.quests.is_empty() {
+            writeln!(f, "Quests:")?;
+            recap
+                .quests
+                .iter()
+                .try_for_each(|(name, state)| writeln!(f, "  {}: {:?}", name, state))?;
+        }
+        if !recap.combat.is_empty() {
+            writeln!(f, "Last messages:")?;
+            recap
+                .combat
+                .iter()
+                .try_for_each(|message| writeln!(f, "  {}", message))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Recap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", Summary(self))?;
+        writeln!(f, "{}", self.map)
+    }
+}
+
+/// Gather the recap before the level is cleaned up
+#[allow(clippy::type_complexity)]
+fn write_recap(
+    mut recap: ResMut<Recap>,
+    stats: Res<RunStats>,
+    log: Res<CombatLog>,
+    map_builder: Res<MapBuilder>,
+    player: Query<(Entity, &EntityName, &MapPosition, &MapLevel), With<Player>>,
+    carried: Query<(&EntityName, &Carried)>,
+    quests: Query<(&EntityName, &QuestState, &AssignedQuest)>,
+    placed: Query<
+        (
+            &MapPosition,
+            Option<&Monster>,
+            Option<&AvailableQuest>,
+            Option<&WinItem>,
+        ),
+        Or<(With<Monster>, With<AvailableQuest>, With<Item>)>,
+    >,
+) {
+    let Ok((player, name, position, level)) = player.get_single() else {
+        return;
+    };
+    let mut inventory = carried
+        .iter()
+        .filter(|(_, c)| c.entity == player)
+        .map(|(name, _)| name.0.clone())
+        .collect::<Vec<_>>();
+    inventory.sort();
+    let mut quests = quests
+        .iter()
+        .filter(|(.., assigned)| assigned.assignee == player)
+        .map(|(name, state, _)| (name.0.clone(), *state))
+        .collect::<Vec<_>>();
+    quests.sort_by(|a, b| a.0.cmp(&b.0));
+    let map = map_builder.render(|mp| {
+        if mp == *position {
+            return Some('@');
+        }
+        placed
+            .iter()
+            .find(|(p, ..)| **p == mp)
+            .map(|(_, monster, quest, win)| match (monster, quest, win) {
+                (Some(_), ..) => 'M',
+                (_, Some(_), _) => 'N',
+                (.., Some(_)) => '?',
+                _ => 'I',
+            })
+    });
+    *recap = Recap {
+        name: name.0.clone(),
+        killer: stats.cause_of_death.clone(),
+        level: level.value,
+        turns: stats.turns,
+        score: HighScore::from_run(&stats, false, level.value).score,
+        seed: stats.seed,
+        inventory,
+        quests,
+        combat: log.0.iter().cloned().collect(),
+        map,
+    };
+}
+
+/// Component that has the whole recap screen
+#[derive(Component)]
+struct Morgue;
+
+/// Button that saves the recap to a text file
+#[derive(Component)]
+struct SaveButton;
+
+/// Button that goes on to the menu
+#[derive(Component)]
+struct ContinueButton;
+
+fn text(value: String, font_size: f32, font_assets: &FontAssets) -> Text {
+    Text::from_section(
+        value,
+        TextStyle {
+            font: font_assets.fira_sans.clone(),
+            font_size,
+            color: Color::rgb(0.9, 0.9, 0.9),
+        },
+    )
+}
+
+fn setup_morgue(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    recap: Res<Recap>,
+) {
+    commands.spawn(Camera2dBundle::default());
+
+    let button = ButtonBundle {
+        style: Style {
+            size: Size::new(Val::Undefined, Val::Px(30.0)),
+            margin: UiRect::all(Val::Px(5.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: button_colors.normal,
+        ..default()
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.0).into(),
+            ..default()
+        })
+        .insert(Morgue)
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: text(Summary(&recap).to_string(), 16.0, &font_assets),
+                ..default()
+            });
+            parent.spawn(TextBundle {
+                text: text(recap.map.clone(), 8.0, &font_assets),
+                ..default()
+            });
+            parent
+                .spawn(button.clone())
+                .insert(SaveButton)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle {
+                        text: text("Save recap".to_string(), 20.0, &font_assets),
+                        ..default()
+                    });
+                });
+            parent
+                .spawn(button)
+                .insert(ContinueButton)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle {
+                        text: text("Continue".to_string(), 20.0, &font_assets),
+                        ..default()
+                    });
+                });
+        });
+}
+
+/// Write the recap to a text file, for bug reports
+fn click_save_button(
+    button_colors: Res<ButtonColors>,
+    recap: Res<Recap>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &Children),
+        (Changed<Interaction>, With<SaveButton>),
+    >,
+    mut text_query: Query<&mut Text>,
+) {
+    interaction_query.iter_mut().for_each(
+        |(interaction, mut color, children)| match *interaction {
+            Interaction::Clicked => {
+                let file_name = recap.file_name();
+                let label = match fs::write(&file_name, recap.to_string()) {
+                    Ok(()) => format!("Saved to {}", file_name),
+                    Err(e) => {
+                        error!("Could not save recap {}: {}", file_name, e);
+                        "Could not save".to_string()
+                    }
+                };
+                children.iter().for_each(|child| {
+                    if let Ok(mut text) = text_query.get_mut(*child) {
+                        text.sections[0].value = label.clone();
+                    }
+                });
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+        },
+    );
+}
+
+fn click_continue_button(
+    button_colors: Res<ButtonColors>,
+    mut state: ResMut<State<GameState>>,
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<ContinueButton>),
+    >,
+) {
+    interaction_query
+        .iter_mut()
+        .for_each(|(interaction, mut color)| match *interaction {
+            Interaction::Clicked => {
+                state.set(GameState::Menu).unwrap();
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered;
+            }
+            Interaction::None => {
+                *color = button_colors.normal;
+            }
+        });
+}
+
+fn cleanup_morgue(
+    mut commands: Commands,
+    morgue: Query<Entity, With<Morgue>>,
+    camera: Query<Entity, With<Camera2d>>,
+) {
+    commands.entity(morgue.single()).despawn_recursive();
+    commands.entity(camera.single()).despawn_recursive();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recap_text() {
+        let recap = Recap {
+            name: "Cake Addict".to_string(),
+            killer: Some("Gym Bro".to_string()),
+            level: 1,
+            turns: 42,
+            score: 210,
+            seed: 7,
+            inventory: vec!["Fries".to_string()],
+            quests: vec![("Get Weapon".to_string(), QuestState::Todo)],
+            combat: vec!["Gym Bro kills Cake Addict".to_string()],
+            map: "#@M#".to_string(),
+        };
+        let text = recap.to_string();
+        assert!(text.starts_with("Cake Addict was killed by Gym Bro on level 1 after 42 turns"));
+        assert!(text.contains("Carrying: Fries"));
+        assert!(text.contains("  Get Weapon: Todo"));
+        assert!(text.contains("  Gym Bro kills Cake Addict"));
+        assert!(text.ends_with("#@M#\n"));
+        assert_eq!(recap.file_name(), "morgue-7.txt");
+    }
+}
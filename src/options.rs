@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext, EguiSettings};
+
+use crate::{
+    config::{
+        profiles, Preferences, Settings, WindowSettings, MAX_HUD_SCALE, MIN_HUD_SCALE, MIN_WINDOW,
+    },
+    GameState,
+};
+
+/// Plugin for the options screen opened from the menu, and for applying the
+/// window size and HUD scale whenever the settings change
+pub struct OptionsPlugin;
+
+impl Plugin for OptionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OptionsScreen>()
+            .add_system(apply_preferences)
+            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(options_ui))
+            .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(close_options_screen));
+    }
+}
+
+/// Options being edited, `None` while the screen is closed
+#[derive(Resource, Default)]
+pub struct OptionsScreen {
+    editing: Option<Preferences>,
+    /// The seed as typed, empty for a random one
+    seed: String,
+    profiles: Vec<String>,
+    message: Option<String>,
+}
+
+impl OptionsScreen {
+    /// Open the screen with the current options
+    pub fn open(&mut self, settings: &Settings) {
+        let editing = Preferences::current(settings);
+        *self = Self {
+            seed: editing.seed.map(|s| s.to_string()).unwrap_or_default(),
+            editing: Some(editing),
+            profiles: profiles(),
+            message: None,
+        };
+    }
+}
+
+/// Empty for a random seed, otherwise a whole number
+fn parse_seed(seed: &str) -> Result<Option<u64>, String> {
+    match seed.trim() {
+        "" => Ok(None),
+        seed => seed
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("Seed '{}' is not a whole number", seed)),
+    }
+}
+
+fn options_ui(
+    mut egui_context: ResMut<EguiContext>,
+    mut screen: ResMut<OptionsScreen>,
+    mut settings: ResMut<Settings>,
+) {
+    let screen = screen.as_mut();
+    let Some(editing) = screen.editing.as_mut() else {
+        return;
+    };
+    let mut close = false;
+    egui::Window::new("Options")
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            egui::Grid::new("options").show(ui, |ui| {
+                ui.label("Profile");
+                egui::ComboBox::from_id_source("profile")
+                    .selected_text(editing.profile.as_deref().unwrap_or("From RUN_MODE"))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut editing.profile, None, "From RUN_MODE");
+                        screen.profiles.iter().for_each(|profile| {
+                            ui.selectable_value(
+                                &mut editing.profile,
+                                Some(profile.clone()),
+                                profile,
+                            );
+                        });
+                    });
+                ui.end_row();
+
+                ui.label("Window");
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut editing.window.width)
+                            .clamp_range(MIN_WINDOW.0..=3840.0)
+                            .speed(10.0),
+                    );
+                    ui.label("x");
+                    ui.add(
+                        egui::DragValue::new(&mut editing.window.height)
+                            .clamp_range(MIN_WINDOW.1..=2160.0)
+                            .speed(10.0),
+                    );
+                });
+                ui.end_row();
+
+                ui.label("HUD scale");
+                ui.add(egui::Slider::new(
+                    &mut editing.hud_scale,
+                    MIN_HUD_SCALE..=MAX_HUD_SCALE,
+                ));
+                ui.end_row();
+
+                ui.label("Seed");
+                ui.text_edit_singleline(&mut screen.seed)
+                    .on_hover_text("Empty for a random seed each run");
+                ui.end_row();
+
+                ui.label("Master volume");
+                ui.add(egui::Slider::new(&mut editing.volume.master, 0.0..=1.0));
+                ui.end_row();
+                ui.label("Music volume");
+                ui.add(egui::Slider::new(&mut editing.volume.music, 0.0..=1.0));
+                ui.end_row();
+                ui.label("Effects volume");
+                ui.add(egui::Slider::new(&mut editing.volume.effects, 0.0..=1.0));
+                ui.end_row();
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    screen.message = Some(match parse_seed(&screen.seed) {
+                        Ok(seed) => {
+                            editing.seed = seed;
+                            // Applied now, a new profile is loaded once the
+                            // saved file is picked up
+                            settings.window = editing.window;
+                            settings.hud_scale = editing.hud_scale;
+                            settings.seed = editing.seed;
+                            settings.audio.volume = editing.volume;
+                            match editing.save() {
+                                Ok(()) => "Saved".to_string(),
+                                Err(e) => format!("Could not save: {}", e),
+                            }
+                        }
+                        Err(e) => e,
+                    });
+                }
+                close = ui.button("Close").clicked();
+            });
+            if let Some(message) = &screen.message {
+                ui.label(message);
+            }
+        });
+    if close {
+        *screen = OptionsScreen::default();
+    }
+}
+
+fn close_options_screen(mut screen: ResMut<OptionsScreen>) {
+    *screen = OptionsScreen::default();
+}
+
+/// Resize the window when its size is changed in the settings, leaving it
+/// alone otherwise so a window resized by hand stays that way
+fn apply_preferences(
+    settings: Res<Settings>,
+    mut windows: ResMut<Windows>,
+    mut egui_settings: ResMut<EguiSettings>,
+    mut applied: Local<Option<WindowSettings>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    if egui_settings.scale_factor != settings.hud_scale as f64 {
+        egui_settings.scale_factor = settings.hud_scale as f64;
+    }
+    if *applied == Some(settings.window) {
+        return;
+    }
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_resolution(settings.window.width, settings.window.height);
+    }
+    *applied = Some(settings.window);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_seed() {
+        assert_eq!(parse_seed(""), Ok(None));
+        assert_eq!(parse_seed(" 42 "), Ok(Some(42)));
+        assert!(parse_seed("cake").is_err());
+        assert!(parse_seed("-1").is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::{
+    actions::{GameControl, InputSources},
+    config::{BindingSettings, InputBinding, Settings},
+    GameState,
+};
+
+/// Plugin for the screen that rebinds the game controls, opened from the menu
+pub struct RebindPlugin;
+
+impl Plugin for RebindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RebindScreen>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Menu)
+                    .with_system(rebind_ui)
+                    .with_system(capture_binding),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(close_rebind_screen));
+    }
+}
+
+/// Bindings being edited, `None` while the screen is closed
+#[derive(Resource, Default)]
+pub struct RebindScreen {
+    editing: Option<BindingSettings>,
+    /// Control the next input gets bound to
+    waiting: Option<GameControl>,
+    message: Option<String>,
+}
+
+impl RebindScreen {
+    /// Open the screen with a copy of the current bindings
+    pub fn open(&mut self, bindings: &BindingSettings) {
+        *self = Self {
+            editing: Some(bindings.clone()),
+            ..default()
+        };
+    }
+}
+
+fn binding_label(binding: &InputBinding) -> String {
+    match binding {
+        InputBinding::Key(key) => format!("{:?}", key),
+        InputBinding::Mouse(button) => format!("Mouse {:?}", button),
+        InputBinding::GamepadButton(button) => format!("Pad {:?}", button),
+        InputBinding::GamepadAxis(axis, direction) => format!("Pad {:?} {:?}", axis, direction),
+    }
+}
+
+fn rebind_ui(
+    mut egui_context: ResMut<EguiContext>,
+    mut screen: ResMut<RebindScreen>,
+    mut settings: ResMut<Settings>,
+) {
+    let screen = screen.as_mut();
+    let Some(editing) = screen.editing.as_mut() else {
+        return;
+    };
+    let mut close = false;
+    egui::Window::new("Controls")
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            egui::Grid::new("bindings").striped(true).show(ui, |ui| {
+                GameControl::all(editing).into_iter().for_each(|control| {
+                    ui.label(control.label());
+                    ui.horizontal(|ui| {
+                        if let Some(bindings) = control.bindings_mut(editing) {
+                            bindings.retain(|binding| {
+                                !ui.button(binding_label(binding))
+                                    .on_hover_text("Click to remove")
+                                    .clicked()
+                            });
+                        }
+                        if screen.waiting == Some(control) {
+                            ui.label("Press an input...");
+                        } else if ui.button("+").on_hover_text("Add an input").clicked() {
+                            screen.waiting = Some(control);
+                        }
+                    });
+                    ui.end_row();
+                });
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    settings.bindings = editing.clone();
+                    screen.message = Some(match editing.save() {
+                        Ok(()) => "Saved".to_string(),
+                        Err(e) => format!("Could not save: {}", e),
+                    });
+                }
+                if ui.button("Defaults").clicked() {
+                    match BindingSettings::defaults() {
+                        Ok(defaults) => *editing = defaults,
+                        Err(e) => screen.message = Some(e.to_string()),
+                    }
+                }
+                if screen.waiting.is_some() && ui.button("Cancel").clicked() {
+                    screen.waiting = None;
+                }
+                close = ui.button("Close").clicked();
+            });
+            if let Some(message) = &screen.message {
+                ui.label(message);
+            }
+        });
+    if close {
+        *screen = RebindScreen::default();
+    }
+}
+
+/// Bind the next input pressed to the control waiting for one
+fn capture_binding(
+    mut egui_context: ResMut<EguiContext>,
+    mut screen: ResMut<RebindScreen>,
+    inputs: InputSources,
+) {
+    let Some(control) = screen.waiting else {
+        return;
+    };
+    let Some(binding) = inputs.any_just_pressed() else {
+        return;
+    };
+    // Clicks on the screen itself are not bindings
+    if matches!(binding, InputBinding::Mouse(_)) && egui_context.ctx_mut().is_pointer_over_area() {
+        return;
+    }
+    let screen = screen.as_mut();
+    if let Some(bindings) = screen
+        .editing
+        .as_mut()
+        .and_then(|editing| control.bindings_mut(editing))
+    {
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+    screen.waiting = None;
+}
+
+fn close_rebind_screen(mut screen: ResMut<RebindScreen>) {
+    *screen = RebindScreen::default();
+}
@@ -0,0 +1,452 @@
+use std::{
+    fs::File,
+    hash::Hasher,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use bevy_turborand::GlobalRng;
+use fnv::FnvHasher;
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::{Actions, ACTIONS_LABEL},
+    components::{health::Health, map_position::MapPosition, name::EntityName},
+    entities::MapLevel,
+    map::map_builder::MapBuilder,
+    stages::{GameStage, TurnState},
+    systems::{
+        inventory::Carried,
+        stats::{RunStats, SEED_LABEL},
+        travel::TRAVEL_LABEL,
+    },
+    theme::{ThemeChoice, THEME_LABEL},
+    GameState,
+};
+
+/// Plugin for recording the player's actions each turn to a replay file,
+/// and playing them back through the same systems
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayMode>()
+            .init_resource::<Replay>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Generation)
+                    .with_system(start_run.after(SEED_LABEL).before(THEME_LABEL)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(
+                    play_turn
+                        .run_if_resource_equals(TurnState::AwaitingInput)
+                        .after(ACTIONS_LABEL)
+                        .before(TRAVEL_LABEL),
+                ),
+            )
+            .add_system_set_to_stage(
+                GameStage::PlayerCombat,
+                ConditionSet::new()
+                    .run_if_resource_equals(TurnState::PlayerTurn)
+                    .with_system(record_turn)
+                    .into(),
+            );
+    }
+}
+
+/// Whether runs are recorded or played back, set before the game starts.
+/// Playing back only stays in step with the same config as the recording
+#[derive(Resource, Default, Debug, Clone, PartialEq, Eq)]
+pub enum ReplayMode {
+    /// Play as normal
+    #[default]
+    Off,
+    /// Write each turn of the next run to a replay file
+    Record(PathBuf),
+    /// Play the next run from a replay file, checking it stays in step
+    Playback(PathBuf),
+}
+
+/// First line of a replay file, what the run was started with
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct ReplayHeader {
+    seed: u64,
+    theme: Option<String>,
+}
+
+/// What the player did in one turn
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct TurnActions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    movement: Option<IVec2>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pick_up: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    interact: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    use_item: Option<usize>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    wait: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+impl TurnActions {
+    fn from_actions(actions: &Actions) -> Self {
+        Self {
+            movement: actions
+                .player_movement
+                .map(|m| m.as_ivec2())
+                .filter(|m| *m != IVec2::ZERO),
+            pick_up: actions.pick_up_item.is_some(),
+            interact: actions.interact.is_some(),
+            use_item: actions.use_item,
+            wait: actions.wait.is_some(),
+        }
+    }
+
+    /// Replace the player's input with these actions
+    fn apply(&self, actions: &mut Actions) {
+        actions.player_movement = self.movement.map(|m| m.as_vec2());
+        actions.pick_up_item = self.pick_up.then_some(true);
+        actions.interact = self.interact.then_some(true);
+        actions.use_item = self.use_item;
+        actions.wait = self.wait.then_some(true);
+        actions.explore = None;
+        actions.rest = None;
+        actions.count = None;
+        actions.clicked_tile = None;
+    }
+}
+
+/// A line per turn after the header
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct ReplayTurn {
+    actions: TurnActions,
+    /// Hash of the state the actions were taken in
+    hash: u64,
+}
+
+/// The run being recorded or played back
+#[derive(Resource, Default)]
+pub struct Replay {
+    writer: Option<BufWriter<File>>,
+    turns: Vec<ReplayTurn>,
+    /// Next turn to play back
+    cursor: usize,
+    playing: bool,
+}
+
+fn read_replay(path: &Path) -> Result<(ReplayHeader, Vec<ReplayTurn>), String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut lines = BufReader::new(file).lines();
+    let header = lines.next().ok_or("empty replay file")?;
+    let header =
+        serde_json::from_str(&header.map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+    let turns = lines
+        .map(|line| {
+            serde_json::from_str(&line.map_err(|e| e.to_string())?).map_err(|e| e.to_string())
+        })
+        .collect::<Result<_, String>>()?;
+    Ok((header, turns))
+}
+
+fn write_line<T: Serialize>(writer: &mut BufWriter<File>, value: &T) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *writer, value)?;
+    writeln!(writer)?;
+    // Flushed every turn so a crash still leaves a full replay
+    writer.flush()
+}
+
+/// Start recording with the run's seed, or reseed it from the replay file
+/// when playing one back
+fn start_run(
+    mut mode: ResMut<ReplayMode>,
+    mut replay: ResMut<Replay>,
+    mut rng: ResMut<GlobalRng>,
+    mut stats: ResMut<RunStats>,
+    mut theme_choice: ResMut<ThemeChoice>,
+) {
+    *replay = Replay::default();
+    match mode.clone() {
+        ReplayMode::Off => {}
+        ReplayMode::Record(path) => {
+            let header = ReplayHeader {
+                seed: stats.seed,
+                theme: theme_choice.0.clone(),
+            };
+            match File::create(&path) {
+                Ok(file) => {
+                    let mut writer = BufWriter::new(file);
+                    match write_line(&mut writer, &header) {
+                        Ok(()) => replay.writer = Some(writer),
+                        Err(e) => error!("Could not write replay {:?}: {}", path, e),
+                    }
+                    info!("Recording replay to {:?}, seed {}", path, header.seed);
+                }
+                Err(e) => error!("Could not create replay {:?}: {}", path, e),
+            }
+        }
+        ReplayMode::Playback(path) => {
+            // Only the next run is played back
+            *mode = ReplayMode::Off;
+            match read_replay(&path) {
+                Ok((header, turns)) => {
+                    info!("Playing {} turns from {:?}", turns.len(), path);
+                    *rng = GlobalRng::with_seed(header.seed);
+                    stats.seed = header.seed;
+                    theme_choice.0 = header.theme;
+                    replay.turns = turns;
+                    replay.playing = true;
+                }
+                Err(e) => error!("Could not read replay {:?}: {}", path, e),
+            }
+        }
+    }
+}
+
+/// Feed the next recorded turn in as the player's input
+fn play_turn(mut actions: ResMut<Actions>, mut replay: ResMut<Replay>) {
+    if !replay.playing {
+        return;
+    }
+    match replay.turns.get(replay.cursor) {
+        Some(turn) => turn.actions.apply(&mut actions),
+        None => {
+            info!("Replay finished after {} turns", replay.cursor);
+            replay.playing = false;
+        }
+    }
+}
+
+/// Hashes values as their little endian bytes with FNV, so a replay's
+/// hashes stay the same on every platform and Rust release
+#[derive(Default)]
+struct StableHasher(FnvHasher);
+
+impl StableHasher {
+    fn u32(&mut self, value: u32) {
+        self.0.write(&value.to_le_bytes());
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.0.write(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.write(&value.to_le_bytes());
+    }
+
+    /// The length first, so strings next to each other can't run together
+    fn str(&mut self, value: &str) {
+        self.u64(value.len() as u64);
+        self.0.write(value.as_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+}
+
+/// Hash of what turns change, entities are sorted so their order does not matter
+fn state_hash(
+    map_hash: u64,
+    level: u32,
+    mut placed: Vec<(String, (i32, i32), Option<(i32, i32)>)>,
+    mut carried: Vec<String>,
+) -> u64 {
+    placed.sort();
+    carried.sort();
+    let mut hasher = StableHasher::default();
+    hasher.u64(map_hash);
+    hasher.u32(level);
+    hasher.u64(placed.len() as u64);
+    placed.iter().for_each(|(name, (x, y), health)| {
+        hasher.str(name);
+        hasher.i32(*x);
+        hasher.i32(*y);
+        match health {
+            Some((current, max)) => {
+                hasher.u32(1);
+                hasher.i32(*current);
+                hasher.i32(*max);
+            }
+            None => hasher.u32(0),
+        }
+    });
+    hasher.u64(carried.len() as u64);
+    carried.iter().for_each(|name| hasher.str(name));
+    hasher.finish()
+}
+
+/// Write the turn being taken, or check it against the one played back
+fn record_turn(
+    actions: Res<Actions>,
+    mut replay: ResMut<Replay>,
+    map_builder: Res<MapBuilder>,
+    level: Query<&MapLevel>,
+    placed: Query<(&EntityName, &MapPosition, Option<&Health>)>,
+    carried: Query<&EntityName, With<Carried>>,
+) {
+    let mut map_hasher = StableHasher::default();
+    map_builder
+        .map
+        .tiles
+        .iter()
+        .for_each(|t| map_hasher.u32(t.glyph().into()));
+    let hash = state_hash(
+        map_hasher.finish(),
+        level.get_single().map_or(0, |l| l.value),
+        placed
+            .iter()
+            .map(|(name, p, health)| {
+                (
+                    name.0.clone(),
+                    p.position.into(),
+                    health.map(|h| (h.current, h.max)),
+                )
+            })
+            .collect(),
+        carried.iter().map(|name| name.0.clone()).collect(),
+    );
+
+    let replay = replay.as_mut();
+    if replay.playing {
+        let turn = replay.cursor;
+        if replay.turns[turn].hash != hash {
+            error!("Replay diverged at turn {}", turn);
+            replay.playing = false;
+        }
+        replay.cursor += 1;
+    } else if let Some(writer) = replay.writer.as_mut() {
+        let turn = ReplayTurn {
+            actions: TurnActions::from_actions(&actions),
+            hash,
+        };
+        if let Err(e) = write_line(writer, &turn) {
+            error!("Could not write replay: {}", e);
+            replay.writer = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bot::{Bot, BotPlugin, GreedyBot},
+        config::Settings,
+        entities::Player,
+        headless::headless_app,
+    };
+
+    /// Where the player is and how they are doing
+    fn player_state(app: &mut App) -> (IVec2, i32) {
+        let mut query = app
+            .world
+            .query_filtered::<(&MapPosition, &Health), With<Player>>();
+        let (position, health) = query.single(&app.world);
+        (position.position, health.current)
+    }
+
+    #[test]
+    fn test_record_and_play_back() {
+        let path = std::env::temp_dir().join("cake_addict_test_replay.jsonl");
+        let settings = || Settings::load_profile("mini").unwrap();
+
+        let mut recording = headless_app(settings(), 1, None);
+        recording
+            .add_plugin(ReplayPlugin)
+            .add_plugin(BotPlugin)
+            .insert_resource(Bot::new(GreedyBot::default()))
+            .insert_resource(ReplayMode::Record(path.clone()));
+        // Stop between turns, so everything recorded has been played out
+        while recording.world.resource::<Bot>().turns < 20
+            || *recording.world.resource::<TurnState>() != TurnState::AwaitingInput
+        {
+            recording.update();
+        }
+        let recorded = player_state(&mut recording);
+
+        // The seed comes from the replay, not the app
+        let mut playback = headless_app(settings(), 2, None);
+        playback
+            .add_plugin(ReplayPlugin)
+            .insert_resource(ReplayMode::Playback(path.clone()));
+        // Stop on the last turn, nothing clears the actions it left behind
+        for _ in 0..200 {
+            playback.update();
+            if playback.world.resource::<Replay>().cursor == 20 {
+                break;
+            }
+        }
+        let replay = playback.world.resource::<Replay>();
+        assert_eq!(replay.turns.len(), 20);
+        assert_eq!(replay.cursor, replay.turns.len());
+        // Diverging would have stopped it
+        assert!(replay.playing);
+        assert_eq!(player_state(&mut playback), recorded);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_state_hash() {
+        let goblin = ("Goblin".to_string(), (1, 2), Some((3, 3)));
+        let cake = ("Cake".to_string(), (4, 2), None);
+        let hash = state_hash(1, 0, vec![goblin.clone(), cake.clone()], vec![]);
+        // Replays from older builds have to keep matching
+        assert_eq!(hash, 14500332052670853751);
+        assert_eq!(
+            hash,
+            state_hash(1, 0, vec![cake.clone(), goblin.clone()], vec![])
+        );
+
+        let hurt_goblin = ("Goblin".to_string(), (1, 2), Some((2, 3)));
+        assert_ne!(
+            hash,
+            state_hash(1, 0, vec![hurt_goblin, cake.clone()], vec![])
+        );
+        assert_ne!(
+            hash,
+            state_hash(1, 1, vec![goblin.clone(), cake.clone()], vec![])
+        );
+        assert_ne!(
+            hash,
+            state_hash(1, 0, vec![goblin], vec!["Cake".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_turn_round_trip() {
+        let actions = Actions {
+            player_movement: Some(Vec2::new(0.0, -1.0)),
+            use_item: Some(2),
+            ..default()
+        };
+        let turn = ReplayTurn {
+            actions: TurnActions::from_actions(&actions),
+            hash: 42,
+        };
+        let line = serde_json::to_string(&turn).unwrap();
+        assert_eq!(
+            line,
+            r#"{"actions":{"movement":[0,-1],"use_item":2},"hash":42}"#
+        );
+        let read: ReplayTurn = serde_json::from_str(&line).unwrap();
+        assert_eq!(read, turn);
+
+        let mut played = Actions {
+            explore: Some(true),
+            ..default()
+        };
+        read.actions.apply(&mut played);
+        assert_eq!(played.player_movement, actions.player_movement);
+        assert_eq!(played.use_item, Some(2));
+        assert_eq!(played.explore, None);
+    }
+}
@@ -0,0 +1,120 @@
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::{
+    components::{glyph::Glyph, map_position::MapPosition},
+    config::Settings,
+    entities::{AvailableQuest, QuestState, Tile},
+    loading::TextureAtlasAssets,
+    systems::lighting::light_tiles,
+    GameState,
+};
+
+/// Label for putting the sprites on their tiles, animations start after it
+pub const PLACE_SPRITES_LABEL: &str = "PlaceSprites";
+
+/// Plugin drawing the game in the window. The rules only give entities a
+/// [`Glyph`] and a [`MapPosition`], this adds a sprite for each and keeps it
+/// on its tile
+pub struct SpritesPlugin;
+
+impl Plugin for SpritesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PostUpdate, add_sprites)
+            .add_system_to_stage(CoreStage::PostUpdate, update_sprites)
+            .add_system_to_stage(CoreStage::PostUpdate, remove_sprites)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                place_sprites
+                    .label(PLACE_SPRITES_LABEL)
+                    .before(TransformSystem::TransformPropagate),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(update_quest_giver_display)
+                    .with_system(light_tiles),
+            );
+    }
+}
+
+/// The parts of a [`SpriteSheetBundle`] the rules don't already give
+type SpriteParts = (
+    TextureAtlasSprite,
+    Handle<TextureAtlas>,
+    Transform,
+    GlobalTransform,
+    ComputedVisibility,
+);
+
+fn add_sprites(
+    mut commands: Commands,
+    textures: Option<Res<TextureAtlasAssets>>,
+    settings: Res<Settings>,
+    added: Query<(Entity, &Glyph, &MapPosition, Option<&Tile>), Added<Glyph>>,
+) {
+    // Nothing is spawned until the textures are loaded
+    let Some(textures) = textures else {
+        return;
+    };
+    added.iter().for_each(|(entity, glyph, position, tile)| {
+        let z = match tile {
+            Some(_) => settings.map_settings.z_level,
+            None => settings.entity_z_level,
+        };
+        let transform = Transform::from_translation(position.translation(z, settings.tile_size));
+        let sprite: SpriteParts = (
+            TextureAtlasSprite::new(glyph.0),
+            textures.texture_atlas.clone(),
+            transform,
+            // Placed already, so it is not drawn at the origin for a frame
+            GlobalTransform::from(transform),
+            ComputedVisibility::default(),
+        );
+        commands.entity(entity).insert(sprite);
+    });
+}
+
+fn update_sprites(mut sprites: Query<(&Glyph, &mut TextureAtlasSprite), Changed<Glyph>>) {
+    sprites.iter_mut().for_each(|(glyph, mut sprite)| {
+        if sprite.index != glyph.0 {
+            sprite.index = glyph.0;
+        }
+    });
+}
+
+/// Anything taken off the map, such as a picked up item, stops being drawn
+fn remove_sprites(mut commands: Commands, removed: RemovedComponents<Glyph>) {
+    removed.iter().for_each(|entity| {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.remove::<SpriteParts>();
+        }
+    });
+}
+
+/// Move the sprites of whatever moved onto their new tiles
+fn place_sprites(
+    settings: Res<Settings>,
+    mut moved: Query<(&MapPosition, &mut Transform), Changed<MapPosition>>,
+) {
+    moved.iter_mut().for_each(|(position, mut transform)| {
+        transform.translation = position.translation(transform.translation.z, settings.tile_size);
+    });
+}
+
+/// Tint the quest givers by how far their quest has got
+fn update_quest_giver_display(
+    mut quest_giver: Query<(&mut TextureAtlasSprite, &AvailableQuest)>,
+    changed_quests: Query<&QuestState, Changed<QuestState>>,
+) {
+    quest_giver.iter_mut().for_each(|(mut sprite, q)| {
+        if let Ok(s) = changed_quests.get(q.0) {
+            let state_color = match s {
+                QuestState::Todo => Color::default(),
+                QuestState::Updated => Color::GREEN,
+                QuestState::Completed => Color::DARK_GRAY,
+            };
+            if sprite.color != state_color {
+                sprite.color = state_color;
+            }
+        }
+    });
+}
@@ -28,6 +28,7 @@
 //!         - system after [`RESPAWN_LABEL`] ([`advance_level`])
 //!     - back to [`TurnState::AwaitingInput`]
 //!     - possible [`TurnState::GameOver`] or [`TurnState::Victory`]
+//!  - [`GameState::Morgue`] after a [`TurnState::GameOver`]
 //!  - Return to [`GameState::Menu`]
 //!         
 
@@ -177,9 +178,14 @@ fn end_game(
             _ => WELCOME_MESSAGE.to_owned(),
         },
     });
+    // A lost run is recapped before the menu
+    let next = match *turn_state {
+        TurnState::GameOver => GameState::Morgue,
+        _ => GameState::Menu,
+    };
     commands.insert_resource(TurnState::AwaitingInput);
 
-    state.set(GameState::Menu).unwrap();
+    state.set(next).unwrap();
 }
 
 /// Trigures the change of level
@@ -4,10 +4,15 @@ use crate::{
     components::map_position::MapPosition,
     config::Settings,
     entities::{Monster, Player},
-    map::{grid_map::DjikstraMapCalc, map_builder::MapBuilder},
+    map::{grid_map::DjikstraMapCalc, map_builder::MapBuilder, tile_map::DiggableMap},
 };
 
-use super::{combat::WantsToAttack, fov::FieldOfView, movement::WantsToMove};
+use super::{
+    combat::WantsToAttack,
+    digging::{SmashesWalls, WantsToDig},
+    fov::FieldOfView,
+    movement::WantsToMove,
+};
 
 #[derive(Component, Default)]
 pub struct ChasingPlayer {}
@@ -16,18 +21,36 @@ pub fn chase_player(
     player_query: Query<(Entity, &MapPosition, With<Player>)>,
     map: Res<MapBuilder>,
     all_positions: Query<&MapPosition, With<Monster>>,
-    mut chasers: Query<(Entity, &mut ChasingPlayer, &FieldOfView, &MapPosition)>,
+    mut chasers: Query<(
+        Entity,
+        &mut ChasingPlayer,
+        &FieldOfView,
+        &MapPosition,
+        Option<&SmashesWalls>,
+    )>,
     mut move_events: EventWriter<WantsToMove>,
     mut combat_events: EventWriter<WantsToAttack>,
+    mut dig_events: EventWriter<WantsToDig>,
     settings: Res<Settings>,
 ) {
     let (player, player_position, _) = player_query.single();
     let max_fov = settings.max_fov;
+    // Maps are rebuilt every turn so dug out walls are always accounted for
     let dmap = map.map.depth_djikstra_map(*player_position, Some(max_fov));
+    let smash_dmap = chasers
+        .iter()
+        .any(|(_, _, _, _, s)| s.is_some())
+        .then(|| DiggableMap(&map.map).depth_djikstra_map(*player_position, Some(max_fov)));
     // Find all the new positions
-    chasers.iter_mut().for_each(|(entity, _, fov, p)| {
-        if fov.visible_positions.contains(player_position) {
-            let destination = dmap.next_along_path(*p);
+    chasers.iter_mut().for_each(|(entity, _, fov, p, smashes)| {
+        // Wall smashers can sense the player through walls
+        let senses_player = fov.visible_positions.contains(player_position)
+            || (smashes.is_some() && p.distance(*player_position) <= fov.radius as f32);
+        if senses_player {
+            let destination = match (smashes, &smash_dmap) {
+                (Some(_), Some(smash_dmap)) => smash_dmap.next_along_path(*p),
+                _ => dmap.next_along_path(*p),
+            };
 
             if destination == *player_position {
                 info!("Attacking Player");
@@ -35,6 +58,12 @@ pub fn chase_player(
                     attacker: entity,
                     victim: player,
                 });
+            } else if let Some(smashes) = smashes.filter(|_| map.map.is_diggable(destination)) {
+                dig_events.send(WantsToDig {
+                    digger: entity,
+                    position: destination,
+                    power: smashes.power,
+                });
             } else if !all_positions
                 .iter()
                 .any(|entity_position| destination == *entity_position)
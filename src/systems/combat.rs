@@ -1,20 +1,49 @@
+use std::collections::VecDeque;
+
 use bevy::prelude::*;
 
 use crate::{
-    components::{damage::Damage, health::Health},
+    components::{damage::Damage, health::Health, map_position::MapPosition, name::EntityName},
     entities::{Player, Weapon},
+    GameState,
 };
 
-use super::inventory::Carried;
+use super::{inventory::Carried, stats::RunStats};
+
+/// Number of messages the combat log keeps
+const COMBAT_LOG_LENGTH: usize = 10;
 
 pub struct CombatPlugin;
 
 impl Plugin for CombatPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<WantsToAttack>();
+        app.add_event::<WantsToAttack>()
+            .add_event::<DamageDealt>()
+            .init_resource::<CombatLog>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Generation).with_system(clear_combat_log),
+            );
+    }
+}
+
+/// The latest hits and kills of the run, oldest first
+#[derive(Resource, Default, Debug)]
+pub struct CombatLog(pub VecDeque<String>);
+
+impl CombatLog {
+    pub fn push(&mut self, message: String) {
+        if self.0.len() == COMBAT_LOG_LENGTH {
+            self.0.pop_front();
+        }
+        self.0.push_back(message);
     }
 }
 
+fn clear_combat_log(mut log: ResMut<CombatLog>) {
+    log.0.clear();
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn combat(
     mut commands: Commands,
     mut combat_events: EventReader<WantsToAttack>,
@@ -22,6 +51,11 @@ pub fn combat(
     mut healths: Query<&mut Health>,
     damages: Query<&Damage>,
     weapons: Query<&Carried, With<Weapon>>,
+    names: Query<&EntityName>,
+    positions: Query<&MapPosition>,
+    mut stats: ResMut<RunStats>,
+    mut log: ResMut<CombatLog>,
+    mut damage_events: EventWriter<DamageDealt>,
 ) {
     let (player, _) = player_query.single();
     combat_events.iter().for_each(|event| {
@@ -29,7 +63,44 @@ pub fn combat(
 
         if let Ok(mut health) = healths.get_mut(event.victim) {
             info!("Attack, health start: {}", health.current);
+            // Hits on what is already dead do not count
+            let alive = health.current > 0;
             health.current -= final_damage;
+            let name = |entity| names.get(entity).map_or("Something", |n| &n.0);
+            if alive {
+                log.push(format!(
+                    "{} hits {} for {}",
+                    name(event.attacker),
+                    name(event.victim),
+                    final_damage
+                ));
+                if health.current < 1 {
+                    log.push(format!(
+                        "{} kills {}",
+                        name(event.attacker),
+                        name(event.victim)
+                    ));
+                }
+                if let Ok(position) = positions.get(event.victim) {
+                    damage_events.send(DamageDealt {
+                        victim: event.victim,
+                        position: *position,
+                        amount: final_damage,
+                    });
+                }
+            }
+            if event.attacker == player && alive {
+                stats.damage_dealt += final_damage;
+                if health.current < 1 {
+                    stats.add_kill(name(event.victim));
+                }
+            }
+            if event.victim == player && alive {
+                stats.damage_taken += final_damage;
+                if health.current < 1 {
+                    stats.cause_of_death = names.get(event.attacker).ok().map(|n| n.0.clone());
+                }
+            }
             if health.current < 1 && event.victim != player {
                 commands.entity(event.victim).despawn_recursive();
             }
@@ -61,3 +132,12 @@ pub struct WantsToAttack {
     pub attacker: Entity,
     pub victim: Entity,
 }
+
+/// Sent for each hit on something alive, with where it stood as it may
+/// have been killed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DamageDealt {
+    pub victim: Entity,
+    pub position: MapPosition,
+    pub amount: i32,
+}
@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{health::Health, map_position::MapPosition},
+    entities::{Tile, TileType},
+    map::{grid_map::base_map::BaseMapMut, map_builder::MapBuilder},
+};
+
+use super::fov::FieldOfView;
+
+pub struct DiggingPlugin;
+
+impl Plugin for DiggingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<WantsToDig>();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WantsToDig {
+    pub digger: Entity,
+    pub position: MapPosition,
+    pub power: i32,
+}
+
+/// Monster that chases the player straight through walls
+#[derive(Component, Default)]
+pub struct SmashesWalls {
+    pub power: i32,
+}
+
+/// Damage walls, turning them into floor once their health runs out
+pub fn dig(
+    mut commands: Commands,
+    mut dig_events: EventReader<WantsToDig>,
+    mut map_builder: ResMut<MapBuilder>,
+    mut walls: Query<(Entity, &MapPosition, &mut TileType, &mut Health), With<Tile>>,
+    mut fovs: Query<(&MapPosition, &mut FieldOfView)>,
+) {
+    dig_events.iter().for_each(|event| {
+        if !map_builder.map.is_diggable(event.position) {
+            return;
+        }
+        if let Some((wall, _, mut tile_type, mut health)) =
+            walls.iter_mut().find(|(_, p, _, _)| **p == event.position)
+        {
+            health.current -= event.power;
+            info!("Digging, wall health: {}", health.current);
+            if health.current < 1 {
+                map_builder.map.set(event.position, TileType::Floor);
+                *tile_type = TileType::Floor;
+                commands.entity(wall).remove::<Health>();
+
+                // Anyone who could see the wall may now see through it
+                fovs.iter_mut()
+                    .filter(|(p, fov)| p.distance(event.position) <= fov.radius as f32)
+                    .for_each(|(_, mut fov)| *fov = fov.clone_dirty());
+            }
+        }
+    });
+}
@@ -4,7 +4,7 @@ use nannou_core::prelude::PI;
 use crate::{
     components::map_position::MapPosition,
     entities::{Player, Tile},
-    map::{grid_map::base_map::BaseMap, map_builder::MapBuilder, tile_map::TileMap},
+    map::{map_builder::MapBuilder, tile_map::TileMap},
 };
 
 pub fn fov(mut query: Query<(&MapPosition, &mut FieldOfView)>, map: Res<MapBuilder>) {
@@ -16,21 +16,16 @@ pub fn fov(mut query: Query<(&MapPosition, &mut FieldOfView)>, map: Res<MapBuild
         });
 }
 
+/// Show what the player can see, tiles stay shown once seen
 pub fn set_fov_visibility(
     player_fov: Query<(&FieldOfView, With<Player>)>,
-    mut visibility_query: Query<(Entity, &mut Visibility, &MapPosition)>,
-    mut tiles: Query<(&mut TextureAtlasSprite, With<Tile>)>,
+    mut visibility_query: Query<(&mut Visibility, &MapPosition, Option<&Tile>)>,
 ) {
     let (fov, _) = player_fov.single();
-    visibility_query.iter_mut().for_each(|(entity, mut v, p)| {
+    visibility_query.iter_mut().for_each(|(mut v, p, tile)| {
         if fov.visible_positions.contains(p) {
             v.is_visible = true;
-            if let Ok((mut tile_sprite, _)) = tiles.get_mut(entity) {
-                tile_sprite.color = Color::WHITE;
-            }
-        } else if let Ok((mut tile_sprite, _)) = tiles.get_mut(entity) {
-            tile_sprite.color = Color::GRAY;
-        } else {
+        } else if tile.is_none() {
             v.is_visible = false;
         }
     });
@@ -96,7 +91,7 @@ fn trace_path(p: MapPosition, p2: MapPosition, radius: i32, map: &TileMap) -> Ha
         })
     {
         res.insert(p);
-        if !map.can_enter_tile(p) {
+        if map.blocks_sight(p) {
             break;
         }
     }
@@ -120,6 +115,7 @@ fn field_of_view_set(p: MapPosition, radius: i32, map: &TileMap) -> HashSet<MapP
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{entities::TileType, map::grid_map::base_map::BaseMapMut};
 
     #[test]
     fn test_circle_set_low() {
@@ -210,6 +206,21 @@ mod tests {
         );
     }
     #[test]
+    fn test_trace_path_closed_door() {
+        let p = MapPosition::new(0, 0);
+        let p2 = MapPosition::new(3, 0);
+        let mut map = TileMap::new(10, 10);
+        map.set(MapPosition::new(1, 0), TileType::DoorClosed);
+        assert_eq!(
+            trace_path(p, p2, 3, &map),
+            vec![MapPosition::new(0, 0), MapPosition::new(1, 0)]
+                .into_iter()
+                .collect()
+        );
+        map.set(MapPosition::new(1, 0), TileType::DoorOpen);
+        assert_eq!(trace_path(p, p2, 3, &map).len(), 4);
+    }
+    #[test]
     fn test_field_of_view_set_simple() {
         let p = MapPosition::new(0, 0);
         let map = TileMap::new(10, 10);
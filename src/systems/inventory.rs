@@ -2,9 +2,11 @@ use bevy::prelude::*;
 
 use crate::{
     cleanup::cleanup_components,
-    components::map_position::MapPosition,
+    components::{glyph::Glyph, map_position::MapPosition},
     config::ItemType,
-    entities::{FetchItem, Player, ProvidesHealing, ProvidesMap, QuestState, Weapon},
+    entities::{
+        FetchItem, Player, ProvidesDigging, ProvidesHealing, ProvidesMap, QuestState, Weapon,
+    },
     GameState,
 };
 
@@ -39,14 +41,16 @@ pub fn assign_item(
     weapons: Query<(Entity, With<Weapon>)>,
     healing: Query<(Entity, With<ProvidesHealing>)>,
     dungeon_maps: Query<(Entity, With<ProvidesMap>)>,
+    digging: Query<(Entity, With<ProvidesDigging>)>,
     carried_weapons: Query<(Entity, &Carried, With<Weapon>)>,
     mut assigned_fetch_quests: Query<(&FetchItem, &mut QuestState), With<AssignedQuest>>,
 ) {
     pick_up_events.iter().for_each(|event| {
         info!("Pick up event");
         // Remove item from map
-        commands.entity(event.item).remove::<MapPosition>();
-        commands.entity(event.item).remove::<SpriteSheetBundle>();
+        commands
+            .entity(event.item)
+            .remove::<(MapPosition, Glyph, Visibility)>();
 
         // Add to players inventory
         commands.entity(event.item).insert(Carried {
@@ -69,6 +73,8 @@ pub fn assign_item(
             ItemType::Healing
         } else if dungeon_maps.contains(event.item) {
             ItemType::DungeonMap
+        } else if digging.contains(event.item) {
+            ItemType::Digging
         } else {
             ItemType::Weapon
         };
@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::IntoConditionalSystem;
+
+use crate::{
+    cleanup::cleanup_components,
+    components::{glyph::Glyph, map_position::MapPosition},
+    config::{Palette, Settings},
+    entities::{Player, Tile, RESPAWN_LABEL},
+    map::{map_builder::MapBuilder, GEN_MAP_LABEL},
+    stages::TurnState,
+    theme::ActiveTheme,
+    GameState,
+};
+
+use super::fov::FieldOfView;
+
+/// Plugin for the lights on each level. Only the window shows them, with
+/// [`light_tiles`] added by the [`SpritesPlugin`](crate::sprites::SpritesPlugin)
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_torches))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(
+                        spawn_torches
+                            .run_if_resource_equals(TurnState::NextLevel)
+                            .label(RESPAWN_LABEL)
+                            .after(GEN_MAP_LABEL),
+                    )
+                    .with_system(
+                        cleanup_components::<Torch>
+                            .run_if_resource_equals(TurnState::NextLevel)
+                            .before(GEN_MAP_LABEL),
+                    ),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(cleanup_components::<Torch>),
+            );
+    }
+}
+
+/// Lights the tiles in its field of view, less the further they are
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightSource {
+    Player,
+    Torch,
+}
+
+impl LightSource {
+    fn color(self, palette: &Palette) -> Vec3 {
+        match self {
+            LightSource::Player => palette.player.into(),
+            LightSource::Torch => palette.torch.into(),
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct Torch;
+
+#[derive(Bundle)]
+struct TorchBundle {
+    _t: Torch,
+    light: LightSource,
+    position: MapPosition,
+    fov: FieldOfView,
+    glyph: Glyph,
+    visibility: Visibility,
+}
+
+fn spawn_torches(mut commands: Commands, map_builder: Res<MapBuilder>, settings: Res<Settings>) {
+    let lighting = &settings.map_settings.lighting;
+    MapBuilder::in_order(&map_builder.torches)
+        .into_iter()
+        .for_each(|position| {
+            let mut fov = FieldOfView::new(lighting.torch_radius);
+            fov.update(position, &map_builder.map);
+            commands.spawn(TorchBundle {
+                _t: Torch,
+                light: LightSource::Torch,
+                position,
+                fov,
+                glyph: Glyph(lighting.torch_sprite),
+                visibility: Visibility { is_visible: false },
+            });
+        });
+}
+
+/// Tint the tiles in the player's view by the lights reaching them, and
+/// the ones out of view as remembered
+pub fn light_tiles(
+    settings: Res<Settings>,
+    theme: Res<ActiveTheme>,
+    player: Query<&FieldOfView, With<Player>>,
+    lights: Query<(&MapPosition, &FieldOfView, &LightSource)>,
+    mut tiles: Query<(&MapPosition, &mut TextureAtlasSprite), With<Tile>>,
+) {
+    let Ok(view) = player.get_single() else {
+        return;
+    };
+    let palette = theme.palette(&settings);
+    let lights = lights
+        .iter()
+        .map(|(p, fov, light)| (*p, fov, light.color(&palette)))
+        .collect::<Vec<_>>();
+    tiles.iter_mut().for_each(|(p, mut sprite)| {
+        let tint = match view.visible_positions.contains(p) {
+            true => light_tint(*p, &lights, palette.ambient.into()),
+            false => palette.remembered.into(),
+        };
+        let color = Color::rgb(tint.x, tint.y, tint.z);
+        // Only touch the sprites that change, so they aren't all re-extracted
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    });
+}
+
+/// Share of its light a light gives a tile `distance` away, fading to
+/// nothing just past its radius
+fn falloff(distance: f32, radius: i32) -> f32 {
+    (1.0 - distance / (radius as f32 + 1.0)).max(0.0)
+}
+
+/// The ambient light and every light that can see `p`, at most white
+fn light_tint(p: MapPosition, lights: &[(MapPosition, &FieldOfView, Vec3)], ambient: Vec3) -> Vec3 {
+    lights
+        .iter()
+        .filter(|(_, fov, _)| fov.visible_positions.contains(&p))
+        .fold(ambient, |tint, (at, fov, color)| {
+            tint + *color * falloff(p.distance(*at), fov.radius)
+        })
+        .min(Vec3::ONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::tile_map::TileMap;
+
+    #[test]
+    fn test_light_tint() {
+        let map = "#######
+                   #.....#
+                   #..#..#
+                   #######"
+            .parse::<MapBuilder>()
+            .unwrap()
+            .map;
+        let light = |p: MapPosition, map: &TileMap| {
+            let mut fov = FieldOfView::new(4);
+            fov.update(p, map);
+            fov
+        };
+        let red = light(MapPosition::new(1, 2), &map);
+        let blue = light(MapPosition::new(5, 2), &map);
+        let lights = [
+            (MapPosition::new(1, 2), &red, Vec3::X),
+            (MapPosition::new(5, 2), &blue, Vec3::Z),
+        ];
+        let ambient = Vec3::splat(0.1);
+        // Full strength at the light, the wall between keeps the other out
+        assert_eq!(
+            light_tint(MapPosition::new(1, 2), &lights, ambient),
+            Vec3::new(1.0, 0.1, 0.1)
+        );
+        // Fading with distance
+        let near = light_tint(MapPosition::new(2, 1), &lights, ambient);
+        assert!(near.x > 0.1 && near.x < 1.0);
+        assert!(falloff(2.0, 4) > falloff(3.0, 4));
+        assert_eq!(falloff(5.0, 4), 0.0);
+    }
+}
@@ -1,27 +1,38 @@
 use bevy::prelude::*;
 
 use self::{
-    combat::CombatPlugin, inventory::InventoryPlugin, movement::MovementPlugin,
-    player_input::PlayerInputPlugin, quest_engine::QuestEnginePlugin,
+    combat::CombatPlugin, digging::DiggingPlugin, inventory::InventoryPlugin,
+    lighting::LightingPlugin, movement::MovementPlugin, player_input::PlayerInputPlugin,
+    quest_engine::QuestEnginePlugin, stats::StatsPlugin, travel::TravelPlugin,
 };
 
 pub mod chasing_player;
 pub mod combat;
+pub mod digging;
 pub mod fov;
 pub mod inventory;
+pub mod lighting;
 pub mod movement;
 pub mod player_input;
 pub mod quest_engine;
 pub mod random_actor;
+pub mod regeneration;
+pub mod stats;
+pub mod tile_effects;
+pub mod travel;
 
 pub struct SystemsPlugin;
 
 impl Plugin for SystemsPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(CombatPlugin)
+            .add_plugin(DiggingPlugin)
             .add_plugin(MovementPlugin)
             .add_plugin(InventoryPlugin)
+            .add_plugin(LightingPlugin)
             .add_plugin(PlayerInputPlugin)
-            .add_plugin(QuestEnginePlugin);
+            .add_plugin(QuestEnginePlugin)
+            .add_plugin(StatsPlugin)
+            .add_plugin(TravelPlugin);
     }
 }
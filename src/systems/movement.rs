@@ -1,10 +1,10 @@
-use bevy::prelude::*;
+use bevy::{
+    ecs::event::{Event, ManualEventReader},
+    prelude::*,
+};
 
 use crate::{
-    camera::focus_camera,
     components::map_position::MapPosition,
-    config::Settings,
-    entities::Player,
     map::{grid_map::base_map::BaseMap, map_builder::MapBuilder},
 };
 
@@ -14,45 +14,71 @@ pub struct MovementPlugin;
 
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<WantsToMove>();
+        app.add_event::<WantsToMove>()
+            .add_event::<EnteredTile>()
+            .init_resource::<TurnEvents<WantsToMove>>()
+            .init_resource::<TurnEvents<EnteredTile>>();
+    }
+}
+
+/// Where the systems run in both the player's and the NPCs' turn got up to
+/// in an event queue. Both turns can run in one frame, so with a reader each
+/// the NPCs' turn would handle the player's events again
+#[derive(Resource)]
+pub struct TurnEvents<E: Event>(ManualEventReader<E>);
+
+impl<E: Event> Default for TurnEvents<E> {
+    fn default() -> Self {
+        Self(ManualEventReader::default())
+    }
+}
+
+impl<E: Event> TurnEvents<E> {
+    /// The events not handled yet by either turn
+    pub fn read<'a>(&'a mut self, events: &'a Events<E>) -> impl Iterator<Item = &'a E> {
+        self.0.iter(events)
     }
 }
 
+/// Label for the movement system, tile effects run after it
+pub const MOVEMENT_LABEL: &str = "MoveEntities";
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct WantsToMove {
     pub entity: Entity,
     pub destination: MapPosition,
 }
 
+/// Sent after an entity has moved onto a tile
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnteredTile {
+    pub entity: Entity,
+    pub position: MapPosition,
+}
+
 pub fn movement(
-    mut move_events: EventReader<WantsToMove>,
-    mut query: Query<(&mut Transform, &mut MapPosition, Without<Camera2d>)>,
+    move_events: Res<Events<WantsToMove>>,
+    mut reader: ResMut<TurnEvents<WantsToMove>>,
+    mut entered_events: EventWriter<EnteredTile>,
+    mut positions: Query<&mut MapPosition>,
     mut fovs: Query<&mut FieldOfView>,
-    player_query: Query<Entity, With<Player>>,
-    mut camera_query: Query<&mut Transform, With<Camera2d>>,
     map_builder: Res<MapBuilder>,
-    settings: Res<Settings>,
 ) {
-    let player = player_query.single();
-    let tile_size = settings.tile_size;
-    move_events.iter().for_each(
+    reader.read(&move_events).for_each(
         |&WantsToMove {
              entity,
              destination,
          }| {
             if map_builder.map.can_enter_tile(destination) {
-                if let Ok((mut transform, mut position, _)) = query.get_mut(entity) {
-                    transform.translation =
-                        destination.translation(transform.translation.z, tile_size);
+                if let Ok(mut position) = positions.get_mut(entity) {
                     position.position = destination.position;
-
-                    // If moving player also move camera
-                    if entity == player {
-                        focus_camera(&mut camera_query, transform);
-                    }
                     if let Ok(mut fov) = fovs.get_mut(entity) {
                         *fov = fov.clone_dirty();
                     }
+                    entered_events.send(EnteredTile {
+                        entity,
+                        position: destination,
+                    });
                 }
             }
         },
@@ -4,16 +4,20 @@ use iyes_loopless::prelude::*;
 use crate::{
     actions::Actions,
     components::map_position::MapPosition,
-    entities::{ActivateItem, AvailableQuest, Item, Monster, Player},
+    entities::{ActivateItem, AvailableQuest, Item, Monster, Player, ProvidesDigging},
+    map::map_builder::MapBuilder,
     stages::TurnState,
     GameState,
 };
 
 use super::{
     combat::WantsToAttack,
-    inventory::{PickUpEvent, PlayerInventory},
+    digging::WantsToDig,
+    inventory::{Carried, PickUpEvent, PlayerInventory},
     movement::WantsToMove,
     quest_engine::InteractQuestGiver,
+    stats::RunStats,
+    travel::TRAVEL_LABEL,
 };
 
 pub struct PlayerInputPlugin;
@@ -22,10 +26,30 @@ impl Plugin for PlayerInputPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(
             SystemSet::on_update(GameState::Playing)
-                .with_system(pick_up.run_if_resource_equals(TurnState::AwaitingInput))
-                .with_system(movement.run_if_resource_equals(TurnState::AwaitingInput))
-                .with_system(interact.run_if_resource_equals(TurnState::AwaitingInput))
-                .with_system(use_item.run_if_resource_equals(TurnState::AwaitingInput)),
+                .with_system(
+                    pick_up
+                        .run_if_resource_equals(TurnState::AwaitingInput)
+                        .after(TRAVEL_LABEL),
+                )
+                .with_system(
+                    movement
+                        .run_if_resource_equals(TurnState::AwaitingInput)
+                        .after(TRAVEL_LABEL),
+                )
+                .with_system(
+                    interact
+                        .run_if_resource_equals(TurnState::AwaitingInput)
+                        .after(TRAVEL_LABEL),
+                )
+                .with_system(
+                    use_item
+                        .run_if_resource_equals(TurnState::AwaitingInput)
+                        .after(TRAVEL_LABEL),
+                )
+                .with_system(
+                    wait.run_if_resource_equals(TurnState::AwaitingInput)
+                        .after(TRAVEL_LABEL),
+                ),
         );
     }
 }
@@ -78,16 +102,20 @@ fn interact(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn movement(
     mut commands: Commands,
     actions: Res<Actions>,
     mut move_events: EventWriter<WantsToMove>,
     mut combat_events: EventWriter<WantsToAttack>,
+    mut dig_events: EventWriter<WantsToDig>,
     player_query: Query<(Entity, &MapPosition, With<Player>)>,
     monsters: Query<(Entity, &MapPosition, With<Monster>)>,
+    diggers: Query<(&Carried, &ProvidesDigging)>,
+    map_builder: Res<MapBuilder>,
 ) {
-    if actions.player_movement.is_some() {
-        let movement = actions.player_movement.unwrap().as_ivec2();
+    if let Some(player_movement) = actions.player_movement {
+        let movement = player_movement.as_ivec2();
 
         if movement != IVec2::ZERO {
             let (entity, position, _) = player_query.single();
@@ -97,11 +125,24 @@ fn movement(
                 .iter()
                 .filter(|(_, m, _)| **m == new_position)
                 .last();
+            // Walking into a wall digs it with the best tool carried
+            let dig_power = diggers
+                .iter()
+                .filter(|(c, _)| c.entity == entity)
+                .map(|(_, d)| d.power)
+                .max()
+                .filter(|_| map_builder.map.is_diggable(new_position));
             if let Some((m, _, _)) = monster {
                 combat_events.send(WantsToAttack {
                     attacker: entity,
                     victim: m,
                 });
+            } else if let Some(power) = dig_power {
+                dig_events.send(WantsToDig {
+                    digger: entity,
+                    position: new_position,
+                    power,
+                });
             } else {
                 move_events.send(WantsToMove {
                     entity,
@@ -120,9 +161,10 @@ fn use_item(
     mut use_events: EventWriter<ActivateItem>,
     inventory_query: Query<&PlayerInventory>,
     player_query: Query<(Entity, With<Player>)>,
+    diggers: Query<(), With<ProvidesDigging>>,
+    mut stats: ResMut<RunStats>,
 ) {
-    if actions.use_item.is_some() {
-        let item_key = actions.use_item.unwrap();
+    if let Some(item_key) = actions.use_item {
         let inventory = inventory_query.single();
         let (player, _) = player_query.single();
 
@@ -131,8 +173,18 @@ fn use_item(
                 used_by: player,
                 item: *item,
             });
+            // Digging tools are not used up, so do not count as used
+            if !diggers.contains(*item) {
+                stats.items_used += 1;
+            }
         }
 
         commands.insert_resource(TurnState::PlayerTurn);
     }
 }
+
+fn wait(mut commands: Commands, actions: Res<Actions>) {
+    if actions.wait.is_some() {
+        commands.insert_resource(TurnState::PlayerTurn);
+    }
+}
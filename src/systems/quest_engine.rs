@@ -2,8 +2,8 @@ use bevy::prelude::*;
 
 use crate::{
     cleanup::cleanup_components,
-    entities::{AvailableQuest, Player, QuestState, Reward},
-    systems::inventory::Carried,
+    entities::{Player, QuestState, Reward},
+    systems::{inventory::Carried, stats::RunStats},
     GameState,
 };
 
@@ -12,6 +12,9 @@ pub struct InteractQuestGiver {
     pub reciever: Entity,
 }
 
+/// Sent when a quest is handed in
+pub struct QuestCompleted;
+
 #[derive(Debug, Component)]
 pub struct AssignedQuest {
     pub assignee: Entity,
@@ -22,12 +25,9 @@ pub struct QuestEnginePlugin;
 impl Plugin for QuestEnginePlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<InteractQuestGiver>()
+            .add_event::<QuestCompleted>()
             .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_quests))
-            .add_system_set(
-                SystemSet::on_update(GameState::Playing)
-                    .with_system(update_quests)
-                    .with_system(update_quest_giver_display),
-            )
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(update_quests))
             .add_system_set(
                 SystemSet::on_exit(GameState::Playing)
                     .with_system(cleanup_components::<PlayerQuests>),
@@ -40,12 +40,16 @@ pub fn interact_quest_giver(
     mut quest_events: EventReader<InteractQuestGiver>,
     mut assigned_quests: Query<(&mut QuestState, &AssignedQuest)>,
     rewards: Query<&Reward>,
+    mut stats: ResMut<RunStats>,
+    mut completed_events: EventWriter<QuestCompleted>,
 ) {
     quest_events.iter().for_each(|event| {
         info!("Interact quest giver");
         if let Ok((mut s, aq)) = assigned_quests.get_mut(event.quest) {
             if *s == QuestState::Updated {
                 *s = QuestState::Completed;
+                stats.quests_completed += 1;
+                completed_events.send(QuestCompleted);
                 // If reward exists, assign to the Quest assignee
                 if let Ok(reward) = rewards.get(event.quest) {
                     commands.entity(reward.0).insert(Carried {
@@ -111,21 +115,3 @@ pub fn update_quests(
         quests.updated = updated;
     }
 }
-
-pub fn update_quest_giver_display(
-    mut quest_giver: Query<(&mut TextureAtlasSprite, &AvailableQuest)>,
-    changed_quests: Query<&QuestState, Changed<QuestState>>,
-) {
-    quest_giver.iter_mut().for_each(|(mut sprite, q)| {
-        if let Ok(s) = changed_quests.get(q.0) {
-            let state_color = match s {
-                QuestState::Todo => Color::default(),
-                QuestState::Updated => Color::GREEN,
-                QuestState::Completed => Color::DARK_GRAY,
-            };
-            if sprite.color != state_color {
-                sprite.color = state_color;
-            }
-        }
-    });
-}
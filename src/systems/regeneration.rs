@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+
+use crate::components::health::{Health, Regeneration};
+
+/// Heal actors a little each round, the dead stay dead
+pub fn regenerate(mut actors: Query<(&mut Health, &mut Regeneration)>) {
+    actors
+        .iter_mut()
+        .filter(|(health, _)| health.current > 0)
+        .for_each(|(mut health, mut regeneration)| {
+            if let Some(amount) = regeneration.tick() {
+                health.heal(amount);
+            }
+        });
+}
@@ -0,0 +1,258 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use bevy::prelude::*;
+use bevy_turborand::{DelegatedRng, GlobalRng};
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Settings,
+    entities::{MapLevel, Player},
+    stages::{GameStage, TurnState},
+    GameState,
+};
+
+/// Label for seeding the run, anything reseeding it runs after
+pub const SEED_LABEL: &str = "SeedRun";
+
+/// File in the config directory the high-score table is saved to
+pub const HIGH_SCORES_FILE: &str = "high_scores.json";
+
+/// Number of runs kept in the high-score table
+const HIGH_SCORES_KEPT: usize = 10;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .init_resource::<HighScores>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Generation)
+                    .with_system(start_stats.label(SEED_LABEL)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(record_score.run_if_resource_equals(TurnState::GameOver))
+                    .with_system(record_score.run_if_resource_equals(TurnState::Victory)),
+            )
+            .add_system_set_to_stage(
+                GameStage::PlayerCombat,
+                ConditionSet::new()
+                    .run_if_resource_equals(TurnState::PlayerTurn)
+                    .with_system(count_turn)
+                    .into(),
+            );
+    }
+}
+
+/// What happened during the current run
+#[derive(Resource, Default, Debug, Clone)]
+pub struct RunStats {
+    /// Seed the run was generated from
+    pub seed: u64,
+    pub turns: u32,
+    /// Monsters the player killed, by name
+    pub kills: BTreeMap<String, u32>,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub items_used: u32,
+    pub quests_completed: u32,
+    pub deepest_level: u32,
+    /// What dealt the killing blow
+    pub cause_of_death: Option<String>,
+}
+
+impl RunStats {
+    pub fn add_kill(&mut self, name: &str) {
+        *self.kills.entry(name.to_string()).or_default() += 1;
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.kills.values().sum()
+    }
+
+    /// Depth counts most, then kills and quests, with a bonus for winning
+    /// that is larger the fewer turns it took
+    pub fn score(&self, won: bool) -> u32 {
+        let win_bonus = if won {
+            1000 + 1000u32.saturating_sub(self.turns / 2)
+        } else {
+            0
+        };
+        (self.deepest_level + 1) * 100
+            + self.total_kills() * 10
+            + self.quests_completed * 50
+            + win_bonus
+    }
+}
+
+/// A finished run in the high-score table
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HighScore {
+    pub score: u32,
+    pub seed: u64,
+    pub level: u32,
+    pub turns: u32,
+    /// `None` for a won run
+    pub cause_of_death: Option<String>,
+}
+
+impl HighScore {
+    /// Score a finished run, counting the level it ended on as reached
+    pub fn from_run(stats: &RunStats, won: bool, level: u32) -> Self {
+        let mut stats = stats.clone();
+        stats.deepest_level = stats.deepest_level.max(level);
+        Self {
+            score: stats.score(won),
+            seed: stats.seed,
+            level: stats.deepest_level,
+            turns: stats.turns,
+            cause_of_death: match won {
+                true => None,
+                false => Some(stats.cause_of_death.unwrap_or_else(|| "unknown".into())),
+            },
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let end = match &self.cause_of_death {
+            None => "won".to_string(),
+            Some(cause) => format!("killed by {}", cause),
+        };
+        format!(
+            "{} - level {}, {}, seed {}",
+            self.score, self.level, end, self.seed
+        )
+    }
+}
+
+/// The best runs, best first, and the run just finished. Saved to `path`
+/// when there is one
+#[derive(Resource, Default, Debug)]
+pub struct HighScores {
+    path: Option<PathBuf>,
+    pub scores: Vec<HighScore>,
+    pub last: Option<HighScore>,
+}
+
+impl HighScores {
+    /// Read the table from a file, starting a new one if it is missing or
+    /// unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let scores = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            scores,
+            last: None,
+        }
+    }
+
+    /// Add a run, keeping the best
+    pub fn add(&mut self, score: HighScore) {
+        self.scores.push(score.clone());
+        self.scores.sort_by_key(|s| std::cmp::Reverse(s.score));
+        self.scores.truncate(HIGH_SCORES_KEPT);
+        self.last = Some(score);
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&self.scores).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+}
+
+/// Start counting a new run, seeding it so the seed can be shown with the score
+fn start_stats(mut stats: ResMut<RunStats>, mut rng: ResMut<GlobalRng>, settings: Res<Settings>) {
+    let seed = settings.seed.unwrap_or_else(|| rng.u64(..));
+    *rng = GlobalRng::with_seed(seed);
+    *stats = RunStats { seed, ..default() };
+}
+
+fn count_turn(mut stats: ResMut<RunStats>, level: Query<&MapLevel, With<Player>>) {
+    stats.turns += 1;
+    if let Ok(level) = level.get_single() {
+        stats.deepest_level = stats.deepest_level.max(level.value);
+    }
+}
+
+/// Score the finished run and add it to the high-score table
+fn record_score(
+    stats: Res<RunStats>,
+    mut high_scores: ResMut<HighScores>,
+    turn_state: Res<TurnState>,
+    level: Query<&MapLevel, With<Player>>,
+) {
+    let won = *turn_state == TurnState::Victory;
+    let level = level.get_single().map_or(0, |l| l.value);
+    let score = HighScore::from_run(&stats, won, level);
+    info!("Run over: {}", score.describe());
+    high_scores.add(score);
+    if let Err(e) = high_scores.save() {
+        error!("Could not save high scores: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        let mut stats = RunStats {
+            turns: 400,
+            deepest_level: 2,
+            quests_completed: 1,
+            ..default()
+        };
+        stats.add_kill("Nutritionist");
+        stats.add_kill("Nutritionist");
+        assert_eq!(stats.kills["Nutritionist"], 2);
+        assert_eq!(stats.score(false), 300 + 20 + 50);
+        assert_eq!(stats.score(true), 300 + 20 + 50 + 1000 + 800);
+        // Slow wins still beat losing on the same level
+        stats.turns = 100_000;
+        assert_eq!(stats.score(true), 300 + 20 + 50 + 1000);
+    }
+
+    #[test]
+    fn test_from_run() {
+        let stats = RunStats {
+            deepest_level: 1,
+            cause_of_death: Some("Gym Bro".into()),
+            ..default()
+        };
+        // The level the run ended on counts even before it is recorded as reached
+        let lost = HighScore::from_run(&stats, false, 2);
+        assert_eq!(lost.level, 2);
+        assert_eq!(lost.score, 300);
+        assert_eq!(lost.cause_of_death.as_deref(), Some("Gym Bro"));
+        let won = HighScore::from_run(&stats, true, 0);
+        assert_eq!(won.level, 1);
+        assert_eq!(won.cause_of_death, None);
+    }
+
+    #[test]
+    fn test_high_scores() {
+        let mut high_scores = HighScores::default();
+        (0..12).for_each(|score| {
+            high_scores.add(HighScore {
+                score,
+                seed: 0,
+                level: 0,
+                turns: 0,
+                cause_of_death: None,
+            })
+        });
+        assert_eq!(high_scores.scores.len(), HIGH_SCORES_KEPT);
+        assert_eq!(high_scores.scores[0].score, 11);
+        assert_eq!(high_scores.last.as_ref().unwrap().score, 11);
+        assert_eq!(high_scores.scores.last().unwrap().score, 2);
+    }
+}
@@ -0,0 +1,96 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    components::{health::Health, map_position::MapPosition},
+    config::Settings,
+    entities::{Player, Tile, TileEffect, TileType},
+    map::{
+        grid_map::base_map::{BaseMap, BaseMapMut},
+        map_builder::MapBuilder,
+    },
+};
+
+use super::{
+    combat::CombatLog,
+    fov::FieldOfView,
+    movement::{EnteredTile, TurnEvents},
+    stats::RunStats,
+};
+
+/// Trigger the doors, traps and plates entities have moved onto
+#[allow(clippy::too_many_arguments)]
+pub fn tile_effects(
+    mut commands: Commands,
+    entered_events: Res<Events<EnteredTile>>,
+    mut reader: ResMut<TurnEvents<EnteredTile>>,
+    mut map_builder: ResMut<MapBuilder>,
+    mut healths: Query<&mut Health>,
+    player_query: Query<Entity, With<Player>>,
+    mut tiles: Query<(&MapPosition, &mut TileType), With<Tile>>,
+    mut fovs: Query<&mut FieldOfView>,
+    settings: Res<Settings>,
+    mut stats: ResMut<RunStats>,
+    mut log: ResMut<CombatLog>,
+) {
+    let traps = &settings.map_settings.traps;
+    let mut changed = HashMap::new();
+    reader.read(&entered_events).for_each(|event| {
+        let tile = map_builder.map.value(event.position);
+        match tile.properties().on_enter {
+            Some(TileEffect::OpenDoor) => {
+                info!("Door opened");
+                changed.insert(event.position, TileType::DoorOpen);
+            }
+            Some(TileEffect::Spikes) => {
+                info!("Spike trap triggered");
+                if tile == TileType::HiddenSpikes {
+                    changed.insert(event.position, TileType::Spikes);
+                }
+                if let Ok(mut health) = healths.get_mut(event.entity) {
+                    health.current -= traps.spike_damage;
+                    if !player_query.contains(event.entity) {
+                        if health.current < 1 {
+                            commands.entity(event.entity).despawn_recursive();
+                        }
+                    } else {
+                        log.push(format!("A spike trap hits for {}", traps.spike_damage));
+                        stats.damage_taken += traps.spike_damage;
+                        if health.current < 1 {
+                            stats.cause_of_death = Some("a spike trap".to_string());
+                        }
+                    }
+                }
+            }
+            Some(TileEffect::OpenSecretWalls) => {
+                info!("Pressure plate pressed");
+                map_builder
+                    .map
+                    .tiles
+                    .indexed_iter()
+                    .map(|(idx, t)| (MapPosition::from_utuple(&idx), t))
+                    .filter(|(p, t)| {
+                        **t == TileType::SecretWall
+                            && p.distance(event.position) <= traps.plate_radius
+                    })
+                    .for_each(|(p, _)| {
+                        changed.insert(p, TileType::Floor);
+                    });
+            }
+            None => {}
+        }
+    });
+    if changed.is_empty() {
+        return;
+    }
+
+    changed
+        .iter()
+        .for_each(|(p, t)| map_builder.map.set(*p, *t));
+    tiles.iter_mut().for_each(|(p, mut tile_type)| {
+        if let Some(new_type) = changed.get(p) {
+            *tile_type = *new_type;
+        }
+    });
+    // The map changed so what everyone can see may have too
+    fovs.iter_mut().for_each(|mut fov| *fov = fov.clone_dirty());
+}
@@ -0,0 +1,399 @@
+use bevy::{prelude::*, utils::HashSet};
+use iyes_loopless::prelude::*;
+
+use crate::{
+    actions::{Actions, ACTIONS_LABEL},
+    components::{health::Health, map_position::MapPosition},
+    entities::{AvailableQuest, Item, Monster, Player, Tile},
+    map::{
+        grid_map::{base_map::BaseMap, DjikstraMapCalc},
+        map_builder::MapBuilder,
+        tile_map::TileMap,
+    },
+    stages::TurnState,
+    GameState,
+};
+
+use super::fov::FieldOfView;
+
+pub struct TravelPlugin;
+
+/// Label for auto walking, player input systems run after it
+pub const TRAVEL_LABEL: &str = "Travel";
+
+/// Time between the steps of an auto walk, so it can be followed
+const STEP_SECONDS: f32 = 0.1;
+
+impl Plugin for TravelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Travel>()
+            .init_resource::<Explored>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(
+                    travel
+                        .run_if_resource_equals(TurnState::AwaitingInput)
+                        .label(TRAVEL_LABEL)
+                        .after(ACTIONS_LABEL),
+                ),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing)
+                    .with_system(cancel_travel)
+                    .with_system(forget_explored),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(cancel_travel.run_if_resource_equals(TurnState::NextLevel))
+                    .with_system(forget_explored.run_if_resource_equals(TurnState::NextLevel)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TravelTarget {
+    /// Walk to a tile
+    Tile(MapPosition),
+    /// Walk up to a quest giver and talk to them
+    Interact(Entity),
+    /// Keep walking to the nearest tile not seen yet
+    Explore,
+    /// Repeat a step, or wait when there is none, for the turns left
+    Repeat { step: Option<IVec2>, remaining: u32 },
+    /// Wait until healed
+    Rest,
+}
+
+/// Tiles the player has seen on this level, apart from the tiles' own
+/// visibility, which a dungeon map sets for the whole level
+#[derive(Resource, Default)]
+pub struct Explored(HashSet<MapPosition>);
+
+/// Where the player is auto walking to, one step per turn
+#[derive(Resource)]
+pub struct Travel {
+    pub target: Option<TravelTarget>,
+    /// Monsters in view at the last step, only new ones stop the walk
+    known_hostiles: HashSet<Entity>,
+    /// Where the walk started, an item there is already known about
+    origin: MapPosition,
+    /// Player health at the last step
+    health: i32,
+    timer: Timer,
+}
+
+impl Default for Travel {
+    fn default() -> Self {
+        Self {
+            target: None,
+            known_hostiles: HashSet::new(),
+            origin: MapPosition::ZERO,
+            health: 0,
+            timer: Timer::from_seconds(STEP_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
+impl Travel {
+    pub fn start(
+        &mut self,
+        target: TravelTarget,
+        origin: MapPosition,
+        health: i32,
+        known_hostiles: HashSet<Entity>,
+    ) {
+        *self = Self {
+            target: Some(target),
+            known_hostiles,
+            origin,
+            health,
+            ..default()
+        };
+    }
+
+    pub fn cancel(&mut self) {
+        self.target = None;
+    }
+}
+
+/// Next step from `from` towards the nearest of `to`, `None` if there
+/// is no way there or it has been reached
+pub fn step_towards(map: &TileMap, from: MapPosition, to: &[MapPosition]) -> Option<Vec2> {
+    if to.contains(&from) {
+        return None;
+    }
+    let dmap = map.multi_djikstra_map(to);
+    dmap.value(from)?;
+    let next = dmap.next_along_path(from);
+    Some((next.position - from.position).as_vec2())
+}
+
+fn is_adjacent(a: MapPosition, b: MapPosition) -> bool {
+    let diff = (a.position - b.position).abs();
+    diff.x + diff.y == 1
+}
+
+/// Turn clicks into walks, attacks and interactions, and counted inputs
+/// into repeats, then take the next step of a walk, exploration, repeat
+/// or rest by setting the player's [`Actions`]
+#[allow(clippy::too_many_arguments)]
+pub fn travel(
+    mut actions: ResMut<Actions>,
+    mut travel: ResMut<Travel>,
+    mut explored: ResMut<Explored>,
+    time: Res<Time>,
+    player: Query<(&MapPosition, &FieldOfView, &Health), With<Player>>,
+    monsters: Query<(Entity, &MapPosition), With<Monster>>,
+    quest_givers: Query<(Entity, &MapPosition), With<AvailableQuest>>,
+    items: Query<&MapPosition, With<Item>>,
+    tiles: Query<(&MapPosition, &Visibility), With<Tile>>,
+    map_builder: Res<MapBuilder>,
+) {
+    let player_input = actions.player_movement.is_some()
+        || actions.wait.is_some()
+        || actions.pick_up_item.is_some()
+        || actions.interact.is_some()
+        || actions.use_item.is_some();
+    let mut count = None;
+    if player_input
+        || actions.rest.is_some()
+        || actions.explore.is_some()
+        || actions.clicked_tile.is_some()
+    {
+        // Any other input takes over from a walk, and takes the count typed before it
+        travel.cancel();
+        count = actions.count.take();
+    }
+    let (position, fov, health) = player.single();
+    explored.0.extend(fov.visible_positions.iter().copied());
+    let visible_hostiles = monsters
+        .iter()
+        .filter(|(_, p)| fov.visible_positions.contains(p))
+        .map(|(e, _)| e)
+        .collect::<HashSet<_>>();
+    let mut start = |target| {
+        travel.start(target, *position, health.current, visible_hostiles.clone());
+    };
+
+    if player_input {
+        // The first go is taken by the player input systems this turn
+        let remaining = count.unwrap_or(1).saturating_sub(1);
+        let step = actions
+            .player_movement
+            .map(|movement| movement.as_ivec2())
+            .filter(|step| *step != IVec2::ZERO);
+        if remaining > 0 && (step.is_some() || actions.wait.is_some()) {
+            start(TravelTarget::Repeat { step, remaining });
+        }
+        return;
+    }
+    if actions.rest.is_some() {
+        if health.is_full() {
+            info!("Already at full health");
+            return;
+        }
+        if !visible_hostiles.is_empty() {
+            info!("Cannot rest with hostiles in view");
+            return;
+        }
+        start(TravelTarget::Rest);
+    } else if actions.explore.is_some() {
+        start(TravelTarget::Explore);
+    } else if let Some(clicked) = actions.clicked_tile {
+        if let Some((_, monster)) = monsters.iter().find(|(_, p)| **p == clicked) {
+            if is_adjacent(*position, *monster) {
+                info!("Clicked to attack");
+                actions.player_movement = Some((monster.position - position.position).as_vec2());
+            }
+            return;
+        }
+        if let Some((npc, _)) = quest_givers.iter().find(|(_, p)| **p == clicked) {
+            start(TravelTarget::Interact(npc));
+        } else if tiles
+            .iter()
+            .any(|(p, v)| *p == clicked && v.is_visible && map_builder.map.can_enter_tile(*p))
+        {
+            start(TravelTarget::Tile(clicked));
+        }
+        // The first step is taken straight away
+    } else if travel.target.is_none() || !travel.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if !visible_hostiles.is_subset(&travel.known_hostiles) {
+        info!("Hostile in view, stopped");
+        travel.cancel();
+        return;
+    }
+    // One that leaves the view stops the walk again when it comes back
+    travel.known_hostiles = visible_hostiles;
+    if health.current < travel.health {
+        info!("Hurt, stopped");
+        travel.cancel();
+        return;
+    }
+    travel.health = health.current;
+    if *position != travel.origin && items.iter().any(|p| p == position) {
+        info!("Item underfoot, stopped walking");
+        travel.cancel();
+        return;
+    }
+    let destinations = match travel.target {
+        Some(TravelTarget::Tile(destination)) => vec![destination],
+        Some(TravelTarget::Interact(npc)) => match quest_givers.get(npc) {
+            Ok((_, npc_position)) if is_adjacent(*position, *npc_position) => {
+                actions.interact = Some(true);
+                travel.cancel();
+                return;
+            }
+            Ok((_, npc_position)) => vec![*npc_position],
+            Err(_) => {
+                travel.cancel();
+                return;
+            }
+        },
+        Some(TravelTarget::Explore) => tiles
+            .iter()
+            .map(|(p, _)| *p)
+            .filter(|p| !explored.0.contains(p) && map_builder.map.can_enter_tile(*p))
+            .collect(),
+        Some(TravelTarget::Repeat { step, remaining }) => {
+            travel.target = (remaining > 1).then_some(TravelTarget::Repeat {
+                step,
+                remaining: remaining - 1,
+            });
+            match step {
+                Some(step) => vec![MapPosition::from_ivec2(position.position + step)],
+                None => {
+                    actions.wait = Some(true);
+                    return;
+                }
+            }
+        }
+        Some(TravelTarget::Rest) => {
+            if health.is_full() {
+                info!("Rested");
+                travel.cancel();
+            } else {
+                actions.wait = Some(true);
+            }
+            return;
+        }
+        None => return,
+    };
+    let step = step_towards(&map_builder.map, *position, &destinations);
+    if step.is_none() && travel.target == Some(TravelTarget::Explore) {
+        info!("Nothing left to explore");
+    }
+    // Walking into a monster would attack it, leave that to the player
+    let blocked = step.is_some_and(|step| {
+        let next = MapPosition::from_ivec2(position.position + step.as_ivec2());
+        monsters.iter().any(|(_, p)| *p == next)
+    });
+    match step {
+        Some(step) if !blocked => actions.player_movement = Some(step),
+        _ => travel.cancel(),
+    }
+}
+
+fn cancel_travel(mut travel: ResMut<Travel>) {
+    travel.cancel();
+}
+
+fn forget_explored(mut explored: ResMut<Explored>) {
+    explored.0.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entities::TileType,
+        map::grid_map::base_map::BaseMapMut,
+        systems::chasing_player::ChasingPlayer,
+        test_harness::{only_monster, Harness},
+    };
+
+    use super::*;
+
+    /// Take the next step of a walk without waiting for it
+    fn next_step(game: &mut Harness) {
+        let mut travel = game.app.world.resource_mut::<Travel>();
+        let duration = travel.timer.duration();
+        travel.timer.set_elapsed(duration);
+        game.app.update();
+        // The step would otherwise be taken as the player's input next frame
+        *game.app.world.resource_mut::<Actions>() = Actions::default();
+    }
+
+    #[test]
+    fn test_explore_after_dungeon_map() {
+        let mut game = Harness::new(
+            "##########################
+             #@.......................#
+             ##########################",
+        );
+        // Shown as reading a dungeon map shows them
+        game.app
+            .world
+            .query_filtered::<&mut Visibility, With<Tile>>()
+            .iter_mut(&mut game.app.world)
+            .for_each(|mut v| v.is_visible = true);
+        let player = game.player();
+        let x = |game: &mut Harness| game.get::<MapPosition>(player).unwrap().position.x;
+        game.act(|actions| actions.explore = Some(true));
+        let walked = x(&mut game);
+        assert!(walked > 1);
+        next_step(&mut game);
+        assert!(x(&mut game) > walked);
+    }
+
+    #[test]
+    fn test_hostile_coming_back() {
+        let mut game = Harness::with_settings(
+            "##########################
+             #@.......................#
+             #...M....................#
+             ##########################",
+            |settings| only_monster(settings, "Nutritionist"),
+        );
+        let monster = game.named("Nutritionist")[0];
+        // Stays where the test puts it
+        game.app.world.entity_mut(monster).remove::<ChasingPlayer>();
+        let move_monster = |game: &mut Harness, x| {
+            *game.app.world.get_mut::<MapPosition>(monster).unwrap() = MapPosition::new(x, 2);
+        };
+
+        // Already in view, so it does not stop the walk
+        game.act(|actions| actions.explore = Some(true));
+        assert!(game.app.world.resource::<Travel>().target.is_some());
+        move_monster(&mut game, 24);
+        next_step(&mut game);
+        assert!(game.app.world.resource::<Travel>().target.is_some());
+        move_monster(&mut game, 5);
+        next_step(&mut game);
+        assert!(game.app.world.resource::<Travel>().target.is_none());
+    }
+
+    #[test]
+    fn test_step_towards() {
+        let mut map = TileMap::new(3, 5);
+        (0..3).for_each(|y| map.set(MapPosition::new(2, y), TileType::Wall));
+        let from = MapPosition::new(0, 1);
+        assert_eq!(
+            step_towards(&map, from, &[MapPosition::new(0, 2)]),
+            Some(Vec2::new(0.0, 1.0))
+        );
+        assert_eq!(step_towards(&map, from, &[from]), None);
+        assert_eq!(step_towards(&map, from, &[]), None);
+        // Walled off
+        assert_eq!(step_towards(&map, from, &[MapPosition::new(4, 1)]), None);
+        // Nearest of several
+        assert_eq!(
+            step_towards(
+                &map,
+                from,
+                &[MapPosition::new(4, 1), MapPosition::new(1, 1)]
+            ),
+            Some(Vec2::new(1.0, 0.0))
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! Runs the turn pipeline headless on a fixed map, so tests can script
+//! the player's actions turn by turn.
+
+use bevy::prelude::*;
+
+use crate::{
+    actions::Actions, components::name::EntityName, config::Settings, entities::Player,
+    headless::headless_app, map::map_builder::MapBuilder, stages::TurnState,
+};
+
+/// Seed for the harness, so the monsters and items picked are the same each run
+const SEED: u64 = 1000;
+
+pub struct Harness {
+    pub app: App,
+}
+
+impl Harness {
+    /// Start a game on a map in the [`MapBuilder`] text format with the
+    /// default settings
+    pub fn new(map: &str) -> Self {
+        Self::with_settings(map, |_| {})
+    }
+
+    /// Start a game on a map, changing the default settings first
+    pub fn with_settings(map: &str, configure: impl FnOnce(&mut Settings)) -> Self {
+        let mut settings = Settings::load_profile("development").expect("default settings");
+        configure(&mut settings);
+        let map_builder = map.parse::<MapBuilder>().expect("valid map");
+
+        let mut app = headless_app(settings, SEED, Some(map_builder));
+        // Spawn the level
+        app.update();
+        Self { app }
+    }
+
+    /// Take a turn with the actions `set` gives the player, then let the
+    /// frame after it settle any events read outside the turn stages
+    pub fn act(&mut self, set: impl FnOnce(&mut Actions)) -> TurnState {
+        set(&mut self.app.world.resource_mut::<Actions>());
+        self.app.update();
+        *self.app.world.resource_mut::<Actions>() = Actions::default();
+        // A lost or won game is left for the test to see, the next frame ends it
+        if self.turn_state() == TurnState::AwaitingInput {
+            self.app.update();
+        }
+        self.turn_state()
+    }
+
+    /// Take a turn moving, or attacking, in a direction
+    pub fn step(&mut self, x: i32, y: i32) -> TurnState {
+        self.act(|actions| actions.player_movement = Some(Vec2::new(x as f32, y as f32)))
+    }
+
+    pub fn turn_state(&self) -> TurnState {
+        *self.app.world.resource::<TurnState>()
+    }
+
+    pub fn player(&mut self) -> Entity {
+        self.app
+            .world
+            .query_filtered::<Entity, With<Player>>()
+            .single(&self.app.world)
+    }
+
+    /// Entities with the name, spawned from the settings
+    pub fn named(&mut self, name: &str) -> Vec<Entity> {
+        self.app
+            .world
+            .query::<(Entity, &EntityName)>()
+            .iter(&self.app.world)
+            .filter(|(_, n)| n.0 == name)
+            .map(|(e, _)| e)
+            .collect()
+    }
+
+    pub fn get<T: Component + Copy>(&self, entity: Entity) -> Option<T> {
+        self.app.world.get::<T>(entity).copied()
+    }
+}
+
+/// Keep only the named monster, on every level
+pub fn only_monster(settings: &mut Settings, name: &str) {
+    let monsters = &mut settings.monsters_settings.monsters;
+    monsters.retain(|m| m.actor.entity.name == name);
+    monsters
+        .iter_mut()
+        .for_each(|m| m.actor.entity.levels = vec![0, 1, 2]);
+}
+
+/// Keep only the named item, on every level
+pub fn only_item(settings: &mut Settings, name: &str) {
+    let items = &mut settings.items_settings.items;
+    items.retain(|i| i.entity.name == name);
+    items
+        .iter_mut()
+        .for_each(|i| i.entity.levels = vec![0, 1, 2]);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        components::{health::Health, map_position::MapPosition},
+        entities::{AvailableQuest, MapLevel, Monster},
+        systems::{inventory::Carried, quest_engine::AssignedQuest, stats::RunStats},
+        theme::ThemeChoice,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_movement() {
+        let mut game = Harness::new(
+            "#####
+             #@..#
+             #####",
+        );
+        let player = game.player();
+        assert_eq!(game.step(1, 0), TurnState::AwaitingInput);
+        assert_eq!(game.get(player), Some(MapPosition::new(2, 1)));
+        // Walls block
+        game.step(0, 1);
+        assert_eq!(game.get(player), Some(MapPosition::new(2, 1)));
+    }
+
+    #[test]
+    fn test_combat_and_chasing() {
+        let mut game = Harness::with_settings(
+            "########
+             #@....M#
+             ########",
+            |settings| only_monster(settings, "Nutritionist"),
+        );
+        let player = game.player();
+        let monster = game.named("Nutritionist")[0];
+
+        // The monster closes in while the player waits, then attacks
+        let player_position = game.get::<MapPosition>(player).unwrap();
+        let start = game.get::<MapPosition>(monster).unwrap();
+        game.act(|actions| actions.wait = Some(true));
+        let position = game.get::<MapPosition>(monster).unwrap();
+        assert!(position.distance(player_position) < start.distance(player_position));
+        let max = game.get::<Health>(player).unwrap().max;
+        for _ in 0..10 {
+            if game.get::<Health>(player).unwrap().current < max {
+                break;
+            }
+            game.act(|actions| actions.wait = Some(true));
+        }
+        assert!(game.get::<Health>(player).unwrap().current < max);
+
+        // Two hits kill it
+        let position = game.get::<MapPosition>(monster).unwrap();
+        let direction = position.position - player_position.position;
+        game.step(direction.x, direction.y);
+        game.step(direction.x, direction.y);
+        assert!(game.named("Nutritionist").is_empty());
+        let stats = game.app.world.resource::<RunStats>();
+        assert_eq!(stats.kills.get("Nutritionist"), Some(&1));
+        assert!(stats.damage_dealt > 0 && stats.damage_taken > 0);
+    }
+
+    #[test]
+    fn test_game_over() {
+        let mut game = Harness::with_settings(
+            "#####
+             #@M.#
+             #####",
+            |settings| {
+                only_monster(settings, "Nutritionist");
+                settings.player_settings.max_health = 1;
+            },
+        );
+        assert_eq!(
+            game.act(|actions| actions.wait = Some(true)),
+            TurnState::GameOver
+        );
+        let stats = game.app.world.resource::<RunStats>();
+        assert_eq!(stats.cause_of_death.as_deref(), Some("Nutritionist"));
+    }
+
+    #[test]
+    fn test_pick_up() {
+        let mut game = Harness::with_settings(
+            "#####
+             #@I.#
+             #####",
+            |settings| only_item(settings, "Rusty Sword"),
+        );
+        let player = game.player();
+        let sword = game.named("Rusty Sword")[0];
+        game.step(1, 0);
+        game.act(|actions| actions.pick_up_item = Some(true));
+        assert_eq!(game.get::<Carried>(sword).map(|c| c.entity), Some(player));
+        assert!(game.get::<MapPosition>(sword).is_none());
+    }
+
+    #[test]
+    fn test_quest_giver() {
+        let mut game = Harness::new(
+            "#####
+             #@N.#
+             #####",
+        );
+        let quest = game
+            .app
+            .world
+            .query_filtered::<Entity, With<AvailableQuest>>()
+            .single(&game.app.world);
+        let quest = game.app.world.get::<AvailableQuest>(quest).unwrap().0;
+        game.act(|actions| actions.interact = Some(true));
+        assert!(game.app.world.get::<AssignedQuest>(quest).is_some());
+    }
+
+    #[test]
+    fn test_spike_trap() {
+        let mut game = Harness::with_settings(
+            "#####
+             #@,.#
+             #####",
+            |settings| settings.map_settings.traps.spike_damage = 2,
+        );
+        let player = game.player();
+        game.step(1, 0);
+        // Hit once, though the NPCs' turn moves things again in the same frame
+        assert_eq!(game.get::<Health>(player).unwrap().current, 8);
+        assert_eq!(game.app.world.resource::<RunStats>().damage_taken, 2);
+    }
+
+    #[test]
+    fn test_end_turn() {
+        let mut game = Harness::new(
+            "#####
+             #@.>#
+             #####",
+        );
+        assert_eq!(game.step(1, 0), TurnState::AwaitingInput);
+        assert_eq!(game.step(1, 0), TurnState::NextLevel);
+        // The next level is generated in the frame after
+        game.app.update();
+        assert_eq!(game.turn_state(), TurnState::AwaitingInput);
+        let player = game.player();
+        assert_eq!(game.app.world.get::<MapLevel>(player).unwrap().value, 1);
+    }
+
+    #[test]
+    fn test_theme_without_level_monsters() {
+        let mut settings = Settings::load_profile("development").unwrap();
+        only_monster(&mut settings, "Nutritionist");
+        // None of the theme's monsters are on the level, so it falls back to all of them
+        settings
+            .themes
+            .iter_mut()
+            .for_each(|t| t.monsters = vec!["Gym Bro".to_string()]);
+        let theme = settings.themes[0].name.clone();
+        let mut app = headless_app(settings, SEED, None);
+        app.insert_resource(ThemeChoice(Some(theme)));
+        // Generate the map, then spawn the level
+        app.update();
+        app.update();
+        let monsters = app
+            .world
+            .query_filtered::<&EntityName, With<Monster>>()
+            .iter(&app.world)
+            .map(|name| name.0.clone())
+            .collect::<Vec<_>>();
+        assert!(!monsters.is_empty());
+        assert!(monsters.iter().all(|name| name == "Nutritionist"));
+    }
+}
@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::IntoConditionalSystem;
+
+use crate::{
+    config::{ArchitectSettings, Palette, Prefab, Settings, ThemeSettings},
+    entities::{MapLevel, TileType},
+    map::GEN_MAP_LABEL,
+    stages::TurnState,
+    GameState,
+};
+
+/// Plugin for picking the dungeon theme of each level
+pub struct ThemePlugin;
+
+/// Label for picking the theme, before the map is generated
+pub const THEME_LABEL: &str = "SelectTheme";
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ThemeChoice>()
+            .init_resource::<ActiveTheme>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Generation)
+                    .with_system(select_first_theme.label(THEME_LABEL)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(
+                    select_next_theme
+                        .run_if_resource_equals(TurnState::NextLevel)
+                        .label(THEME_LABEL)
+                        .before(GEN_MAP_LABEL),
+                ),
+            );
+    }
+}
+
+/// Theme chosen in the menu, `None` picks a theme per level
+#[derive(Resource, Default, Debug)]
+pub struct ThemeChoice(pub Option<String>);
+
+/// Theme of the level being played
+#[derive(Resource, Default, Debug)]
+pub struct ActiveTheme {
+    pub name: Option<String>,
+}
+
+impl ActiveTheme {
+    fn select(choice: &ThemeChoice, settings: &Settings, level: u32) -> Self {
+        let chosen = choice
+            .0
+            .as_ref()
+            .filter(|name| settings.themes.iter().any(|t| &t.name == *name));
+        Self {
+            name: chosen.cloned().or_else(|| {
+                settings
+                    .themes
+                    .iter()
+                    .find(|t| t.levels.contains(&level))
+                    .map(|t| t.name.clone())
+            }),
+        }
+    }
+
+    pub fn settings<'a>(&self, settings: &'a Settings) -> Option<&'a ThemeSettings> {
+        let name = self.name.as_ref()?;
+        settings.themes.iter().find(|t| &t.name == name)
+    }
+
+    pub fn tile_sprite(&self, settings: &Settings, tile: &TileType) -> usize {
+        let tile = &tile.appearance();
+        self.settings(settings)
+            .and_then(|t| t.tile_sprites.get(tile))
+            .copied()
+            .unwrap_or(settings.map_settings.tile_sprites[tile])
+    }
+
+    pub fn architect(&self, settings: &Settings) -> ArchitectSettings {
+        let mut architect = settings.map_settings.architect.clone();
+        if let Some(theme) = self.settings(settings) {
+            if let Some(theme_architect) = theme.architect {
+                architect.architect = theme_architect;
+            }
+            if let Some(pipeline) = &theme.pipeline {
+                architect.pipeline = pipeline.clone();
+            }
+            if let Some(fixed) = &theme.fixed {
+                architect.fixed = Some(fixed.clone());
+            }
+        }
+        architect
+    }
+
+    pub fn prefabs(&self, settings: &Settings) -> Vec<Prefab> {
+        self.settings(settings)
+            .and_then(|t| t.prefabs.clone())
+            .unwrap_or_else(|| vec![Prefab::Fortress])
+    }
+
+    pub fn palette(&self, settings: &Settings) -> Palette {
+        self.settings(settings)
+            .and_then(|t| t.palette)
+            .unwrap_or(settings.map_settings.lighting.palette)
+    }
+
+    pub fn allows_monster(&self, settings: &Settings, name: &str) -> bool {
+        self.settings(settings).map_or(true, |t| {
+            t.monsters.is_empty() || t.monsters.iter().any(|m| m == name)
+        })
+    }
+
+    pub fn allows_item(&self, settings: &Settings, name: &str) -> bool {
+        self.settings(settings).map_or(true, |t| {
+            t.items.is_empty() || t.items.iter().any(|i| i == name)
+        })
+    }
+}
+
+fn select_first_theme(
+    mut active: ResMut<ActiveTheme>,
+    choice: Res<ThemeChoice>,
+    settings: Res<Settings>,
+) {
+    *active = ActiveTheme::select(&choice, &settings, 0);
+    info!("Theme {:?}", active.name);
+}
+
+fn select_next_theme(
+    mut active: ResMut<ActiveTheme>,
+    choice: Res<ThemeChoice>,
+    settings: Res<Settings>,
+    level: Query<&MapLevel>,
+) {
+    // The level is advanced after the new map is generated
+    let next_level = level.get_single().map_or(0, |l| l.value + 1);
+    let next = ActiveTheme::select(&choice, &settings, next_level);
+    if next.name != active.name {
+        info!("Theme {:?}", next.name);
+        *active = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Architect;
+
+    #[test]
+    fn test_select_per_level() {
+        let settings = Settings::from_profile("development").unwrap();
+        let choice = ThemeChoice::default();
+        assert_eq!(
+            ActiveTheme::select(&choice, &settings, 0).name.as_deref(),
+            Some("Bakery")
+        );
+        assert_eq!(
+            ActiveTheme::select(&choice, &settings, 2).name.as_deref(),
+            Some("FatSam's Dinner")
+        );
+        assert_eq!(ActiveTheme::select(&choice, &settings, 40).name, None);
+    }
+
+    #[test]
+    fn test_final_level_fixed() {
+        let settings = Settings::from_profile("development").unwrap();
+        let theme = ActiveTheme::select(&ThemeChoice::default(), &settings, settings.end_level - 1);
+        assert!(matches!(
+            theme.architect(&settings).architect,
+            Architect::Fixed
+        ));
+    }
+
+    #[test]
+    fn test_select_choice() {
+        let settings = Settings::from_profile("development").unwrap();
+        let choice = ThemeChoice(Some("Forest".to_string()));
+        let theme = ActiveTheme::select(&choice, &settings, 0);
+        assert_eq!(theme.name.as_deref(), Some("Forest"));
+        assert_eq!(theme.tile_sprite(&settings, &TileType::Floor), 59);
+        assert!(theme.allows_monster(&settings, "Gym Bro"));
+    }
+}
@@ -0,0 +1,212 @@
+use bevy::prelude::{KeyCode, Vec2};
+use crossterm::event::{KeyCode as TermKey, KeyEvent, KeyModifiers};
+
+use crate::{
+    actions::{Actions, GameControl, MAX_COUNT},
+    config::{BindingSettings, InputBinding},
+};
+
+/// What a key pressed in the terminal does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermInput {
+    /// A key bound to a control, a digit for a count, or both
+    Key {
+        control: Option<GameControl>,
+        digit: Option<u32>,
+    },
+    Quit,
+}
+
+/// The key a terminal key is bound as, the same bindings as in the window
+fn key_code(key: TermKey) -> Option<KeyCode> {
+    let code = match key {
+        TermKey::Up => KeyCode::Up,
+        TermKey::Down => KeyCode::Down,
+        TermKey::Left => KeyCode::Left,
+        TermKey::Right => KeyCode::Right,
+        TermKey::Enter => KeyCode::Return,
+        TermKey::Tab => KeyCode::Tab,
+        TermKey::Char(c) => match c.to_ascii_lowercase() {
+            'a' => KeyCode::A,
+            'b' => KeyCode::B,
+            'c' => KeyCode::C,
+            'd' => KeyCode::D,
+            'e' => KeyCode::E,
+            'f' => KeyCode::F,
+            'g' => KeyCode::G,
+            'h' => KeyCode::H,
+            'i' => KeyCode::I,
+            'j' => KeyCode::J,
+            'k' => KeyCode::K,
+            'l' => KeyCode::L,
+            'm' => KeyCode::M,
+            'n' => KeyCode::N,
+            'o' => KeyCode::O,
+            'p' => KeyCode::P,
+            'q' => KeyCode::Q,
+            'r' => KeyCode::R,
+            's' => KeyCode::S,
+            't' => KeyCode::T,
+            'u' => KeyCode::U,
+            'v' => KeyCode::V,
+            'w' => KeyCode::W,
+            'x' => KeyCode::X,
+            'y' => KeyCode::Y,
+            'z' => KeyCode::Z,
+            '0' => KeyCode::Key0,
+            '1' => KeyCode::Key1,
+            '2' => KeyCode::Key2,
+            '3' => KeyCode::Key3,
+            '4' => KeyCode::Key4,
+            '5' => KeyCode::Key5,
+            '6' => KeyCode::Key6,
+            '7' => KeyCode::Key7,
+            '8' => KeyCode::Key8,
+            '9' => KeyCode::Key9,
+            ' ' => KeyCode::Space,
+            '.' => KeyCode::Period,
+            ',' => KeyCode::Comma,
+            '=' => KeyCode::Equals,
+            '-' => KeyCode::Minus,
+            '/' => KeyCode::Slash,
+            ';' => KeyCode::Semicolon,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Read a key press as one of the game's controls. Escape and Ctrl-C quit
+pub fn read_key(event: KeyEvent, bindings: &BindingSettings) -> Option<TermInput> {
+    match event.code {
+        TermKey::Esc => return Some(TermInput::Quit),
+        TermKey::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+            return Some(TermInput::Quit)
+        }
+        _ => {}
+    }
+    let binding = InputBinding::Key(key_code(event.code)?);
+    let bound = GameControl::all(bindings)
+        .into_iter()
+        .filter(|control| control.bindings(bindings).contains(&binding))
+        .collect::<Vec<_>>();
+    let control = bound
+        .iter()
+        .find(|control| !matches!(control, GameControl::Digit(_)))
+        .copied();
+    let digit = bound.iter().find_map(|control| match control {
+        GameControl::Digit(n) => Some(*n as u32),
+        _ => None,
+    });
+    match (control, digit) {
+        (None, None) => None,
+        (control, digit) => Some(TermInput::Key { control, digit }),
+    }
+}
+
+/// Set the player's actions from what was pressed, as the window's input
+/// systems would. Returns false when it was only part of a count
+pub fn apply_input(input: TermInput, actions: &mut Actions) -> bool {
+    let TermInput::Key { control, digit } = input else {
+        return false;
+    };
+    // Digits go into a count when one is started, before using items
+    if let (Some(count), Some(digit)) = (actions.count, digit) {
+        actions.count = Some((count * 10 + digit).min(MAX_COUNT));
+        return false;
+    }
+    let Some(control) = control else {
+        return false;
+    };
+    match control {
+        GameControl::Up => actions.player_movement = Some(Vec2::Y),
+        GameControl::Down => actions.player_movement = Some(Vec2::NEG_Y),
+        GameControl::Left => actions.player_movement = Some(Vec2::NEG_X),
+        GameControl::Right => actions.player_movement = Some(Vec2::X),
+        GameControl::PickUp => actions.pick_up_item = Some(true),
+        GameControl::Interact => actions.interact = Some(true),
+        GameControl::Explore => actions.explore = Some(true),
+        GameControl::Wait => actions.wait = Some(true),
+        GameControl::Rest => actions.rest = Some(true),
+        GameControl::UseItem(n) => actions.use_item = Some(n),
+        GameControl::Count => {
+            // Pressing it again drops the count
+            actions.count = match actions.count {
+                Some(_) => None,
+                None => Some(0),
+            };
+            return false;
+        }
+        // The terminal shows the map at one size, with nothing to look at
+        GameControl::ZoomIn | GameControl::ZoomOut | GameControl::Look => return false,
+        // Digits only count after a count is started
+        GameControl::Digit(_) => return false,
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Settings;
+    use crossterm::event::KeyEventKind;
+
+    fn press(code: TermKey) -> KeyEvent {
+        KeyEvent {
+            code,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: crossterm::event::KeyEventState::NONE,
+        }
+    }
+
+    #[test]
+    fn test_keys_to_actions() {
+        let settings = Settings::load_profile("development").unwrap();
+        let bindings = &settings.bindings;
+        assert_eq!(
+            read_key(press(TermKey::Char('W')), bindings),
+            Some(TermInput::Key {
+                control: Some(GameControl::Up),
+                digit: None
+            })
+        );
+        assert_eq!(
+            read_key(press(TermKey::Esc), bindings),
+            Some(TermInput::Quit)
+        );
+        assert_eq!(read_key(press(TermKey::Char('`')), bindings), None);
+
+        let mut actions = Actions::default();
+        let count = read_key(press(TermKey::Char('n')), bindings).unwrap();
+        assert!(!apply_input(count, &mut actions));
+        ['2', '0'].iter().for_each(|c| {
+            let digit = read_key(press(TermKey::Char(*c)), bindings).unwrap();
+            assert!(!apply_input(digit, &mut actions));
+        });
+        assert_eq!(actions.count, Some(20));
+        let right = read_key(press(TermKey::Right), bindings).unwrap();
+        assert!(apply_input(right, &mut actions));
+        assert_eq!(actions.player_movement, Some(Vec2::X));
+
+        let mut actions = Actions::default();
+        let use_item = read_key(press(TermKey::Char('1')), bindings).unwrap();
+        assert!(apply_input(use_item, &mut actions));
+        assert_eq!(actions.use_item, Some(1));
+
+        // Count digits follow their bindings, not the character typed
+        let mut settings = settings;
+        settings.bindings.digits[3] = vec![InputBinding::Key(KeyCode::P)];
+        let bindings = &settings.bindings;
+        let mut actions = Actions::default();
+        ['n', 'p'].iter().for_each(|c| {
+            let key = read_key(press(TermKey::Char(*c)), bindings).unwrap();
+            assert!(!apply_input(key, &mut actions));
+        });
+        assert_eq!(actions.count, Some(3));
+        let use_item = read_key(press(TermKey::Char('3')), bindings).unwrap();
+        assert!(apply_input(use_item, &mut actions));
+        assert_eq!(actions.use_item, Some(3));
+    }
+}
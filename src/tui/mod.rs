@@ -0,0 +1,152 @@
+//! Plays the game in a terminal. The rules run headless as they do under the
+//! window, the map and HUD are drawn as text and keys are read into
+//! [`Actions`] with the same bindings.
+
+use std::{
+    collections::VecDeque,
+    io::{self, Stdout, Write},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use bevy::prelude::*;
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyEventKind},
+    execute, queue,
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::{actions::Actions, config::Settings, headless::headless_app, stages::TurnState};
+
+use self::{
+    input::{apply_input, read_key, TermInput},
+    screen::Screen,
+};
+
+mod input;
+mod screen;
+
+/// Time between frames, the turns are taken as fast as the keys come in
+const FRAME: Duration = Duration::from_millis(30);
+
+/// Size of the terminal snapshots are taken at
+const SNAPSHOT_SIZE: (u16, u16) = (80, 24);
+
+/// Frames the first level has to be made in
+const SNAPSHOT_FRAMES: u32 = 10;
+
+/// The first screen of a run as plain text, to check a profile plays
+/// without a terminal, such as in CI. `None` if no level was made
+pub fn snapshot(settings: Settings, seed: u64) -> Option<String> {
+    let mut app = headless_app(settings, seed, None);
+    let (width, height) = SNAPSHOT_SIZE;
+    (0..SNAPSHOT_FRAMES).find(|_| {
+        app.update();
+        Screen::capture(&mut app.world, width, height).is_some()
+    })?;
+    // What the player sees is shown the frame after they are spawned
+    app.update();
+    Screen::capture(&mut app.world, width, height).map(|screen| screen.text())
+}
+
+/// Play a run in the terminal until it is over or the player quits
+pub fn run_tui(settings: Settings) -> io::Result<()> {
+    let seed = settings.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+    });
+    let mut app = headless_app(settings, seed, None);
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+    let result = play(&mut app, &mut stdout);
+    // Put the terminal back even when the game failed
+    execute!(stdout, ResetColor, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn play(app: &mut App, stdout: &mut Stdout) -> io::Result<()> {
+    let mut pending = VecDeque::new();
+    let mut drawn = None;
+    loop {
+        let over = matches!(
+            app.world.resource::<TurnState>(),
+            TurnState::GameOver | TurnState::Victory
+        );
+        while event::poll(Duration::ZERO)? {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match read_key(key, &app.world.resource::<Settings>().bindings) {
+                Some(TermInput::Quit) => return Ok(()),
+                // The last screen stays up until a key is pressed
+                _ if over => return Ok(()),
+                Some(input) => pending.push_back(input),
+                None => {}
+            }
+        }
+        if !over {
+            take_inputs(app, &mut pending);
+            app.update();
+        }
+        let (width, height) = terminal::size()?;
+        if let Some(screen) = Screen::capture(&mut app.world, width, height) {
+            if drawn.as_ref() != Some(&screen) {
+                draw(&screen, stdout)?;
+                drawn = Some(screen);
+            }
+        }
+        thread::sleep(FRAME);
+    }
+}
+
+/// Set the actions for the coming frame. Keys typed during the other
+/// actors' turns wait for the player's, then one action is taken a turn
+fn take_inputs(app: &mut App, pending: &mut VecDeque<TermInput>) {
+    let awaiting = *app.world.resource::<TurnState>() == TurnState::AwaitingInput;
+    let mut actions = app.world.resource_mut::<Actions>();
+    // Nothing is held down in a terminal, only the count lasts between frames
+    *actions = Actions {
+        count: actions.count,
+        ..default()
+    };
+    if !awaiting {
+        return;
+    }
+    while let Some(input) = pending.pop_front() {
+        if apply_input(input, &mut actions) {
+            break;
+        }
+    }
+}
+
+fn draw(screen: &Screen, stdout: &mut Stdout) -> io::Result<()> {
+    let mut color = None;
+    for (row, cells) in screen.map.iter().enumerate() {
+        queue!(stdout, MoveTo(0, row as u16))?;
+        for cell in cells {
+            if color != Some(cell.color) {
+                queue!(stdout, SetForegroundColor(cell.color))?;
+                color = Some(cell.color);
+            }
+            queue!(stdout, Print(cell.glyph))?;
+        }
+    }
+    queue!(stdout, ResetColor)?;
+    for (i, line) in screen.hud.iter().enumerate() {
+        queue!(
+            stdout,
+            MoveTo(0, (screen.map.len() + i) as u16),
+            Print(line),
+            Clear(ClearType::UntilNewLine)
+        )?;
+    }
+    stdout.flush()
+}
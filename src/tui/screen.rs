@@ -0,0 +1,238 @@
+use bevy::{prelude::*, utils::HashMap};
+use crossterm::style::Color;
+
+use crate::{
+    actions::Actions,
+    components::{glyph::Glyph, health::Health, map_position::MapPosition, name::EntityName},
+    entities::{AvailableQuest, Item, MapLevel, Monster, Player, QuestState, Tile, TileType},
+    stages::TurnState,
+    systems::{
+        fov::FieldOfView,
+        inventory::{Carried, PlayerInventory},
+        quest_engine::AssignedQuest,
+        stats::RunStats,
+    },
+};
+
+/// Lines of HUD under the map
+pub const HUD_LINES: u16 = 3;
+
+/// A character on the screen and its colour
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub glyph: char,
+    pub color: Color,
+}
+
+impl Cell {
+    const BLANK: Cell = Cell {
+        glyph: ' ',
+        color: Color::Reset,
+    };
+}
+
+/// The map around the player and the HUD as text, read from the world after
+/// a frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Screen {
+    /// Top row first
+    pub map: Vec<Vec<Cell>>,
+    pub hud: Vec<String>,
+}
+
+impl Screen {
+    /// The screen for a terminal `width` by `height`, `None` while there is
+    /// no player, such as before the first level is made
+    pub fn capture(world: &mut World, width: u16, height: u16) -> Option<Self> {
+        let (player, centre, view) = world
+            .query_filtered::<(Entity, &MapPosition, &FieldOfView), With<Player>>()
+            .get_single(world)
+            .map(|(e, p, fov)| (e, *p, fov.visible_positions.clone()))
+            .ok()?;
+
+        let tiles = world
+            .query_filtered::<(&MapPosition, &TileType, &Visibility), With<Tile>>()
+            .iter(world)
+            .filter(|(_, _, seen)| seen.is_visible)
+            .map(|(p, tile_type, _)| {
+                let color = match view.contains(p) {
+                    true => tile_color(tile_type.appearance()),
+                    false => Color::DarkGrey,
+                };
+                let cell = Cell {
+                    glyph: tile_type.appearance().glyph(),
+                    color,
+                };
+                (*p, cell)
+            })
+            .collect::<HashMap<_, _>>();
+        let mut entities = world
+            .query_filtered::<(
+                &MapPosition,
+                &Glyph,
+                &Visibility,
+                Option<&Player>,
+                Option<&Monster>,
+                Option<&AvailableQuest>,
+                Option<&Item>,
+            ), Without<Tile>>()
+            .iter(world)
+            .filter(|(p, _, seen, ..)| seen.is_visible && view.contains(p))
+            .map(|(p, glyph, _, player, monster, quest, item)| {
+                // Actors are drawn over items, and the player over everything
+                let (layer, color) = match (player, monster, quest, item) {
+                    (Some(_), ..) => (3, Color::Yellow),
+                    (_, Some(_), ..) => (2, Color::Red),
+                    (_, _, Some(_), _) => (2, Color::Green),
+                    (.., Some(_)) => (1, Color::Cyan),
+                    _ => (0, Color::DarkYellow),
+                };
+                let cell = Cell {
+                    glyph: glyph_char(glyph.0),
+                    color,
+                };
+                (layer, *p, cell)
+            })
+            .collect::<Vec<_>>();
+        entities.sort_by_key(|(layer, ..)| *layer);
+        let mut cells = tiles;
+        entities.into_iter().for_each(|(_, p, cell)| {
+            cells.insert(p, cell);
+        });
+
+        let rows = height.saturating_sub(HUD_LINES) as i32;
+        let columns = width as i32;
+        let top_left = centre.position + IVec2::new(-columns / 2, rows / 2);
+        let map = (0..rows)
+            .map(|row| {
+                (0..columns)
+                    .map(|column| {
+                        let p = MapPosition::from_ivec2(top_left + IVec2::new(column, -row));
+                        cells.get(&p).copied().unwrap_or(Cell::BLANK)
+                    })
+                    .collect()
+            })
+            .collect();
+        let hud = hud_lines(world, player);
+        Some(Self { map, hud })
+    }
+
+    /// Without the colours, as the lines would be printed
+    pub fn text(&self) -> String {
+        self.map
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.glyph)
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .chain(self.hud.iter().cloned())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// The dungeon font puts each character at its code, anything past plain
+/// ascii has no character to draw
+fn glyph_char(index: usize) -> char {
+    match u8::try_from(index) {
+        Ok(code) if code.is_ascii_graphic() => code as char,
+        _ => '?',
+    }
+}
+
+fn tile_color(tile: TileType) -> Color {
+    match tile {
+        TileType::Wall => Color::Grey,
+        TileType::Exit => Color::Magenta,
+        TileType::DoorClosed | TileType::DoorOpen => Color::DarkYellow,
+        TileType::Spikes => Color::Red,
+        _ => Color::White,
+    }
+}
+
+/// Health, level and turn, the inventory, then the quests
+fn hud_lines(world: &mut World, player: Entity) -> Vec<String> {
+    let level = world.get::<MapLevel>(player).map_or(0, |l| l.value);
+    let health = world
+        .get::<Health>(player)
+        .map(|h| format!("{}/{}", h.current, h.max))
+        .unwrap_or_default();
+    let turns = world.resource::<RunStats>().turns;
+    let mut status = format!("Level {}  Health {}  Turn {}", level, health, turns);
+    if let Some(count) = world.resource::<Actions>().count {
+        status += &format!("  Count {}", count);
+    }
+    match *world.resource::<TurnState>() {
+        TurnState::GameOver => status += "  You lost, press any key",
+        TurnState::Victory => status += "  You won! Press any key",
+        _ => {}
+    }
+
+    let carried = world
+        .query::<&PlayerInventory>()
+        .get_single(world)
+        .map(|inventory| inventory.key_map.clone())
+        .unwrap_or_default();
+    let items = carried
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| {
+            world.get::<Carried>(*item)?;
+            let name = world.get::<EntityName>(*item)?;
+            Some(format!("{}: {}", i, name))
+        })
+        .collect::<Vec<_>>();
+
+    let quests = world
+        .query::<(&EntityName, &QuestState, &AssignedQuest)>()
+        .iter(world)
+        .filter(|(.., assigned)| assigned.assignee == player)
+        .map(|(name, state, _)| format!("{} ({:?})", name, state))
+        .collect::<Vec<_>>();
+
+    vec![
+        status,
+        format!("Items: {}", items.join(", ")),
+        format!("Quests: {}", quests.join(", ")),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::Harness;
+
+    #[test]
+    fn test_capture_screen() {
+        let mut harness = Harness::new(
+            "\
+#####
+#@..#
+#...#
+#####",
+        );
+        // What the player sees is shown the frame after they are spawned
+        harness.app.update();
+        let screen = Screen::capture(&mut harness.app.world, 5, 4 + HUD_LINES).unwrap();
+        let text = screen.text();
+        // Up is the top of the screen as in the window, with the player in
+        // the middle
+        assert_eq!(
+            text.lines().collect::<Vec<_>>(),
+            vec![
+                " ####",
+                " #...",
+                " #@..",
+                " ####",
+                "Level 0  Health 10/10  Turn 0",
+                "Items: ",
+                "Quests: ",
+            ]
+        );
+        assert_eq!(glyph_char(64), '@');
+        assert_eq!(glyph_char(200), '?');
+    }
+}